@@ -16,6 +16,7 @@ local hl_rules = {
   { pattern = '^\\ ',                   group = 'DiagnosticWarn', sigil = 2 },
   { pattern = '^%+ ',                   group = 'DiagnosticOk', sigil = 2 },
   { pattern = '^%- ',                   group = 'DiagnosticError', sigil = 2 },
+  { pattern = '^@ ',                    group = 'Constant', sigil = 2 },        -- event
 }
 
 local lang_aliases = {
@@ -0,0 +1,142 @@
+/// A kind of structured token: `contact:`, `link:` or `book:`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Contact,
+    Link,
+    Book,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 3] = [Kind::Contact, Kind::Link, Kind::Book];
+
+    /// The token prefix, e.g. `link:`.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Kind::Contact => "contact:",
+            Kind::Link => "link:",
+            Kind::Book => "book:",
+        }
+    }
+}
+
+/// A token found in a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub kind: Kind,
+    pub value: String,
+    /// Byte range of the whole token, prefix included
+    pub span: std::ops::Range<usize>,
+}
+
+/// The value following a prefix: a `"quoted string"` (for titles with
+/// spaces), or everything up to the next whitespace minus trailing
+/// punctuation. Returns the value and its length in `rest`.
+fn token_value(rest: &str) -> Option<(String, usize)> {
+    if let Some(quoted) = rest.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        let value = quoted[..end].trim();
+        return (!value.is_empty()).then(|| (value.to_string(), end + 2));
+    }
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let value = rest[..end].trim_end_matches([',', '.', ';', ':', ')', '!', '?']);
+    (!value.is_empty()).then(|| (value.to_string(), value.len()))
+}
+
+/// The length of a bare `http://` or `https://` URL at the start of `rest`,
+/// up to the next whitespace minus trailing punctuation, quotes, and
+/// closing brackets.
+fn bare_url_len(rest: &str) -> Option<usize> {
+    let scheme = ["http://", "https://"].into_iter().find(|s| {
+        rest.get(..s.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(s))
+    })?;
+    let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    let url =
+        rest[..end].trim_end_matches([',', '.', ';', ':', ')', '!', '?', '>', ']', '"', '\'']);
+    (url.len() > scheme.len()).then_some(url.len())
+}
+
+/// `contact:`, `link:` and `book:` tokens in `line`, in order of appearance.
+///
+/// Like tags, a token starts at the beginning of the line or after
+/// whitespace, and prefixes match case-insensitively:
+/// `contact:alice`, `link:https://example.com`, `book:"The Dispossessed"`.
+/// Bare `http(s)://` URLs count as links too, and may also follow `(` or
+/// `<`, as in Markdown links.
+pub fn extract(line: &str) -> Vec<Annotation> {
+    let mut found = Vec::new();
+    let mut prev_is_space = true;
+    let mut prev_is_open = false;
+    let mut skip_to = 0;
+    for (i, c) in line.char_indices() {
+        if i >= skip_to
+            && (prev_is_space || prev_is_open)
+            && let Some(len) = bare_url_len(&line[i..])
+        {
+            found.push(Annotation {
+                kind: Kind::Link,
+                value: line[i..i + len].to_string(),
+                span: i..i + len,
+            });
+            skip_to = i + len;
+        } else if i >= skip_to && prev_is_space {
+            let rest = &line[i..];
+            for kind in Kind::ALL {
+                let prefix = kind.prefix();
+                let matches = rest
+                    .get(..prefix.len())
+                    .is_some_and(|p| p.eq_ignore_ascii_case(prefix));
+                if !matches {
+                    continue;
+                }
+                if let Some((value, len)) = token_value(&rest[prefix.len()..]) {
+                    let end = i + prefix.len() + len;
+                    found.push(Annotation {
+                        kind,
+                        value,
+                        span: i..end,
+                    });
+                    skip_to = end;
+                }
+                break;
+            }
+        }
+        prev_is_space = c.is_whitespace();
+        prev_is_open = matches!(c, '(' | '<');
+    }
+    found
+}
+
+/// `line` with its annotation tokens removed, for showing as context. A URL
+/// written as `<URL>`, `(URL)` or `[text](URL)` takes its brackets with it,
+/// leaving the link text.
+pub fn context(line: &str, annotations: &[Annotation]) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for a in annotations {
+        let mut before = &line[last..a.span.start];
+        last = a.span.end;
+        let after = &line[last..];
+        if let Some(open) = before.strip_suffix("](")
+            && after.starts_with(')')
+            && let Some(bracket) = open.rfind('[')
+        {
+            out.push_str(&open[..bracket]);
+            before = &open[bracket + 1..];
+            last += 1;
+        } else if let Some(open) = before.strip_suffix('<')
+            && after.starts_with('>')
+        {
+            before = open;
+            last += 1;
+        } else if let Some(open) = before.strip_suffix('(')
+            && after.starts_with(')')
+        {
+            before = open;
+            last += 1;
+        }
+        out.push_str(before);
+    }
+    out.push_str(&line[last..]);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,169 @@
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Subdirectory of the plan directory that holds attached files. Scanning
+/// only looks at top-level files, so nothing in here is mistaken for a plan
+/// file or reported as unexpected.
+pub const ASSETS_DIR: &str = "assets";
+
+/// `name` with anything but letters, digits, `.`, `-` and `_` replaced by
+/// `-`, so the reference needs no escaping.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Copy `source` into the assets directory as `DATE-NAME`, adding `-2`,
+/// `-3`, ... before the extension if that name is taken. Returns the copy's
+/// path relative to `plan_dir`, e.g. `assets/2026-02-19-report.pdf`.
+pub fn copy_in(plan_dir: &Path, source: &Path, date: NaiveDate) -> io::Result<String> {
+    let name = source
+        .file_name()
+        .map(|n| sanitize(&n.to_string_lossy()))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a file", source.display()),
+            )
+        })?;
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (name.clone(), String::new()),
+    };
+    let assets = plan_dir.join(ASSETS_DIR);
+    fs::create_dir_all(&assets)?;
+    let prefix = date.format("%Y-%m-%d");
+    let mut n = 1;
+    loop {
+        let candidate = if n == 1 {
+            format!("{}-{}{}", prefix, stem, ext)
+        } else {
+            format!("{}-{}-{}{}", prefix, stem, n, ext)
+        };
+        // create_new, so two attaches at once can't pick the same name
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(assets.join(&candidate))
+        {
+            Ok(mut dest) => {
+                io::copy(&mut fs::File::open(source)?, &mut dest)?;
+                return Ok(format!("{}/{}", ASSETS_DIR, candidate));
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The inbox line for an attachment: a Markdown link named after the
+/// original file.
+pub fn reference(source: &Path, relative: &str) -> String {
+    let label = source
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| relative.to_string());
+    format!("[{}]({})", label, relative)
+}
+
+/// Files in the assets directory that none of `contents` mention, as paths
+/// relative to `plan_dir`, sorted.
+pub fn orphans<'a>(
+    plan_dir: &Path,
+    contents: impl IntoIterator<Item = &'a str>,
+) -> io::Result<Vec<String>> {
+    let assets = plan_dir.join(ASSETS_DIR);
+    if !assets.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut unreferenced = Vec::new();
+    for entry in fs::read_dir(&assets)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            unreferenced.push(format!(
+                "{}/{}",
+                ASSETS_DIR,
+                entry.file_name().to_string_lossy()
+            ));
+        }
+    }
+    for content in contents {
+        unreferenced.retain(|asset| !content.contains(asset.as_str()));
+    }
+    unreferenced.sort();
+    Ok(unreferenced)
+}
+
+/// The assets `content` links to, in order of first mention, as paths
+/// relative to the plan directory.
+pub fn referenced(content: &str) -> Vec<String> {
+    let marker = format!("]({}/", ASSETS_DIR);
+    let mut found: Vec<String> = Vec::new();
+    let mut rest = content;
+    while let Some(at) = rest.find(&marker) {
+        let link = &rest[at + 2..];
+        let Some(end) = link.find(')') else { break };
+        if !found.iter().any(|f| f == &link[..end]) {
+            found.push(link[..end].to_string());
+        }
+        rest = &link[end..];
+    }
+    found
+}
+
+/// A broad kind for an asset, from its extension: image, video, audio,
+/// document, text, archive, or file.
+pub fn kind(asset: &str) -> &'static str {
+    let ext = asset
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "heic" | "bmp" | "tiff" => "image",
+        "mp4" | "mov" | "mkv" | "webm" | "avi" => "video",
+        "mp3" | "m4a" | "wav" | "flac" | "ogg" | "opus" => "audio",
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "xls" | "xlsx" | "ods" | "ppt" | "pptx"
+        | "odp" | "epub" => "document",
+        "txt" | "md" | "csv" | "json" | "html" | "xml" | "log" => "text",
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "7z" => "archive",
+        _ => "file",
+    }
+}
+
+/// Pick one of `assets` by its 1-based number or by a case-insensitive
+/// piece of its name. The error says why nothing, or more than one, fits.
+pub fn pick<'a>(assets: &'a [String], query: &str) -> Result<&'a str, String> {
+    if let Ok(n) = query.parse::<usize>() {
+        return n
+            .checked_sub(1)
+            .and_then(|i| assets.get(i))
+            .map(String::as_str)
+            .ok_or_else(|| format!("No attachment {}; the day has {}.", n, assets.len()));
+    }
+    let needle = query.to_lowercase();
+    let matches: Vec<&String> = assets
+        .iter()
+        .filter(|a| {
+            let name = a.rsplit('/').next().unwrap_or(a);
+            name.to_lowercase().contains(&needle)
+        })
+        .collect();
+    match matches.as_slice() {
+        [one] => Ok(one.as_str()),
+        [] => Err(format!("No attachment matches '{}'.", query)),
+        many => Err(format!(
+            "'{}' matches {} attachments; use a number from 'plan attach ls'.",
+            query,
+            many.len()
+        )),
+    }
+}
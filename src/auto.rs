@@ -0,0 +1,129 @@
+use crate::config::LockConfig;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const APPLIED_MARKER: &str = "auto";
+
+/// Commands an auto rule may run. Only capture commands, so a rule can never
+/// rewrite or delete anything.
+pub const ALLOWED_COMMANDS: &[&str] = &["log", "jot", "event"];
+
+/// A scheduled capture from the `[auto]` config section:
+/// `daily_standup = "09:25 mon-fri log 'standup'"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoRule {
+    pub name: String,
+    pub time: NaiveTime,
+    /// Indexed by days from Monday
+    pub days: [bool; 7],
+    /// Arguments to `plan`, starting with the command
+    pub args: Vec<String>,
+}
+
+/// Parse `mon` or `monday` (any case) into days from Monday.
+fn parse_day(s: &str) -> Option<usize> {
+    s.trim()
+        .parse::<Weekday>()
+        .ok()
+        .map(|d| d.num_days_from_monday() as usize)
+}
+
+/// Parse a day spec: `*` for every day, or a comma list of days and ranges
+/// (`mon-fri`, `sat,sun`).
+fn parse_days(spec: &str) -> Option<[bool; 7]> {
+    if spec == "*" {
+        return Some([true; 7]);
+    }
+    let mut days = [false; 7];
+    for part in spec.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (parse_day(from)?, parse_day(to)?);
+                // Ranges may wrap past Sunday, e.g. fri-mon
+                let mut d = from;
+                loop {
+                    days[d] = true;
+                    if d == to {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days[parse_day(part)?] = true,
+        }
+    }
+    Some(days)
+}
+
+/// Parse a rule value: `HH:MM DAYS COMMAND [ARGS...]`, with shell quoting.
+pub fn parse_rule(name: &str, spec: &str) -> crate::Result<AutoRule> {
+    parse_rule_spec(name, spec).map_err(crate::Error::ParseError)
+}
+
+fn parse_rule_spec(name: &str, spec: &str) -> Result<AutoRule, String> {
+    let words = shlex::split(spec).ok_or_else(|| format!("unbalanced quotes in '{}'", spec))?;
+    let [time, days, args @ ..] = words.as_slice() else {
+        return Err("expected 'HH:MM DAYS COMMAND [ARGS...]'".to_string());
+    };
+    let time = NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| format!("invalid time '{}'; use HH:MM", time))?;
+    let days = parse_days(days)
+        .ok_or_else(|| format!("invalid days '{}'; use * or e.g. mon-fri,sun", days))?;
+    match args.first() {
+        Some(cmd) if ALLOWED_COMMANDS.contains(&cmd.as_str()) => {}
+        Some(cmd) => {
+            return Err(format!(
+                "command '{}' is not allowed; use one of {}",
+                cmd,
+                ALLOWED_COMMANDS.join(", ")
+            ));
+        }
+        None => return Err("missing command".to_string()),
+    }
+    Ok(AutoRule {
+        name: name.to_string(),
+        time,
+        days,
+        args: args.to_vec(),
+    })
+}
+
+impl AutoRule {
+    /// Whether the rule should have fired by `now` on the current day.
+    pub fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.days[now.weekday().num_days_from_monday() as usize] && now.time() >= self.time
+    }
+}
+
+/// Whether rule `name` already fired on `date`, according to the state directory.
+pub fn was_applied(state_dir: &Path, date: NaiveDate, name: &str) -> io::Result<bool> {
+    let key = format!("{} {}", date.format("%Y-%m-%d"), name);
+    match fs::read_to_string(state_dir.join(APPLIED_MARKER)) {
+        Ok(content) => Ok(content.lines().any(|l| l == key)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Record that rule `name` fired on `date`. Entries from earlier days are
+/// dropped so the marker doesn't grow without bound.
+pub fn mark_applied(state_dir: &Path, date: NaiveDate, name: &str) -> io::Result<()> {
+    crate::migrate::ensure_current(state_dir)?;
+    let marker = state_dir.join(APPLIED_MARKER);
+    let _lock = crate::file::acquire_lock(&marker, &LockConfig::default())?;
+    let prefix = format!("{} ", date.format("%Y-%m-%d"));
+    let existing = match fs::read_to_string(&marker) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut content: String = existing
+        .lines()
+        .filter(|l| l.starts_with(&prefix) && l[prefix.len()..] != *name)
+        .map(|l| format!("{}\n", l))
+        .collect();
+    content.push_str(&format!("{}{}\n", prefix, name));
+    Ok(crate::file::write_atomic(&marker, &content)?)
+}
@@ -0,0 +1,139 @@
+use crate::config::BackupsConfig;
+use crate::layout::Layout;
+use chrono::{NaiveDateTime, Timelike};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where older versions kept snapshots, inside the plan directory itself.
+/// Any found there are moved to [`crate::paths::backups_dir`].
+const LEGACY_DIR: &str = ".backups";
+
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+/// One snapshot of a plan file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// When the snapshot was taken
+    pub taken: NaiveDateTime,
+    /// Orders snapshots taken within the same second
+    seq: u32,
+}
+
+/// Parse `2026-02-19.plan.20260219T101500` (or `...T101500-2` for a second
+/// snapshot in the same second) into its time and sequence number.
+fn parse_name(name: &str, file_name: &str) -> Option<(NaiveDateTime, u32)> {
+    let rest = name.strip_prefix(file_name)?.strip_prefix('.')?;
+    let (stamp, seq) = match rest.split_once('-') {
+        Some((stamp, seq)) => (stamp, seq.parse().ok()?),
+        None => (rest, 1),
+    };
+    let taken = NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).ok()?;
+    Some((taken, seq))
+}
+
+/// Move snapshots out of the plan directory, so they no longer travel with
+/// it when it is synced.
+fn adopt_legacy(dir: &Path) -> crate::Result<()> {
+    let legacy = dir.join(LEGACY_DIR);
+    let entries = match fs::read_dir(&legacy) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+    let backup_dir = crate::paths::backups_dir(dir);
+    fs::create_dir_all(&backup_dir)?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let target = backup_dir.join(entry.file_name());
+        // rename fails across filesystems; copy instead
+        if fs::rename(entry.path(), &target).is_err() {
+            fs::copy(entry.path(), &target)?;
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(fs::remove_dir(&legacy)?)
+}
+
+/// Snapshots of the plan file `file_name` in `dir`, newest first.
+pub fn list(dir: &Path, file_name: &str) -> crate::Result<Vec<Backup>> {
+    adopt_legacy(dir)?;
+    let backup_dir = crate::paths::backups_dir(dir);
+    let entries = match fs::read_dir(&backup_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let (taken, seq) = parse_name(&name, file_name)?;
+            Some(Backup {
+                path: e.path(),
+                taken,
+                seq,
+            })
+        })
+        .collect();
+    backups.sort_by(|a, b| (b.taken, b.seq).cmp(&(a.taken, a.seq)));
+    Ok(backups)
+}
+
+/// Names of every plan file in `dir` that has snapshots, sorted.
+pub fn backed_up_files(layout: &Layout, dir: &Path) -> crate::Result<Vec<String>> {
+    adopt_legacy(dir)?;
+    let entries = match fs::read_dir(crate::paths::backups_dir(dir)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let (file_name, _) = name.rsplit_once('.')?;
+            crate::file::is_plan_file(layout, file_name).then(|| file_name.to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Copy the plan file at `path` into its directory's backups before it is
+/// changed, then drop the oldest snapshots past `backups.keep`. Does nothing
+/// when backups are off or the file doesn't exist yet. Whatever writes a plan
+/// file calls this first; [`crate::file::write_atomic`] doesn't.
+pub fn snapshot(path: &Path, backups: &BackupsConfig) -> crate::Result<Option<PathBuf>> {
+    let keep = backups.keep;
+    let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Ok(None);
+    };
+    let file_name = file_name.to_string_lossy();
+    if keep == 0 || !path.is_file() {
+        return Ok(None);
+    }
+
+    let backup_dir = crate::paths::backups_dir(dir);
+    fs::create_dir_all(&backup_dir)?;
+    let now = crate::date::now().with_nanosecond(0).unwrap_or_default();
+    let stamp = now.format(STAMP_FORMAT).to_string();
+    let seq = list(dir, &file_name)?
+        .iter()
+        .filter(|b| b.taken == now)
+        .map(|b| b.seq)
+        .max()
+        .map_or(1, |s| s + 1);
+    let target = if seq == 1 {
+        backup_dir.join(format!("{}.{}", file_name, stamp))
+    } else {
+        backup_dir.join(format!("{}.{}-{}", file_name, stamp, seq))
+    };
+    fs::copy(path, &target)?;
+
+    for old in list(dir, &file_name)?.into_iter().skip(keep) {
+        fs::remove_file(old.path)?;
+    }
+    Ok(Some(target))
+}
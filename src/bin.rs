@@ -1,13 +1,53 @@
 use anyhow::{Context, Result, bail};
+use plan::annotations;
+use plan::attach;
+use plan::auto;
+use plan::backup;
+use plan::color::Role;
 use plan::config;
+use plan::conflict;
 use plan::date;
+use plan::diag::{self, Warning};
+use plan::export;
 use plan::file;
+use plan::history;
+use plan::hooks;
+use plan::ics;
+use plan::index;
+use plan::layout::Layout;
+use plan::lint;
+use plan::migrate;
+use plan::nag;
+use plan::names;
+use plan::opener;
+use plan::paths;
+use plan::plugin;
+use plan::porcelain;
+use plan::rollup;
+use plan::schema;
+use plan::scratch;
+use plan::search;
+use plan::section;
+use plan::selftest;
+use plan::standup;
+use plan::stats;
+use plan::sync;
+use plan::tags;
+use plan::task;
+use plan::template;
+use plan::todotxt;
+use plan::undo;
 
-use clap::{Parser, Subcommand};
+use chrono::Datelike;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
+use std::ffi::OsString;
 use std::fs;
-use std::io;
-use std::path::Path;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::process::Command as ProcessCommand;
 
 #[derive(Debug)]
@@ -34,6 +74,85 @@ fn silent_exit(code: i32) -> anyhow::Error {
     anyhow::Error::new(PlanError::SilentExit(code))
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ExportFormat {
+    Markdown,
+    Json,
+    Csv,
+    /// Open tasks only, one todo.txt line each
+    #[value(name = "todotxt", alias = "todo.txt")]
+    TodoTxt,
+    /// iCalendar: tasks with a due:YYYY-MM-DD token and events
+    Ics,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LsGroup {
+    /// ISO weeks, Monday through Sunday
+    Week,
+    Month,
+}
+
+impl LsGroup {
+    /// Label of the group `naive_date` falls in, e.g. `2026-W08` or `2026-02`.
+    fn label(self, naive_date: chrono::NaiveDate) -> String {
+        match self {
+            LsGroup::Week => naive_date.format("%G-W%V").to_string(),
+            LsGroup::Month => naive_date.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ImportFormat {
+    #[value(name = "todo.txt", alias = "todotxt")]
+    TodoTxt,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PorcelainVersion {
+    V1,
+    V2,
+}
+
+impl PorcelainVersion {
+    fn version(self) -> porcelain::Version {
+        match self {
+            PorcelainVersion::V1 => porcelain::Version::V1,
+            PorcelainVersion::V2 => porcelain::Version::V2,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum WarningsFormat {
+    Text,
+    Json,
+}
+
+impl WarningsFormat {
+    fn name(self) -> &'static str {
+        match self {
+            WarningsFormat::Text => "text",
+            WarningsFormat::Json => "json",
+        }
+    }
+
+    fn format(self) -> diag::Format {
+        match self {
+            WarningsFormat::Text => diag::Format::Text,
+            WarningsFormat::Json => diag::Format::Json,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about = "A standalone tool for writing and managing daily plan files.", long_about = None)]
 struct Cli {
@@ -53,10 +172,44 @@ struct Cli {
     #[arg(long)]
     path: bool,
 
+    /// Open the editor with the cursor inside the named section (e.g. inbox)
+    #[arg(long, value_name = "NAME")]
+    section: Option<String>,
+
     /// Open the most recent plan file chronologically
     #[arg(long, global = true)]
     last: bool,
 
+    /// When to color output: auto (terminals only), always, or never
+    #[arg(long, global = true, value_name = "WHEN", default_value = "auto")]
+    color: ColorWhen,
+
+    /// How to print warnings: text, or json (one object per line)
+    #[arg(long, global = true, value_name = "FORMAT", default_value = "text")]
+    warnings: WarningsFormat,
+
+    /// Reopen the most recently opened plan file
+    #[arg(long)]
+    back: bool,
+
+    /// Create plan files even when the system clock seems to have jumped
+    /// back, or, for 'import', files for past days
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Fail right away instead of waiting when another process has a plan file locked
+    #[arg(long, global = true)]
+    no_wait: bool,
+
+    /// Skip features that need the network (such as 'nag --email') instead
+    /// of waiting on it; same as offline = true in the config
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Resolve relative dates against this date instead of today
+    #[arg(long, global = true, value_name = "DATE")]
+    from: Option<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -65,33 +218,728 @@ struct Cli {
 enum Commands {
     /// Insert '* <text>' into today's inbox (reads stdin if '-')
     Log {
-        text: String,
+        #[arg(required_unless_present = "path")]
+        text: Option<String>,
         /// Relative date: @~N, today, yesterday, "N days ago"
         #[arg(name = "DATE")]
         date: Option<String>,
+        /// Append to this section instead of the inbox (created if missing)
+        #[arg(long, value_name = "NAME")]
+        section: Option<String>,
+        /// Skip (exit 3) if the same entry is already in the target section
+        #[arg(long)]
+        once: bool,
+        /// Task priority: a letter (A is most urgent) or high, medium, low
+        #[arg(short, long, value_name = "PRIORITY")]
+        priority: Option<String>,
+        /// With '-', log each non-empty line of stdin as its own task, in
+        /// one write
+        #[arg(long)]
+        batch: bool,
+        /// Print the file this would write to without touching it; a lone
+        /// TEXT is taken as the date
+        #[arg(long)]
+        path: bool,
+        #[command(flatten)]
+        stamp: TimestampArgs,
     },
-    /// Insert raw note into today's inbox (reads stdin if '-')
+    /// Insert raw note into today's inbox ('-' reads all of stdin as one
+    /// block, keeping its lines and indentation)
     Jot {
-        text: String,
+        #[arg(required_unless_present_any = ["clipboard", "path"])]
+        text: Option<String>,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Append to this section instead of the inbox (created if missing)
+        #[arg(long, value_name = "NAME")]
+        section: Option<String>,
+        /// Skip (exit 3) if the same entry is already in the target section
+        #[arg(long)]
+        once: bool,
+        /// Jot the clipboard's text, fenced with ``` when it spans lines.
+        /// Give a date as 'plan DATE jot --clipboard'
+        #[arg(long, conflicts_with = "text")]
+        clipboard: bool,
+        /// Print the file this would write to without touching it; a lone
+        /// TEXT is taken as the date
+        #[arg(long)]
+        path: bool,
+        #[command(flatten)]
+        stamp: TimestampArgs,
+    },
+    /// Insert '@ [HH:MM] <text>' into today's inbox (reads stdin if '-')
+    Event {
+        #[arg(required_unless_present = "path")]
+        text: Option<String>,
         /// Relative date: @~N, today, yesterday, "N days ago"
         #[arg(name = "DATE")]
         date: Option<String>,
+        /// Time of day the event happens (HH:MM)
+        #[arg(long, value_name = "HH:MM")]
+        at: Option<String>,
+        /// Append to this section instead of the inbox (created if missing)
+        #[arg(long, value_name = "NAME")]
+        section: Option<String>,
+        /// Skip (exit 3) if the same entry is already in the target section
+        #[arg(long)]
+        once: bool,
+        /// Print the file this would write to without touching it; a lone
+        /// TEXT is taken as the date
+        #[arg(long)]
+        path: bool,
     },
     /// List recent plan files with dates and line counts
-    Ls,
+    Ls {
+        /// Show at most N days (default: ls.limit from the config, or 30)
+        #[arg(short = 'n', long, value_name = "N", conflicts_with = "all")]
+        limit: Option<usize>,
+        /// Show every day
+        #[arg(short, long)]
+        all: bool,
+        /// Only show days on or after DATE; without --limit, shows all of them
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        /// Comma-separated columns (date, weekday, lines, open, done, files),
+        /// or a template such as '{date}\t{tasks_open}' with raw values
+        #[arg(long, value_name = "FORMAT")]
+        format: Option<String>,
+        /// Print JSON (see 'plan schema ls')
+        #[arg(long, conflicts_with = "format")]
+        json: bool,
+        /// Print stable tab-separated records: date, weekday, lines, open,
+        /// done, files; v2 adds the directory (empty for the plan directory)
+        #[arg(long, value_name = "VERSION", num_args = 0..=1, require_equals = true,
+              default_missing_value = "v1", conflicts_with_all = ["format", "json"])]
+        porcelain: Option<PorcelainVersion>,
+        /// Follow each week's or month's days with a subtotal line
+        #[arg(long, value_enum, value_name = "PERIOD",
+              conflicts_with_all = ["json", "porcelain"])]
+        group_by: Option<LsGroup>,
+    },
     /// Print a plan file to stdout (exit code 2 if not found)
     Show {
         /// Relative date: @~N, today, yesterday, "N days ago"
         #[arg(name = "DATE")]
         date: Option<String>,
+        /// End with a line of word count, reading time, and task figures
+        #[arg(long)]
+        stats: bool,
+        /// Prefix each task with its number, for 'plan check'
+        #[arg(short = 'n', long = "number")]
+        numbered: bool,
+        /// Print only the lines inside this section, e.g. inbox
+        #[arg(long, value_name = "NAME")]
+        section: Option<String>,
+        /// Render for reading on a terminal: boxed sections, an underlined
+        /// header and glyphs for task sigils. Piped output stays raw
+        #[arg(long, conflicts_with_all = ["numbered", "section"])]
+        pretty: bool,
+        /// Print the paths of the day's files instead of their contents
+        #[arg(long, conflicts_with_all = ["stats", "numbered", "section", "pretty"])]
+        path: bool,
+    },
+    /// Print the inbox of a day, or of every day from --since on
+    Inbox {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Start of a range of days ending at DATE
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        /// Print only how many items are waiting
+        #[arg(short, long)]
+        count: bool,
+    },
+    /// Empty a day's inbox into a section, or below the '---' separator
+    Sweep {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Section to move the items into (created if missing)
+        #[arg(long, value_name = "NAME")]
+        to: Option<String>,
+    },
+    /// Mark a day's Nth task done without opening the editor (see
+    /// 'plan show --number')
+    Check {
+        /// Task number, counting every task of the day from 1
+        #[arg(name = "N")]
+        number: usize,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Reopen a day's Nth task after 'plan check'
+    Uncheck {
+        /// Task number, counting every task of the day from 1
+        #[arg(name = "N")]
+        number: usize,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
     },
-    /// Search across all plan files (substring match, case-insensitive)
+    /// Print a day's plan files as JSON: sections, tasks, and events (see
+    /// 'plan schema parse')
+    Parse {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Start a continuation file (e.g. 2026-02-19.2.plan) for a day that has
+    /// grown too large, and open it; new entries for the day go there
+    Continue {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Export plan files as one Markdown document, a JSON array, a CSV of
+    /// tasks, open tasks in todo.txt format, or an iCalendar file
+    Export {
+        /// Output format
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: ExportFormat,
+        /// Dates or ranges (e.g. "@~90..today"); every plan file when omitted
+        #[arg(name = "DATE")]
+        dates: Vec<String>,
+    },
+    /// Add tasks from another tool's file to the inbox of the day each was created
+    Import {
+        /// Format of FILE
+        #[arg(value_enum)]
+        format: ImportFormat,
+        /// File to import ('-' for stdin)
+        file: String,
+    },
+    /// Print several plan files in chronological order with separators
+    Cat {
+        /// Dates or ranges (e.g. "@~4..today"); missing days are skipped
+        #[arg(name = "DATE", required = true)]
+        dates: Vec<String>,
+    },
+    /// Copy a file into the plan directory's assets/ folder and link it from
+    /// a day's inbox
+    #[command(args_conflicts_with_subcommands = true)]
+    Attach {
+        #[command(subcommand)]
+        action: Option<AttachAction>,
+        /// File to attach
+        #[arg(required_unless_present = "gc")]
+        file: Option<PathBuf>,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Delete assets nothing links to instead: no dated or scratch
+        /// file, recurring entry, or template
+        #[arg(long, conflicts_with_all = ["file", "DATE"])]
+        gc: bool,
+        /// With --gc, list what would be deleted without deleting it
+        #[arg(long, requires = "gc")]
+        dry_run: bool,
+    },
+    /// Print the plan files from this calendar date in earlier years
+    #[command(name = "onthisday")]
+    OnThisDay {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Also print the file from N months before the date
+        #[arg(long, value_name = "N")]
+        months: Option<u32>,
+    },
+    /// Check plan files for structural problems (missing or duplicate inbox,
+    /// malformed tilde lines, headers that don't match the file name) and
+    /// exit 1 if any are found
+    Lint {
+        /// Dates or ranges (e.g. "@~4..today") to check (default: every plan file)
+        #[arg(name = "DATE")]
+        dates: Vec<String>,
+    },
+    /// List open tasks across all plan files, most urgent first
+    Tasks {
+        /// Print JSON (see 'plan schema tasks')
+        #[arg(long)]
+        json: bool,
+        /// Prefix each task with its number within its day, for 'plan check'
+        #[arg(short = 'n', long = "number")]
+        numbered: bool,
+        /// Print stable tab-separated records: file, line, number, state,
+        /// priority, text
+        #[arg(long, value_name = "VERSION", num_args = 0..=1, require_equals = true,
+              default_missing_value = "v1", conflicts_with_all = ["json", "numbered"])]
+        porcelain: Option<PorcelainVersion>,
+    },
+    /// Search across all plan files (substring match; case-insensitive
+    /// unless the query has an uppercase letter)
     Search {
         /// The search query
-        query: String,
+        #[arg(required_unless_present = "tag")]
+        query: Option<String>,
+        /// Only show lines tagged #TAG
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+        /// Print JSON (see 'plan schema search')
+        #[arg(long)]
+        json: bool,
+        /// Print the number of matching lines in each file that has any
+        #[arg(short, long, conflicts_with_all = ["json", "files_with_matches"])]
+        count: bool,
+        /// Print only the names of files with a match
+        #[arg(short = 'l', long, conflicts_with = "json")]
+        files_with_matches: bool,
+        /// Match case exactly, even for an all-lowercase query
+        #[arg(short = 's', long, conflicts_with = "ignore_case")]
+        case_sensitive: bool,
+        /// Ignore case, even when the query has uppercase letters
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// Print stable tab-separated records: file, line, text
+        #[arg(long, value_name = "VERSION", num_args = 0..=1, require_equals = true,
+              default_missing_value = "v1",
+              conflicts_with_all = ["json", "count", "files_with_matches"])]
+        porcelain: Option<PorcelainVersion>,
+    },
+    /// List the lines in any plan file that link to a day with [[YYYY-MM-DD]]
+    Backlinks {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Print only the names of files with a link
+        #[arg(short = 'l', long)]
+        files_with_matches: bool,
+    },
+    /// List every #tag with the number of times it is used
+    Tags,
+    /// List link:URL tokens and bare http(s) URLs, newest first and each
+    /// once, with their dates and the line around them
+    Links {
+        #[command(flatten)]
+        range: AnnotationArgs,
+        /// Open the Nth link listed in the browser instead of printing
+        #[arg(long, value_name = "N")]
+        open: Option<usize>,
+    },
+    /// List contact:NAME tokens with their dates and the line around them
+    Contacts(AnnotationArgs),
+    /// List book:TITLE (or book:"Long Title") tokens with their dates and context
+    Books(AnnotationArgs),
+    /// Search one day's plan file; exits 0 on a match, 1 on none, 2 on error
+    GrepToday {
+        /// Text to look for (substring match, case-insensitive)
+        pattern: String,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Print nothing; only set the exit status
+        #[arg(short, long)]
+        quiet: bool,
+    },
+    /// Replace text across plan files, showing a diff before writing
+    Replace {
+        /// Text to find (literal, case-sensitive)
+        old: String,
+        /// Replacement text
+        new: String,
+        /// Only rewrite files dated on or after DATE
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        /// Show the diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Write without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Give a date a name usable anywhere a DATE is accepted
+    Name {
+        /// The date to name (any DATE expression, or YYYY-MM-DD)
+        #[arg(name = "DATE")]
+        date: String,
+        /// Name such as "launch-day"
+        name: String,
+    },
+    /// List named dates
+    Names,
+    /// Browse, search, and edit plan files in a terminal UI
+    #[cfg(feature = "tui")]
+    Tui,
+    /// Fuzzy-find a plan file by date, name, or first inbox entry and open it
+    #[cfg(feature = "tui")]
+    Pick,
+    /// Re-print a day's plan file whenever files in the plan directory
+    /// change, for a live view in a spare terminal pane
+    #[cfg(feature = "watch")]
+    Watch {
+        /// Relative date: @~N, today, yesterday, "N days ago" (default: today,
+        /// following the date past midnight)
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Run COMMAND on each change instead of printing, with PLAN_FILE and
+        /// PLAN_DATE set
+        #[arg(long, value_name = "COMMAND")]
+        exec: Option<String>,
+    },
+    /// Print inbox lines as other processes add or change them, like 'tail -f'
+    #[cfg(feature = "watch")]
+    Tail {
+        /// Relative date: @~N, today, yesterday, "N days ago" (default: today,
+        /// following the date past midnight)
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Print the last N inbox lines already there before following
+        #[arg(short = 'n', long, default_value_t = 0, value_name = "N")]
+        lines: usize,
+    },
+    /// Listen on a Unix socket for 'log TEXT' and 'jot TEXT' lines and add
+    /// them to today's inbox, one write at a time. Each line gets 'ok FILE'
+    /// or 'error MESSAGE' back
+    #[cfg(unix)]
+    Daemon {
+        /// Socket to listen on (default: plan/DIR-HASH.sock under
+        /// $XDG_RUNTIME_DIR, or the state directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+    /// Serve the plan directory over HTTP, with a capture page at '/'.
+    /// Without serve.token, only requests to localhost are answered and
+    /// writes from other sites' pages are refused
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on (default: serve.addr from the config, or 127.0.0.1:8080)
+        #[arg(long, value_name = "ADDR")]
+        addr: Option<String>,
+    },
+    /// Speak the Model Context Protocol on stdin/stdout, offering get_plan,
+    /// append_inbox, and search tools to a local assistant
+    Mcp,
+    /// List recently opened plan files with how often each was opened
+    Recent {
+        /// How many files to show
+        #[arg(short = 'n', long, default_value_t = 10, value_name = "N")]
+        limit: usize,
+    },
+    /// Open an undated scratch file (scratch-NAME.plan). log, jot, event, and
+    /// show accept NAME in place of a date
+    #[command(args_conflicts_with_subcommands = true)]
+    Scratch {
+        /// Scratch file name: letters, digits, '-' and '_'
+        #[arg(name = "NAME", default_value = "default")]
+        name: String,
+        #[command(subcommand)]
+        action: Option<ScratchAction>,
+    },
+    /// Manage the search index used when search.engine = index
+    Index {
+        #[command(subcommand)]
+        action: IndexAction,
+    },
+    /// Apply due [auto] capture rules; meant to run from cron every few minutes
+    CronTick,
+    /// Walk through recent open tasks and keep, complete, drop, or snooze each one
+    Review {
+        /// How many days back to review, ending today
+        #[arg(long, default_value_t = 7, value_name = "N")]
+        days: u32,
+    },
+    /// Print scheduled items and open tasks from upcoming plan files, and
+    /// recurring entries due on days without one
+    Upcoming {
+        /// How many days ahead to look
+        #[arg(long, default_value_t = 14, value_name = "N")]
+        days: u32,
+    },
+    /// List open tasks grouped by age, or email them (e.g. from a Monday cron job)
+    Nag {
+        /// Send the list to ADDRESS through nag.sendmail instead of printing
+        /// it; nothing is sent when there are no open tasks
+        #[arg(long, value_name = "ADDRESS")]
+        email: Option<String>,
+    },
+    /// Print a Markdown standup update: tasks done on the previous working
+    /// day, today's open tasks, and open tasks tagged #blocked
+    Standup {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Summarize a week or month of plan files (highlights, completed tasks,
+    /// and stats) into a rollup file in rollup.dir
+    #[command(group(clap::ArgGroup::new("period").required(true)))]
+    Rollup {
+        /// Any day in the period: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// Cover the Monday-to-Sunday week
+        #[arg(long, group = "period")]
+        week: bool,
+        /// Cover the calendar month
+        #[arg(long, group = "period")]
+        month: bool,
+        /// Ask about each highlight instead of taking only the lines marked '! '
+        #[arg(long)]
+        curate: bool,
+        /// Print the rollup instead of writing it
+        #[arg(long)]
+        print: bool,
+    },
+    /// Pull plan files from the sync.backend remote, then push local changes.
+    /// Files changed on both sides keep the local copy and save the remote
+    /// one as a .sync-conflict file
+    Sync {
+        /// Show what would be pulled, pushed, and conflict without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Merge .sync-conflict copies back into their plan files, keeping the
+    /// lines of both, then delete the copies
+    Resolve {
+        /// Only resolve conflicts in this day's files (default: every conflict)
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Summarize activity across plan files, or export a calendar heatmap
+    Stats {
+        /// Write an SVG calendar heatmap to FILE instead ('-' for stdout)
+        #[arg(long, value_name = "FILE")]
+        svg: Option<String>,
+        /// Number of months covered by the heatmap, ending with the one
+        /// containing --from (default: the current one)
+        #[arg(long, default_value_t = 3, value_name = "N")]
+        months: u32,
+        /// Print the summary as JSON (see 'plan schema stats')
+        #[arg(long, conflicts_with = "svg")]
+        json: bool,
+    },
+    /// Print a calendar heatmap of recent activity, one cell per day
+    Cal {
+        /// Number of months shown, ending with the one containing --from (default:
+        /// the current one)
+        #[arg(long, default_value_t = 3, value_name = "N")]
+        months: u32,
+        /// Shade days by tasks written rather than lines
+        #[arg(long)]
+        tasks: bool,
+    },
+    /// List the days in a range that have no plan file
+    Gaps {
+        /// Last day checked (default: today)
+        #[arg(name = "DATE")]
+        date: Option<String>,
+        /// First day checked (default: the oldest plan file's day)
+        #[arg(long, value_name = "DATE")]
+        since: Option<String>,
+        /// Leave out Saturdays and Sundays (default: gaps.skip_weekends from
+        /// the config)
+        #[arg(long)]
+        skip_weekends: bool,
+    },
+    /// Create the template for a day that has no plan file, even a past one,
+    /// to reconstruct a missed day
+    Backfill {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: String,
+        /// Open the new file in the editor
+        #[arg(long)]
+        edit: bool,
+    },
+    /// Reverse the last write to a plan file, including creating the day's
+    /// file. Refuses if the file was edited since (override with --force)
+    Undo,
+    /// Show, edit, or check the template new plan files start from
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// List or restore the snapshots taken when backups.keep is set
+    Backups {
+        #[command(subcommand)]
+        action: BackupsAction,
+    },
+    /// Inspect or edit the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Check that this build of plan keeps its CLI contract (exit codes,
+    /// error messages, locking) on this machine, in a temporary directory
+    SelfTest,
+    /// Print the JSON Schema for a command's --json output, or for all of them
+    Schema {
+        /// ls, parse, search, tasks, or stats
+        #[arg(name = "COMMAND")]
+        name: Option<String>,
+    },
+    /// Print a shell completion script to stdout. For completion that also
+    /// offers the dates of your plan files, load 'COMPLETE=SHELL plan'
+    /// instead, e.g. 'source <(COMPLETE=bash plan)'
+    Completions {
+        /// Target shell
+        shell: clap_complete::Shell,
+    },
+    /// Print completion candidates, one per line, for shell scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        #[arg(value_enum)]
+        kind: CompleteKind,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompleteKind {
+    /// Dates with a plan file, newest first, then named dates
+    Dates,
+}
+
+/// Which days `plan links`, `plan contacts`, and `plan books` look at.
+#[derive(Args, Debug)]
+struct AnnotationArgs {
+    /// Only this week, Monday through today
+    #[arg(long, conflicts_with_all = ["month", "since"])]
+    week: bool,
+    /// Only this month, from the 1st through today
+    #[arg(long, conflicts_with = "since")]
+    month: bool,
+    /// Only files dated on or after DATE
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ScratchAction {
+    /// Move a scratch file into a dated plan file and delete it: inbox lines go
+    /// to the day's inbox, notes to a section named after the scratch file
+    Merge {
+        #[arg(name = "NAME")]
+        name: String,
+        /// Day to merge into (default today)
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum IndexAction {
+    /// Discard the search index and index every plan file again
+    Rebuild,
+}
+
+/// Whether `log` and `jot` prefix entries with the capture time, overriding
+/// `capture.timestamp`.
+#[derive(Args, Debug)]
+struct TimestampArgs {
+    /// Prefix the entry with the current time, e.g. '* 14:32 call bob'
+    #[arg(long, overrides_with = "no_timestamp")]
+    timestamp: bool,
+    /// Don't prefix the time, even with capture.timestamp set
+    #[arg(long)]
+    no_timestamp: bool,
+}
+
+impl TimestampArgs {
+    fn enabled(&self, configured: bool) -> bool {
+        (configured || self.timestamp) && !self.no_timestamp
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigAction {
+    /// Print the resolved config file path
+    Path,
+    /// Open the config file in the editor, then validate it
+    Edit,
+}
+
+#[derive(Subcommand, Debug)]
+enum TemplateAction {
+    /// Print what a new plan file for DATE would start with
+    Show {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Open the template in the editor, starting from the built-in one,
+    /// then check it
+    Edit,
+    /// Check the template's placeholders and that it makes an inbox
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+enum AttachAction {
+    /// List a day's attachments, numbered, with their sizes and kinds
+    Ls {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Open one of a day's attachments with the platform opener
+    Open {
+        /// Number from 'plan attach ls', or part of the file name
+        #[arg(value_name = "N|PATTERN")]
+        target: String,
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BackupsAction {
+    /// List snapshots, newest first, for one day or for every file
+    List {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: Option<String>,
+    },
+    /// Put a snapshot back in place of a day's plan file. The current
+    /// content is snapshotted first, so a restore can itself be undone
+    Restore {
+        /// Relative date: @~N, today, yesterday, "N days ago"
+        #[arg(name = "DATE")]
+        date: String,
+        /// Which snapshot, as numbered by 'plan backups list'
+        #[arg(short = 'n', long, default_value_t = 1, value_name = "N")]
+        number: usize,
     },
 }
 
+/// All of stdin, for `jot -`. Lines and their indentation are kept;
+/// trailing whitespace and blank lines around the block are dropped.
+fn read_stdin_block() -> io::Result<String> {
+    use std::io::Read;
+    let mut input = String::new();
+    io::stdin().lock().read_to_string(&mut input)?;
+    Ok(trim_block(&input))
+}
+
+/// `input` without trailing whitespace or the blank lines around it.
+fn trim_block(input: &str) -> String {
+    let lines: Vec<&str> = input.lines().map(str::trim_end).collect();
+    let start = lines
+        .iter()
+        .position(|l| !l.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|l| !l.is_empty())
+        .map_or(start, |i| i + 1);
+    lines[start..end].join("\n")
+}
+
+/// Each non-blank line of stdin, trimmed, for `log --batch -`.
+fn read_stdin_lines() -> io::Result<Vec<String>> {
+    use std::io::Read;
+    let mut input = String::new();
+    io::stdin().lock().read_to_string(&mut input)?;
+    Ok(input
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
 fn read_stdin_line() -> io::Result<String> {
     use std::io::BufRead;
     let mut line = String::new();
@@ -99,7 +947,24 @@ fn read_stdin_line() -> io::Result<String> {
     Ok(line.trim().to_string())
 }
 
-fn open_editor(path: &std::path::Path) -> Result<()> {
+/// Editors known to accept `+N` before the file to start on line N.
+const PLUS_LINE_EDITORS: &[&str] = &[
+    "vi",
+    "vim",
+    "nvim",
+    "gvim",
+    "nano",
+    "emacs",
+    "emacsclient",
+    "kak",
+    "micro",
+    "mg",
+    "vis",
+];
+
+/// Open `path` in the editor. When `line` is given, it is passed using
+/// `line_arg` (a template containing `{line}`), or `+N` for known editors.
+fn open_editor(path: &Path, line: Option<usize>, line_arg: Option<&str>) -> Result<()> {
     let editor_env = env::var("VISUAL")
         .or_else(|_| env::var("EDITOR"))
         .unwrap_or_else(|_| "nano".to_string());
@@ -110,7 +975,22 @@ fn open_editor(path: &std::path::Path) -> Result<()> {
     }
 
     let mut cmd = ProcessCommand::new(&args[0]);
-    cmd.args(&args[1..]).arg(path);
+    cmd.args(&args[1..]);
+    if let Some(line) = line {
+        let program = Path::new(&args[0])
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let template = line_arg.or_else(|| {
+            PLUS_LINE_EDITORS
+                .contains(&program.as_str())
+                .then_some("+{line}")
+        });
+        if let Some(template) = template {
+            cmd.arg(template.replace("{line}", &line.to_string()));
+        }
+    }
+    cmd.arg(path);
 
     let status = cmd
         .status()
@@ -127,220 +1007,3600 @@ fn open_editor(path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-fn maybe_warn_unexpected(cfg: &config::Config, unexpected: &[String]) {
-    if cfg.scan.warn_unexpected {
-        file::warn_unexpected_files(unexpected);
-    }
+/// A column in `plan ls` output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LsColumn {
+    Date,
+    Weekday,
+    Lines,
+    Open,
+    Done,
+    Files,
 }
 
-fn parse_date_arg_or_error(arg: Option<&str>) -> Result<u32> {
-    date::parse_date_opt(arg).map_err(|e| usage_err(e.to_string()))
-}
+const DEFAULT_LS_COLUMNS: &[LsColumn] = &[
+    LsColumn::Date,
+    LsColumn::Weekday,
+    LsColumn::Lines,
+    LsColumn::Open,
+    LsColumn::Done,
+];
 
-fn handle_file_exists(path: &Path, naive_date: chrono::NaiveDate, days_ago: u32) -> Result<()> {
-    if let Err(e) = date::ensure_file_exists(path, naive_date, days_ago > 0) {
-        if e.kind() == io::ErrorKind::NotFound {
-            return Err(usage_err(format!(
-                "No plan file for that date: {}",
-                path.file_name()
-                    .map(|n| n.to_string_lossy().into_owned())
-                    .unwrap_or_else(|| path.display().to_string())
-            )));
-        } else {
-            return Err(e).context("Error ensuring file exists");
+impl LsColumn {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "date" => Some(LsColumn::Date),
+            "weekday" => Some(LsColumn::Weekday),
+            "lines" => Some(LsColumn::Lines),
+            "open" | "tasks_open" => Some(LsColumn::Open),
+            "done" | "tasks_done" => Some(LsColumn::Done),
+            "files" => Some(LsColumn::Files),
+            _ => None,
         }
     }
-    Ok(())
-}
 
-fn run() -> Result<()> {
-    let cli = Cli::parse();
+    /// The bare value, for templates.
+    fn value(self, naive_date: chrono::NaiveDate, counts: &stats::DayCounts) -> String {
+        match self {
+            LsColumn::Date | LsColumn::Weekday => self.cell(naive_date, counts),
+            LsColumn::Lines => counts.lines.to_string(),
+            LsColumn::Open => counts.open.to_string(),
+            LsColumn::Done => counts.done.to_string(),
+            LsColumn::Files => counts.files.to_string(),
+        }
+    }
 
-    if cli.init {
-        if let Some(dir) = cli.dir {
-            let expanded_dir = config::expand_tilde(&dir);
-            if !expanded_dir.exists() {
-                fs::create_dir_all(&expanded_dir).context(format!(
-                    "Error creating directory {}",
-                    expanded_dir.display()
-                ))?;
-            }
-            let _cfg = config::Config::init(&dir)?;
-            println!("Configured plan directory: {}", dir);
-            return Ok(());
-        } else {
-            return Err(usage_err("--init requires --dir=<path>"));
+    fn cell(self, naive_date: chrono::NaiveDate, counts: &stats::DayCounts) -> String {
+        match self {
+            LsColumn::Date => naive_date.format("%Y-%m-%d").to_string(),
+            LsColumn::Weekday => naive_date.format("%a").to_string(),
+            LsColumn::Lines => format!("{:>2} lines", counts.lines),
+            LsColumn::Open => format!("{:>2} open", counts.open),
+            LsColumn::Done => format!("{:>2} done", counts.done),
+            LsColumn::Files => format!("{:>2} files", counts.files),
         }
     }
+}
+
+/// How `plan ls` lays out a row: aligned, labeled columns, or a template
+/// of literal text and `{field}` placeholders.
+enum LsFormat {
+    Columns(Vec<LsColumn>),
+    Template(Vec<TemplatePart>),
+}
 
-    let mut cfg = config::Config::load()?;
+enum TemplatePart {
+    Literal(String),
+    Field(LsColumn),
+}
 
-    if let Some(dir) = cli.dir {
-        cfg.dir = config::expand_tilde(&dir);
-        if !cfg.dir.exists() {
-            fs::create_dir_all(&cfg.dir)
-                .context(format!("Error creating directory {}", cfg.dir.display()))?;
+impl LsFormat {
+    fn render(&self, naive_date: chrono::NaiveDate, counts: &stats::DayCounts) -> String {
+        match self {
+            LsFormat::Columns(columns) => columns
+                .iter()
+                .map(|c| c.cell(naive_date, counts))
+                .collect::<Vec<_>>()
+                .join("  "),
+            LsFormat::Template(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    TemplatePart::Literal(text) => text.clone(),
+                    TemplatePart::Field(column) => column.value(naive_date, counts),
+                })
+                .collect(),
         }
     }
+}
 
-    if cli.path && cli.command.is_some() {
-        return Err(usage_err(
-            "--path can only be used with the default command.",
-        ));
-    }
+/// The line closing a group of `plan ls --group-by` rows.
+fn print_ls_subtotal(cfg: &config::Config, label: &str, sum: &stats::DayCounts) {
+    let line = format!(
+        "-- {}: {} files, {} lines, {} open, {} done",
+        label, sum.files, sum.lines, sum.open, sum.done
+    );
+    println!("{}", cfg.colors.paint(Role::Header, &line));
+}
 
-    // Single scan for all commands — warns once, reused by ls/search/--last
-    let mut plan_entries = Vec::new();
-    if cfg.dir.exists() {
-        let scan = file::scan_plan_dir(&cfg.dir, &cfg.scan.ignored_patterns)?;
-        maybe_warn_unexpected(&cfg, &scan.unexpected);
-        plan_entries = scan.plan_entries;
-    }
+fn unknown_ls_field(name: &str) -> anyhow::Error {
+    usage_err(format!(
+        "Unknown ls field '{}'. Use date, weekday, lines, tasks_open, tasks_done, or files.",
+        name
+    ))
+}
 
-    let latest_plan = file::find_latest(&plan_entries);
+/// Parse `--format`: a template when it contains `{`, otherwise a comma list
+/// of columns. Templates understand `\t`, `\n`, and `\\`, and `{{`/`}}` for
+/// literal braces.
+fn parse_ls_format(spec: &str) -> Result<LsFormat> {
+    if !spec.contains('{') {
+        return spec
+            .split(',')
+            .map(|name| {
+                LsColumn::from_name(name.trim()).ok_or_else(|| unknown_ls_field(name.trim()))
+            })
+            .collect::<Result<_>>()
+            .map(LsFormat::Columns);
+    }
 
-    match &cli.command {
-        Some(Commands::Log { text: val, date }) | Some(Commands::Jot { text: val, date }) => {
-            let is_task = matches!(cli.command, Some(Commands::Log { .. }));
-            let text = if val == "-" {
-                read_stdin_line()?
-            } else {
-                val.trim().to_string()
-            };
-            if text.is_empty() {
-                return Err(usage_err("Message cannot be empty."));
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = spec.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => literal.push('\t'),
+                Some('n') => literal.push('\n'),
+                Some('\\') | None => literal.push('\\'),
+                Some(other) => {
+                    literal.push('\\');
+                    literal.push(other);
+                }
+            },
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
             }
-
-            let actual_date = date.as_deref().or(cli.date.as_deref());
-            if actual_date.is_some() && cli.last {
-                return Err(usage_err("Cannot use --last with a specific date."));
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
             }
-
-            let (path, target_date, days_ago) = if cli.last {
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(usage_err("Unclosed '{' in ls format.")),
+                    }
+                }
+                let column =
+                    LsColumn::from_name(name.trim()).ok_or_else(|| unknown_ls_field(&name))?;
+                if !literal.is_empty() {
+                    parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(TemplatePart::Field(column));
+            }
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        parts.push(TemplatePart::Literal(literal));
+    }
+    Ok(LsFormat::Template(parts))
+}
+
+/// Print a `--json` document, pretty-printed.
+fn print_json(doc: serde_json::Value) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&doc)?);
+    Ok(())
+}
+
+/// The file new entries go into for the day `path` belongs to: its last
+/// continuation file, if any.
+fn current_part(cfg: &config::Config, path: std::path::PathBuf) -> std::path::PathBuf {
+    match path
+        .file_name()
+        .and_then(|n| file::plan_date(&cfg.layout, &n.to_string_lossy()))
+    {
+        Some(naive_date) => date::current_plan_path(&cfg.layout, &cfg.dir, naive_date),
+        None => path,
+    }
+}
+
+/// Suggest `plan continue` once the file being written to has grown past
+/// `files.max_size`.
+fn warn_if_oversized(cfg: &config::Config, path: &Path) {
+    let Some(max_size) = cfg.files.max_size else {
+        return;
+    };
+    if fs::metadata(path).is_ok_and(|m| m.len() > max_size) {
+        diag::warn(
+            Warning::FileTooLarge,
+            &format!(
+                "{} is larger than files.max_size; run 'plan continue' to start a continuation file",
+                path.file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string())
+            ),
+        );
+    }
+}
+
+/// Open a plan file in the editor, running the first-open hook beforehand if
+/// this file has never been opened.
+fn open_plan(cfg: &config::Config, path: &Path, section: Option<&str>) -> Result<()> {
+    if let Some(hook) = &cfg.hooks.first_open {
+        run_first_open_hook(cfg, hook, path)?;
+    }
+    let line = match section {
+        Some(name) => Some(section_cursor_line(&cfg.layout, path, name)?),
+        None => None,
+    };
+    if let Err(e) = history::record_open(&paths::state_dir(), path, date::now()) {
+        diag::warn(
+            Warning::HistoryUnavailable,
+            &format!("could not record history: {}", e),
+        );
+    }
+    let snapshot =
+        backup::snapshot(path, &cfg.backups).context("Error backing up the plan file")?;
+    open_editor(path, line, cfg.editor.line_arg.as_deref())?;
+    // Only edits that changed something are worth keeping
+    if let Some(snapshot) = snapshot
+        && fs::read(&snapshot).ok() == fs::read(path).ok()
+    {
+        fs::remove_file(snapshot)?;
+    }
+    Ok(())
+}
+
+/// 1-based line to place the cursor on for `name`: the last line inside the
+/// section, or its opening marker when the section is empty.
+fn section_cursor_line(layout: &Layout, path: &Path, name: &str) -> Result<usize> {
+    let content = fs::read_to_string(path)?;
+    let Some(span) = section::find_section(layout, &content, name) else {
+        bail!(
+            "No section named '{}' in {}",
+            name,
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string())
+        );
+    };
+    Ok(span.body().end.max(span.open + 1))
+}
+
+fn run_first_open_hook(cfg: &config::Config, hook: &str, path: &Path) -> Result<()> {
+    let state_dir = paths::state_dir();
+    let Some(naive_date) = path
+        .file_name()
+        .and_then(|n| file::plan_date(&cfg.layout, &n.to_string_lossy()))
+    else {
+        return Ok(());
+    };
+
+    {
+        let lock = file::acquire_lock(path, &cfg.lock)?;
+        // Checked under the lock so concurrent opens inject only once
+        if hooks::was_opened(&state_dir, path)? {
+            return Ok(());
+        }
+        match hooks::run_first_open(hook, path, naive_date) {
+            Ok(output) if !output.trim().is_empty() => {
+                backup::snapshot(path, &cfg.backups)?;
+                file::append_to_file(path, &output, &lock)?;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                // Leave the file unmarked so the hook is retried next time
+                diag::warn(Warning::HookFailed, &e.to_string());
+                return Ok(());
+            }
+        }
+        hooks::mark_opened(&state_dir, path)?;
+    }
+    Ok(())
+}
+
+/// Print each day's files under a `==> DATE.plan <==` header, skipping days
+/// without a file, and return how many days were printed.
+fn print_days(
+    cfg: &config::Config,
+    days: impl IntoIterator<Item = chrono::NaiveDate>,
+) -> Result<usize> {
+    let mut printed = 0;
+    for naive_date in days {
+        let paths = date::day_paths(&cfg.layout, &cfg.dir, naive_date);
+        if paths.is_empty() {
+            continue;
+        }
+        if printed > 0 {
+            println!();
+        }
+        let header = format!("==> {} <==", date::format_filename(&cfg.layout, naive_date));
+        println!("{}", cfg.colors.paint(Role::Filename, &header));
+        for path in paths {
+            let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+            let content = fs::read_to_string(&path)?;
+            print!("{}", cfg.colors.paint_plan(&content));
+        }
+        printed += 1;
+    }
+    Ok(printed)
+}
+
+/// The non-blank inbox lines of a day's files as (file name, line number,
+/// line).
+fn inbox_items(
+    cfg: &config::Config,
+    naive_date: chrono::NaiveDate,
+) -> Result<Vec<(String, usize, String)>> {
+    let mut items = Vec::new();
+    for path in date::day_paths(&cfg.layout, &cfg.dir, naive_date) {
+        let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+        let content =
+            fs::read_to_string(&path).context(format!("Error reading {}", path.display()))?;
+        let Some(span) = section::find_section(&cfg.layout, &content, "inbox") else {
+            continue;
+        };
+        let filename = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        for (i, line) in content.lines().enumerate() {
+            if span.body().contains(&i) && !line.trim().is_empty() {
+                items.push((filename.clone(), i + 1, line.to_string()));
+            }
+        }
+    }
+    Ok(items)
+}
+
+/// The plan files in each of the config's `extra_dirs`, by directory, with
+/// the label their rows are shown under. A missing directory is skipped
+/// with a warning, since it may live on a drive that isn't mounted.
+fn extra_dir_entries(cfg: &config::Config) -> Result<Vec<(PathBuf, String, Vec<fs::DirEntry>)>> {
+    let mut dirs = Vec::new();
+    for dir in &cfg.extra_dirs {
+        if !dir.is_dir() {
+            diag::warn(
+                Warning::ConfigProblem,
+                &format!("extra_dirs: {} does not exist; skipping it", dir.display()),
+            );
+            continue;
+        }
+        let scan = file::scan_plan_dir(&cfg.layout, dir, &cfg.scan.ignored_patterns)
+            .context(format!("Error reading {}", dir.display()))?;
+        dirs.push((dir.clone(), dir_label(dir), scan.plan_entries));
+    }
+    Ok(dirs)
+}
+
+/// The last component of an extra directory, prefixed to the names of
+/// its files so they can't be mistaken for files in the plan directory.
+fn dir_label(dir: &Path) -> String {
+    dir.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| dir.display().to_string())
+}
+
+/// `name` as shown in output: bare in the plan directory, `label/name`
+/// in an extra directory.
+fn labeled(label: Option<&str>, name: &str) -> String {
+    match label {
+        Some(label) => format!("{}/{}", label, name),
+        None => name.to_string(),
+    }
+}
+
+/// Warn about stray files. Sync conflict copies get their own warning
+/// pointing at `plan resolve`, except while it is the command running.
+fn maybe_warn_unexpected(cfg: &config::Config, unexpected: &[String], resolving: bool) {
+    let (conflicts, others): (Vec<String>, Vec<String>) = unexpected
+        .iter()
+        .cloned()
+        .partition(|name| conflict::original_name(&cfg.layout, name).is_some());
+    if cfg.scan.warn_unexpected {
+        file::warn_unexpected_files(&others);
+    }
+    if !conflicts.is_empty() && !resolving {
+        diag::warn(
+            Warning::SyncConflict,
+            &format!(
+                "{} unresolved sync conflict file(s); merge them with 'plan resolve'",
+                conflicts.len()
+            ),
+        );
+    }
+}
+
+/// Ask a yes/no question on stderr and read the answer from stdin (default no).
+fn confirm(prompt: &str) -> io::Result<bool> {
+    use std::io::{BufRead, Write};
+    eprint!("{} [y/N] ", prompt);
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// What to do with a task during `plan review`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReviewDecision {
+    Keep,
+    Complete,
+    Drop,
+    Snooze,
+}
+
+/// Ask what to do with a task during `plan review`. `None` means stop
+/// reviewing, either by request or because stdin ran out.
+fn ask_review_decision(line: &str) -> io::Result<Option<ReviewDecision>> {
+    use std::io::{BufRead, Write};
+    eprintln!("  {}", line);
+    loop {
+        eprint!("  [k]eep, [c]omplete, [d]rop, [s]nooze, [q]uit? ");
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            eprintln!();
+            return Ok(None);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "" | "k" | "keep" => return Ok(Some(ReviewDecision::Keep)),
+            "c" | "complete" => return Ok(Some(ReviewDecision::Complete)),
+            "d" | "drop" => return Ok(Some(ReviewDecision::Drop)),
+            "s" | "snooze" => return Ok(Some(ReviewDecision::Snooze)),
+            "q" | "quit" => return Ok(None),
+            _ => eprintln!("  Please answer k, c, d, s, or q."),
+        }
+    }
+}
+
+/// Write review decisions back to `path` under its lock. Decisions carry the
+/// line they were made on and are matched by content, so edits made while the
+/// prompt was open are not clobbered. Returns the lines snoozed out of the file.
+fn apply_review(
+    cfg: &config::Config,
+    path: &Path,
+    decisions: &[(usize, String, ReviewDecision)],
+    today: chrono::NaiveDate,
+) -> Result<Vec<String>> {
+    let _lock = file::acquire_lock(path, &cfg.lock)?;
+    let content = fs::read_to_string(path)?;
+    let mut lines: Vec<Option<String>> = content.lines().map(|l| Some(l.to_string())).collect();
+    let mut snoozed = Vec::new();
+
+    for (line_number, original, decision) in decisions {
+        let unchanged = lines.get(line_number - 1).and_then(|l| l.as_deref()) == Some(original);
+        let idx = if unchanged {
+            Some(line_number - 1)
+        } else {
+            lines.iter().position(|l| l.as_deref() == Some(original))
+        };
+        let Some(idx) = idx else {
+            diag::warn(
+                Warning::ReviewConflict,
+                &format!("'{}' changed during review; left as is", original),
+            );
+            continue;
+        };
+        match decision {
+            ReviewDecision::Keep => {}
+            ReviewDecision::Complete | ReviewDecision::Drop => {
+                let state = if *decision == ReviewDecision::Complete {
+                    task::TaskState::Done
+                } else {
+                    task::TaskState::Cancelled
+                };
+                if let Some(closed) = task::close_task(original, state, today) {
+                    lines[idx] = Some(closed);
+                }
+            }
+            ReviewDecision::Snooze => {
+                lines[idx] = None;
+                snoozed.push(original.clone());
+            }
+        }
+    }
+
+    let mut updated: String = lines.into_iter().flatten().map(|l| l + "\n").collect();
+    if !content.ends_with('\n') {
+        updated.pop();
+    }
+    backup::snapshot(path, &cfg.backups)?;
+    file::write_atomic(
+        path,
+        &file::with_line_ending(&updated, file::line_ending(&content)),
+    )?;
+    undo::record_write("review", path, Some(&content));
+    Ok(snoozed)
+}
+
+/// Print the lines of one day's plan files containing `pattern`
+/// (case-insensitive). Exits 1 when nothing matched, like grep; a missing
+/// file counts as no match.
+fn grep_day(
+    cfg: &config::Config,
+    naive_date: chrono::NaiveDate,
+    pattern: &str,
+    quiet: bool,
+) -> Result<()> {
+    let mut content = String::new();
+    for path in date::day_paths(&cfg.layout, &cfg.dir, naive_date) {
+        match fs::read_to_string(&path) {
+            Ok(c) => content.push_str(&c),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context(format!("Error reading {}", path.display())),
+        }
+    }
+
+    let p_lower = pattern.to_lowercase();
+    let mut matched = false;
+    for line in content.lines() {
+        if line.to_lowercase().contains(&p_lower) {
+            matched = true;
+            if quiet {
+                break;
+            }
+            println!("{}", cfg.colors.paint_matches(line, pattern, true));
+        }
+    }
+    if matched { Ok(()) } else { Err(silent_exit(1)) }
+}
+
+fn parse_date_arg_or_error(arg: Option<&str>) -> Result<u32> {
+    let err = match date::parse_date_opt(arg) {
+        Ok(days) => return Ok(days),
+        Err(e) => e,
+    };
+    let Some((input, suggestion)) = arg.and_then(|a| Some((a, date::suggest_date(a)?))) else {
+        return Err(usage_err(err.to_string()));
+    };
+
+    // Offer the correction interactively; scripts get the hint in the error instead
+    if io::stdin().is_terminal()
+        && io::stderr().is_terminal()
+        && confirm(&format!(
+            "plan: unrecognized date '{}'. Did you mean '{}'?",
+            input, suggestion
+        ))?
+    {
+        return date::parse_date_opt(Some(&suggestion)).map_err(|e| usage_err(e.to_string()));
+    }
+    Err(usage_err(format!("{} Did you mean '{}'?", err, suggestion)))
+}
+
+/// Resolve a DATE argument to a calendar day: a named date, an absolute
+/// `YYYY-MM-DD`, or a relative expression counted back from `base` (`base`
+/// itself when absent). With `dates.parser = natural`, expressions the
+/// built-in grammar rejects get a second chance as free-form dates.
+fn resolve_date_arg(
+    cfg: &config::Config,
+    names: &names::Names,
+    base: chrono::NaiveDate,
+    arg: Option<&str>,
+) -> Result<chrono::NaiveDate> {
+    if let Some(arg) = arg {
+        if let Some(named) = names.get(arg) {
+            return Ok(named);
+        }
+        if let Ok(absolute) = chrono::NaiveDate::parse_from_str(arg.trim(), "%Y-%m-%d") {
+            return Ok(absolute);
+        }
+        if cfg.dates.parser == config::DateParser::Natural
+            && date::parse_date_opt(Some(arg)).is_err()
+            && let Some(natural) = date::parse_natural(arg, base)
+        {
+            return Ok(natural);
+        }
+    }
+    let days_ago = parse_date_arg_or_error(arg)?;
+    date::get_date_from(base, days_ago).map_err(|e| usage_err(e.to_string()))
+}
+
+/// Resolve DATE arguments that may be `FROM..TO` ranges into the days they
+/// name. Ranges only expand to days that have a file, so wide spans stay cheap.
+fn resolve_date_targets(
+    cfg: &config::Config,
+    names: &names::Names,
+    base: chrono::NaiveDate,
+    plan_entries: &[fs::DirEntry],
+    args: &[String],
+) -> Result<std::collections::BTreeSet<chrono::NaiveDate>> {
+    let resolve = |arg: &str| resolve_date_arg(cfg, names, base, Some(arg));
+    let mut targets = std::collections::BTreeSet::new();
+    for arg in args {
+        if let Some((from, to)) = arg.split_once("..") {
+            let (from, to) = (resolve(from)?, resolve(to)?);
+            let (start, end) = (from.min(to), from.max(to));
+            targets.extend(
+                plan_entries
+                    .iter()
+                    .filter_map(|e| file::plan_date(&cfg.layout, &e.file_name().to_string_lossy()))
+                    .filter(|d| *d >= start && *d <= end),
+            );
+        } else {
+            targets.insert(resolve(arg)?);
+        }
+    }
+    Ok(targets)
+}
+
+/// The scratch file a DATE argument names. Named dates take precedence, and
+/// only existing scratch files count.
+fn scratch_arg(
+    cfg: &config::Config,
+    names: &names::Names,
+    arg: Option<&str>,
+) -> Option<std::path::PathBuf> {
+    let arg = arg?.trim();
+    if names.get(arg).is_some() {
+        return None;
+    }
+    scratch::path(&cfg.layout, &cfg.dir, arg).filter(|p| p.exists())
+}
+
+/// Move the scratch file at `path` into the plan file for `day`, then delete
+/// it. Both files stay locked until the scratch file is gone.
+fn merge_scratch(
+    cfg: &config::Config,
+    path: &Path,
+    name: &str,
+    day: chrono::NaiveDate,
+    force: bool,
+) -> Result<std::path::PathBuf> {
+    let _scratch_lock = file::acquire_lock(path, &cfg.lock)?;
+    let promotion = scratch::promote(&fs::read_to_string(path)?);
+
+    let target = date::current_plan_path(&cfg.layout, &cfg.dir, day);
+    {
+        let lock = file::acquire_lock(&target, &cfg.lock)?;
+        handle_file_exists(cfg, &target, day, force)?;
+        backup::snapshot(&target, &cfg.backups)?;
+        for line in &promotion.inbox {
+            file::insert_into_inbox(&cfg.layout, &target, line, &lock)?;
+        }
+        for (section, lines) in &promotion.sections {
+            for line in lines {
+                file::insert_into_section(&cfg.layout, &target, section, line, &lock)?;
+            }
+        }
+        for line in &promotion.notes {
+            file::insert_into_section(&cfg.layout, &target, name, line, &lock)?;
+        }
+    }
+    fs::remove_file(path)?;
+    Ok(target)
+}
+
+/// Merge scratch files untouched for `scratch.expire_days` into today's file.
+fn expire_scratch(cfg: &config::Config, entries: &[fs::DirEntry], force: bool) -> Result<()> {
+    let Some(days) = cfg.scratch.expire_days else {
+        return Ok(());
+    };
+    let cutoff = date::now() - chrono::Duration::days(i64::from(days));
+    for entry in entries {
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        let Some(name) = scratch::name_of(&cfg.layout, &filename) else {
+            continue;
+        };
+        let modified = entry.metadata()?.modified()?;
+        let modified = chrono::DateTime::<chrono::Local>::from(modified).naive_local();
+        if modified >= cutoff {
+            continue;
+        }
+        let target = merge_scratch(cfg, &entry.path(), name, date::today(), force)?;
+        eprintln!(
+            "plan: {} was untouched for {} days; merged into {}",
+            filename,
+            days,
+            target.file_name().unwrap_or_default().to_string_lossy()
+        );
+    }
+    Ok(())
+}
+
+/// The user's template for new plan files, if they have one.
+fn load_template(cfg: &config::Config) -> Result<Option<String>> {
+    template::load(&cfg.template).context(format!("Error reading {}", cfg.template.display()))
+}
+
+fn handle_file_exists(
+    cfg: &config::Config,
+    path: &Path,
+    naive_date: chrono::NaiveDate,
+    force: bool,
+) -> Result<()> {
+    let today = date::get_date(0)?;
+    check_clock_skew(&cfg.layout, path, naive_date, today, force)?;
+    match date::ensure_file_exists(
+        &cfg.layout,
+        load_template(cfg)?.as_deref(),
+        path,
+        naive_date,
+        naive_date < today,
+    ) {
+        Ok(()) => {}
+        Err(plan::Error::FileMissing(_)) => {
+            return Err(usage_err(format!(
+                "No plan file for that date: {}",
+                path.file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string())
+            )));
+        }
+        Err(e) => return Err(e).context("Error ensuring file exists"),
+    }
+    Ok(())
+}
+
+/// Refuse to create `path` for a day before the newest plan file's last
+/// write, which means the clock has likely jumped back, unless `force`.
+fn check_clock_skew(
+    layout: &Layout,
+    path: &Path,
+    naive_date: chrono::NaiveDate,
+    today: chrono::NaiveDate,
+    force: bool,
+) -> Result<()> {
+    if !force
+        && !path.exists()
+        && let Some(dir) = path.parent()
+        && let Some(skew) = file::clock_skew(layout, dir, today)?
+        && naive_date < skew.newest
+    {
+        diag::warn(
+            Warning::ClockSkew,
+            &format!(
+                "the system clock says today is {}, but {} was modified later than that; the clock may have jumped back",
+                today.format("%Y-%m-%d"),
+                skew.name
+            ),
+        );
+        return Err(usage_err(format!(
+            "Refusing to create {} before {}. Check the system clock, or pass --force.",
+            date::format_filename(layout, naive_date),
+            skew.name
+        )));
+    }
+    Ok(())
+}
+
+/// Index of the argument clap would read as the subcommand: the first one
+/// that is neither an option nor an option's value.
+fn subcommand_position(command: &clap::Command, args: &[OsString]) -> Option<usize> {
+    let takes_value = |arg: Option<&clap::Arg>| arg.is_some_and(|a| a.get_action().takes_values());
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        if arg == "--" {
+            return None;
+        }
+        if let Some(long) = arg.strip_prefix("--") {
+            if !long.contains('=')
+                && takes_value(command.get_arguments().find(|a| a.get_long() == Some(long)))
+            {
+                i += 1;
+            }
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // A value-taking short flag reads the next argument unless its
+            // value is attached, as in -n5
+            let last = shorts.chars().last();
+            if shorts.chars().count() == 1
+                && takes_value(command.get_arguments().find(|a| a.get_short() == last))
+            {
+                i += 1;
+            }
+        } else {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// `args` with an `[alias]` name in the subcommand position replaced by its
+/// expansion, as git does. Built-in commands always win over aliases.
+fn expand_aliases(mut args: Vec<OsString>, aliases: &[(String, String)]) -> Result<Vec<OsString>> {
+    if aliases.is_empty() {
+        return Ok(args);
+    }
+    let command = Cli::command();
+    let mut expanded: Vec<String> = Vec::new();
+    loop {
+        let Some(at) = subcommand_position(&command, &args) else {
+            return Ok(args);
+        };
+        let name = args[at].to_string_lossy().into_owned();
+        if command.find_subcommand(&name).is_some() {
+            return Ok(args);
+        }
+        let Some((_, expansion)) = aliases.iter().find(|(n, _)| *n == name) else {
+            return Ok(args);
+        };
+        if expanded.contains(&name) {
+            expanded.push(name);
+            return Err(usage_err(format!("Alias loop: {}.", expanded.join(" -> "))));
+        }
+        let words = shlex::split(expansion)
+            .filter(|words| !words.is_empty())
+            .ok_or_else(|| {
+                usage_err(format!(
+                    "Alias '{}' is not a valid command: '{}'",
+                    name, expansion
+                ))
+            })?;
+        expanded.push(name);
+        args.splice(at..=at, words.into_iter().map(OsString::from));
+    }
+}
+
+/// Where an unknown subcommand in `args` is and the `plan-NAME` executable
+/// that provides it, if one is on PATH.
+fn find_plugin(args: &[OsString]) -> Option<(usize, PathBuf)> {
+    let command = Cli::command();
+    let at = subcommand_position(&command, args)?;
+    let name = args[at].to_string_lossy();
+    if command.find_subcommand(name.as_ref()).is_some() {
+        return None;
+    }
+    Some((at, plugin::find(&name)?))
+}
+
+/// The command line after alias expansion, parsed, with the plugin it runs
+/// if any.
+struct Invocation {
+    cli: Cli,
+    args: Vec<OsString>,
+    plugin: Option<(usize, PathBuf)>,
+}
+
+fn parse_args() -> Result<Invocation> {
+    let args = expand_aliases(env::args_os().collect(), &config::aliases())?;
+    // Options before a plugin's name are plan's; everything after is the plugin's
+    let plugin = find_plugin(&args);
+    let cli = match &plugin {
+        Some((at, _)) => Cli::parse_from(&args[..*at]),
+        None => Cli::parse_from(&args),
+    };
+    Ok(Invocation { cli, args, plugin })
+}
+
+fn run(Invocation { cli, args, plugin }: Invocation) -> Result<()> {
+    diag::configure(cli.warnings.format(), &[]);
+
+    if cli.init {
+        if let Some(dir) = cli.dir {
+            let expanded_dir = config::expand_tilde(&dir);
+            if !expanded_dir.exists() {
+                fs::create_dir_all(&expanded_dir).context(format!(
+                    "Error creating directory {}",
+                    expanded_dir.display()
+                ))?;
+            }
+            file::write_root_marker(&expanded_dir).context(format!(
+                "Error marking {} as a plan directory",
+                expanded_dir.display()
+            ))?;
+            let _cfg = config::Config::init(&dir)?;
+            println!("Configured plan directory: {}", dir);
+            return Ok(());
+        } else {
+            return Err(usage_err("--init requires --dir=<path>"));
+        }
+    }
+
+    // Completions are generated from the CLI definition alone; don't require a config
+    if let Some(Commands::Completions { shell }) = &cli.command {
+        let mut cmd = Cli::command();
+        let name = cmd.get_name().to_string();
+        clap_complete::generate(*shell, &mut cmd, name, &mut io::stdout());
+        return Ok(());
+    }
+
+    if let Some(Commands::Complete {
+        kind: CompleteKind::Dates,
+    }) = &cli.command
+    {
+        for date in completion_dates() {
+            println!("{}", date);
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Schema { name }) = &cli.command {
+        let doc = match name {
+            Some(name) => schema::schema(name).ok_or_else(|| {
+                usage_err(format!(
+                    "No schema for '{}'. Use one of: {}.",
+                    name,
+                    schema::NAMES.join(", ")
+                ))
+            })?,
+            None => serde_json::Value::Object(
+                schema::NAMES
+                    .iter()
+                    .filter_map(|n| Some((n.to_string(), schema::schema(n)?)))
+                    .collect(),
+            ),
+        };
+        println!("{}", serde_json::to_string_pretty(&doc)?);
+        return Ok(());
+    }
+
+    if let Some(Commands::SelfTest) = &cli.command {
+        let exe = env::current_exe().context("Cannot find the running plan executable")?;
+        let outcomes = selftest::run(&exe).context("Error running the self-test")?;
+        let failed = outcomes.iter().filter(|o| o.failure.is_some()).count();
+        for outcome in &outcomes {
+            match &outcome.failure {
+                None => println!("ok    {}", outcome.name),
+                Some(why) => println!("FAIL  {}\n      {}", outcome.name, why),
+            }
+        }
+        println!("{} passed, {} failed", outcomes.len() - failed, failed);
+        if failed > 0 {
+            return Err(silent_exit(1));
+        }
+        return Ok(());
+    }
+
+    let state_dir = paths::state_dir();
+    if let Some(report) = migrate::run(&state_dir).context(format!(
+        "Error migrating state directory {}",
+        state_dir.display()
+    ))? {
+        eprintln!(
+            "plan: migrated state from version {} to {} (backup at {})",
+            report.from,
+            report.to,
+            report.backup.display()
+        );
+    }
+
+    // Config commands must work even when the config is missing or broken
+    if let Some(Commands::Config { action }) = &cli.command {
+        let config_path = config::get_config_path();
+        match action {
+            ConfigAction::Path => println!("{}", config_path.display()),
+            ConfigAction::Edit => {
+                if !config_path.exists() {
+                    if let Some(parent) = config_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(&config_path, "")?;
+                }
+                open_editor(&config_path, None, None)?;
+                let content = fs::read_to_string(&config_path)?;
+                for problem in config::validate(&content) {
+                    let location = if problem.line == 0 {
+                        config_path.display().to_string()
+                    } else {
+                        format!("{}:{}", config_path.display(), problem.line)
+                    };
+                    diag::warn(
+                        Warning::ConfigProblem,
+                        &format!("{}: {}", location, problem.message),
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let mut cfg = config::Config::load_in(cli.dir.as_deref().map(config::expand_tilde))?;
+    diag::configure(cli.warnings.format(), &cfg.warnings.suppress);
+    let local_config = cfg.dir.join(config::LOCAL_CONFIG);
+    if let Ok(content) = fs::read_to_string(&local_config) {
+        for problem in config::validate_local(&content) {
+            diag::warn(
+                Warning::ConfigProblem,
+                &format!(
+                    "{}:{}: {}",
+                    local_config.display(),
+                    problem.line,
+                    problem.message
+                ),
+            );
+        }
+    }
+    if cli.no_wait {
+        cfg.lock.timeout = std::time::Duration::ZERO;
+    }
+    cfg.colors.set_enabled(match cli.color {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        // https://no-color.org: any non-empty NO_COLOR disables color
+        ColorWhen::Auto => {
+            io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        }
+    });
+
+    if cli.dir.is_some() && !cfg.dir.exists() {
+        fs::create_dir_all(&cfg.dir)
+            .context(format!("Error creating directory {}", cfg.dir.display()))?;
+        file::write_root_marker(&cfg.dir)?;
+    }
+
+    // Refuse a directory that is clearly not ours before scanning or writing to it
+    if cfg.dir.exists()
+        && let Some(reason) = file::check_plan_root(&cfg.layout, &cfg.dir)?
+    {
+        return Err(usage_err(format!(
+            "{}. If this really is your plan directory, create {} in it or run 'plan --init --dir {}'.",
+            reason,
+            file::ROOT_MARKER,
+            cfg.dir.display()
+        )));
+    }
+
+    if cli.back && cli.command.is_some() {
+        return Err(usage_err(
+            "--back can only be used with the default command.",
+        ));
+    }
+    if cli.path
+        && !matches!(
+            cli.command,
+            None | Some(Commands::Show { .. })
+                | Some(Commands::Log { .. })
+                | Some(Commands::Jot { .. })
+                | Some(Commands::Event { .. })
+        )
+    {
+        return Err(usage_err(
+            "--path can only be used with the default command, show, log, jot, or event.",
+        ));
+    }
+    let path_only = cli.path
+        || matches!(
+            cli.command,
+            Some(Commands::Show { path: true, .. })
+                | Some(Commands::Log { path: true, .. })
+                | Some(Commands::Jot { path: true, .. })
+                | Some(Commands::Event { path: true, .. })
+        );
+    if cli.section.is_some() && cli.command.is_some() {
+        return Err(usage_err(
+            "--section can only be used with the default command.",
+        ));
+    }
+
+    let names = names::Names::load(&cfg.dir).context("Error reading named dates")?;
+    let base = match cli.from.as_deref() {
+        Some(_) if cli.last => {
+            return Err(usage_err("Cannot use --last with --from."));
+        }
+        Some(anchor) => resolve_date_arg(&cfg, &names, date::today(), Some(anchor))?,
+        None => date::today(),
+    };
+
+    if let Some((at, exe)) = &plugin {
+        let code = plugin::run(
+            exe,
+            &args[at + 1..],
+            &cfg.dir,
+            &config::get_config_path(),
+            base,
+        )
+        .context(format!("Error running {}", exe.display()))?;
+        return match code {
+            0 => Ok(()),
+            code => Err(silent_exit(code)),
+        };
+    }
+
+    // grep-today only reads one file, so it skips the directory scan
+    if let Some(Commands::GrepToday {
+        pattern,
+        date,
+        quiet,
+    }) = &cli.command
+    {
+        if cli.last {
+            return Err(usage_err(
+                "--last is not supported with the 'grep-today' command.",
+            ));
+        }
+        let naive_date = resolve_date_arg(&cfg, &names, base, date.as_deref())?;
+        return grep_day(&cfg, naive_date, pattern, *quiet);
+    }
+
+    // Single scan for all commands — warns once, reused by ls/search/--last
+    let mut plan_entries = Vec::new();
+    if cfg.dir.exists() {
+        let scan = file::scan_plan_dir(&cfg.layout, &cfg.dir, &cfg.scan.ignored_patterns)?;
+        let resolving = matches!(cli.command, Some(Commands::Resolve { .. }));
+        maybe_warn_unexpected(&cfg, &scan.unexpected, resolving);
+        plan_entries = scan.plan_entries;
+    }
+
+    let latest_plan = file::find_latest(&cfg.layout, &plan_entries);
+
+    match &cli.command {
+        Some(Commands::Log {
+            date,
+            section,
+            once,
+            ..
+        })
+        | Some(Commands::Jot {
+            date,
+            section,
+            once,
+            ..
+        })
+        | Some(Commands::Event {
+            date,
+            section,
+            once,
+            ..
+        }) => {
+            let val = match &cli.command {
+                Some(Commands::Log { text, .. })
+                | Some(Commands::Jot { text, .. })
+                | Some(Commands::Event { text, .. }) => text.as_deref().unwrap_or_default(),
+                _ => "",
+            };
+            // With --path there is nothing to write, so a lone TEXT is the date
+            let date = match (path_only, date) {
+                (true, None) if !val.is_empty() => &Some(val.to_string()),
+                _ => date,
+            };
+            let actual_date = date.as_deref().or(cli.date.as_deref());
+            if actual_date.is_some() && cli.last {
+                return Err(usage_err("Cannot use --last with a specific date."));
+            }
+            if let Some(name) = section
+                && section::open_marker_name(&format!("~{}~", name)).is_none()
+            {
+                return Err(usage_err(format!("Invalid section name '{}'.", name)));
+            }
+
+            let (path, target_date) = if cli.last {
                 if let Some(p) = latest_plan {
-                    (p, None, None)
+                    (current_part(&cfg, p), None)
                 } else {
                     bail!("No plan files found in {}", cfg.dir.display());
                 }
+            } else if let Some(path) = scratch_arg(&cfg, &names, actual_date) {
+                (path, None)
             } else {
-                let days = parse_date_arg_or_error(actual_date)?;
-                let naive = date::get_date(days).map_err(|e| usage_err(e.to_string()))?;
+                let naive = resolve_date_arg(&cfg, &names, base, actual_date)?;
                 (
-                    date::get_plan_path(&cfg.dir, naive),
+                    date::current_plan_path(&cfg.layout, &cfg.dir, naive),
                     Some(naive),
-                    Some(days),
                 )
             };
 
-            let lock = file::acquire_lock(&path)?;
+            if path_only {
+                println!("{}", path.display());
+                return Ok(());
+            }
+
+            let priority = match &cli.command {
+                Some(Commands::Log {
+                    priority: Some(p), ..
+                }) => Some(task::Priority::parse(p).ok_or_else(|| {
+                    usage_err(format!(
+                        "Invalid priority '{}'. Use a letter A-Z, high, medium, or low.",
+                        p
+                    ))
+                })?),
+                _ => None,
+            };
+            let time = match &cli.command {
+                Some(Commands::Event { at: Some(t), .. }) => Some(
+                    task::parse_time(t)
+                        .ok_or_else(|| usage_err(format!("Invalid time '{}'. Use HH:MM.", t)))?,
+                ),
+                _ => None,
+            };
+            let batch = matches!(cli.command, Some(Commands::Log { batch: true, .. }));
+            if batch && val != "-" {
+                return Err(usage_err("--batch reads stdin; pass '-' as the text."));
+            }
+            let clipboard = matches!(
+                cli.command,
+                Some(Commands::Jot {
+                    clipboard: true,
+                    ..
+                })
+            );
+            let texts = if batch {
+                read_stdin_lines()?
+            } else if clipboard {
+                let text = plan::clipboard::read(cfg.capture.clipboard.as_deref())
+                    .context("Error reading the clipboard")?;
+                vec![plan::clipboard::fence(&trim_block(&text))]
+            } else if val == "-" {
+                if matches!(cli.command, Some(Commands::Jot { .. })) {
+                    vec![read_stdin_block()?]
+                } else {
+                    vec![read_stdin_line()?]
+                }
+            } else {
+                vec![val.trim().to_string()]
+            };
+            if texts.iter().all(|t| t.is_empty()) {
+                return Err(usage_err("Message cannot be empty."));
+            }
+
+            let stamp = match &cli.command {
+                Some(Commands::Log { stamp, .. }) | Some(Commands::Jot { stamp, .. }) => {
+                    stamp.enabled(cfg.capture.timestamp)
+                }
+                _ => false,
+            };
+
+            // A running daemon takes plain one-line captures to today's
+            // existing file, so the two never write it at the same time
+            #[cfg(unix)]
+            {
+                let plain = match &cli.command {
+                    Some(Commands::Log { .. }) => priority.is_none() && !batch,
+                    Some(Commands::Jot { .. }) => !clipboard,
+                    _ => false,
+                };
+                if plain
+                    && section.is_none()
+                    && !*once
+                    && target_date == Some(date::today())
+                    && path.exists()
+                    && let [text] = texts.as_slice()
+                    && !text.contains('\n')
+                    && let Some(mut daemon) =
+                        plan::daemon::Client::connect(&paths::socket_path(&cfg.dir), &cfg.dir)
+                {
+                    let text = if stamp {
+                        format!("{} {}", date::now().format("%H:%M"), text)
+                    } else {
+                        text.clone()
+                    };
+                    let verb = match &cli.command {
+                        Some(Commands::Log { .. }) => "log",
+                        _ => "jot",
+                    };
+                    let reply = daemon
+                        .send(&format!("{} {}", verb, text))
+                        .context("Error talking to the daemon")?;
+                    if let Some(message) = reply.strip_prefix("error ") {
+                        bail!("The daemon could not write {}: {}", path.display(), message);
+                    }
+                    warn_if_oversized(&cfg, &path);
+                    return Ok(());
+                }
+            }
+
+            let lock = file::acquire_lock(&path, &cfg.lock)?;
+            let before = fs::read_to_string(&path).ok();
+
+            if let Some(naive) = target_date {
+                handle_file_exists(&cfg, &path, naive, cli.force)?;
+            }
+
+            let target = section.as_deref().unwrap_or("inbox");
+            let mut entries = Vec::new();
+            for text in texts {
+                let text = if stamp && text.contains('\n') {
+                    // On its own line, so a fenced block's opener stays intact
+                    format!("{}\n{}", date::now().format("%H:%M"), text)
+                } else if stamp {
+                    format!("{} {}", date::now().format("%H:%M"), text)
+                } else {
+                    text
+                };
+                let entry = match (&cli.command, priority) {
+                    (Some(Commands::Log { .. }), Some(p)) => format!("* {} {}", p, text),
+                    (Some(Commands::Log { .. }), None) => format!("* {}", text),
+                    (Some(Commands::Event { .. }), _) => task::format_event(time, &text),
+                    _ => text,
+                };
+                if *once
+                    && (entries.contains(&entry)
+                        || file::section_contains(&cfg.layout, &path, target, &entry, &lock)?)
+                {
+                    continue;
+                }
+                entries.push(entry);
+            }
+            if entries.is_empty() {
+                eprintln!("plan: already present; skipped");
+                return Err(silent_exit(3));
+            }
+            // A batch goes in as one block, so it is one write
+            backup::snapshot(&path, &cfg.backups)?;
+            file::insert_into_section(&cfg.layout, &path, target, &entries.join("\n"), &lock)?;
+            let command = match &cli.command {
+                Some(Commands::Log { .. }) => "log",
+                Some(Commands::Event { .. }) => "event",
+                _ => "jot",
+            };
+            undo::record_write(command, &path, before.as_deref());
+            warn_if_oversized(&cfg, &path);
+        }
+        Some(Commands::Ls {
+            limit,
+            all,
+            since,
+            format,
+            json,
+            porcelain,
+            group_by,
+        }) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'ls' command."));
+            }
+            let layout = match format {
+                Some(spec) => parse_ls_format(spec)?,
+                None => LsFormat::Columns(DEFAULT_LS_COLUMNS.to_vec()),
+            };
+            let since = match since {
+                Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                None => None,
+            };
+            let limit = match (limit, all, since) {
+                (Some(n), _, _) => *n,
+                (None, true, _) | (None, false, Some(_)) => usize::MAX,
+                (None, false, None) => cfg.ls.limit,
+            };
+
+            let extra = extra_dir_entries(&cfg)?;
+            let mut dirs = vec![(None, &plan_entries)];
+            dirs.extend(
+                extra
+                    .iter()
+                    .map(|(_, label, entries)| (Some(label.as_str()), entries)),
+            );
+            // A day and its continuation files are listed as one row. The
+            // same day in another directory gets its own row, after the
+            // plan directory's.
+            let mut days: BTreeMap<(chrono::NaiveDate, Reverse<usize>), Vec<&fs::DirEntry>> =
+                BTreeMap::new();
+            for (i, (_, entries)) in dirs.iter().enumerate() {
+                for entry in entries.iter() {
+                    let name = entry.file_name();
+                    if let Some((naive_date, _)) =
+                        file::plan_part(&cfg.layout, &name.to_string_lossy())
+                    {
+                        days.entry((naive_date, Reverse(i)))
+                            .or_default()
+                            .push(entry);
+                    }
+                }
+            }
+            let days: Vec<_> = days
+                .into_iter()
+                .rev()
+                .take_while(|((d, _), _)| since.is_none_or(|since| *d >= since))
+                .take(limit)
+                .collect();
+
+            // Read in parallel; collecting keeps the date-descending order
+            let counted = days
+                .par_iter()
+                .map(|((naive_date, Reverse(i)), entries)| -> io::Result<_> {
+                    let mut counts = stats::DayCounts::default();
+                    for entry in entries {
+                        counts.add(&fs::read_to_string(entry.path())?);
+                    }
+                    Ok((*naive_date, dirs[*i].0, counts))
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            if *json {
+                let days: Vec<serde_json::Value> = counted
+                    .iter()
+                    .map(|(naive_date, label, counts)| {
+                        serde_json::json!({
+                            "date": naive_date.format("%Y-%m-%d").to_string(),
+                            "weekday": naive_date.format("%a").to_string(),
+                            "lines": counts.lines,
+                            "open": counts.open,
+                            "done": counts.done,
+                            "files": counts.files,
+                            "dir": label,
+                        })
+                    })
+                    .collect();
+                print_json(serde_json::json!({
+                    "schema_version": schema::VERSION,
+                    "days": days,
+                }))?;
+                return Ok(());
+            }
+            if let Some(version) = porcelain {
+                for (naive_date, label, counts) in &counted {
+                    println!(
+                        "{}",
+                        porcelain::ls(version.version(), *naive_date, counts, *label)
+                    );
+                }
+                return Ok(());
+            }
+            // Rows arrive newest first, so a group ends when the label changes
+            let mut subtotal: Option<(String, stats::DayCounts)> = None;
+            for (naive_date, label, counts) in counted {
+                if let Some(group) = group_by {
+                    let label = group.label(naive_date);
+                    match &mut subtotal {
+                        Some((current, sum)) if *current == label => sum.merge(&counts),
+                        _ => {
+                            if let Some((current, sum)) = subtotal.take() {
+                                print_ls_subtotal(&cfg, &current, &sum);
+                                println!();
+                            }
+                            subtotal = Some((label, counts));
+                        }
+                    }
+                }
+                let mut row = layout.render(naive_date, &counts);
+                if format.is_none() && counts.files > 1 {
+                    row.push_str(&format!(" ({} files)", counts.files));
+                }
+                if let (None, Some(label)) = (format, label) {
+                    row.push_str(&format!(" [{}]", label));
+                }
+                println!("{}", row);
+            }
+            if let Some((current, sum)) = subtotal {
+                print_ls_subtotal(&cfg, &current, &sum);
+            }
+        }
+        Some(Commands::Show {
+            date,
+            stats: with_stats,
+            numbered,
+            section,
+            pretty,
+            ..
+        }) => {
+            let actual_date = date.as_deref().or(cli.date.as_deref());
+            if actual_date.is_some() && cli.last {
+                return Err(usage_err("Cannot use --last with a specific date."));
+            }
+
+            let paths = if let Some(path) = scratch_arg(&cfg, &names, actual_date) {
+                vec![path]
+            } else {
+                let naive_date = if cli.last {
+                    let Some(p) = latest_plan else {
+                        bail!("No plan files found in {}", cfg.dir.display());
+                    };
+                    p.file_name()
+                        .and_then(|n| file::plan_date(&cfg.layout, &n.to_string_lossy()))
+                        .context("Latest plan file has no date")?
+                } else {
+                    resolve_date_arg(&cfg, &names, base, actual_date)?
+                };
+                // A day's continuation files follow it as one document
+                date::day_paths(&cfg.layout, &cfg.dir, naive_date)
+            };
+            if paths.is_empty() {
+                return Err(silent_exit(2));
+            }
+            if path_only {
+                for path in &paths {
+                    println!("{}", path.display());
+                }
+                return Ok(());
+            }
+            let mut totals = stats::DocStats::default();
+            let mut number = 0;
+            let mut found_section = false;
+            for path in &paths {
+                let _lock = file::acquire_shared_lock(path, &cfg.lock)?;
+                let content = fs::read_to_string(path)?;
+                let painted = if *pretty {
+                    cfg.colors.render_plan(&content)
+                } else {
+                    cfg.colors.paint_plan(&content)
+                };
+                if !*numbered && section.is_none() {
+                    print!("{}", painted);
+                    totals.add(&cfg.layout, &content);
+                    continue;
+                }
+                // Only the section's body, when asked for one
+                let shown = match section {
+                    Some(name) => match section::find_section(&cfg.layout, &content, name) {
+                        Some(span) => span.body(),
+                        None => continue,
+                    },
+                    None => 0..usize::MAX,
+                };
+                found_section = true;
+                let task_lines: HashSet<usize> = task::parse_tasks(&content)
+                    .iter()
+                    .map(|t| t.line_number)
+                    .collect();
+                let mut shown_text = String::new();
+                for (i, (line, raw)) in painted.lines().zip(content.lines()).enumerate() {
+                    // Numbers count every task of the day, shown or not
+                    let is_task = task_lines.contains(&(i + 1));
+                    if is_task {
+                        number += 1;
+                    }
+                    if !shown.contains(&i) {
+                        continue;
+                    }
+                    if *numbered && is_task {
+                        let label = format!("[{}]", number);
+                        print!("{} ", cfg.colors.paint(Role::LineNumber, &label));
+                    }
+                    println!("{}", line);
+                    shown_text.push_str(raw);
+                    shown_text.push('\n');
+                }
+                totals.add(&cfg.layout, &shown_text);
+            }
+            if let Some(name) = section
+                && !found_section
+            {
+                bail!(
+                    "No section named '{}' in {}",
+                    name,
+                    paths
+                        .first()
+                        .and_then(|p| p.file_name())
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default()
+                );
+            }
+            if *with_stats {
+                let footer = format!(
+                    "-- {} words, ~{} min read, {} open, {} done, {} captures",
+                    totals.words,
+                    totals.reading_minutes(),
+                    totals.open,
+                    totals.done,
+                    totals.captures
+                );
+                println!("{}", cfg.colors.paint(Role::Header, &footer));
+            }
+        }
+        Some(Commands::Inbox { date, since, count }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'inbox' command.",
+                ));
+            }
+
+            let end =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let start = match since {
+                Some(arg) => resolve_date_arg(&cfg, &names, base, Some(arg))?,
+                None => end,
+            };
+            if start > end {
+                return Err(usage_err("--since must not be after the date."));
+            }
+            let mut items = Vec::new();
+            for day in start.iter_days().take_while(|d| *d <= end) {
+                items.extend(inbox_items(&cfg, day)?);
+            }
+            if *count {
+                println!("{}", items.len());
+            } else if since.is_none() {
+                for (_, _, line) in items {
+                    println!("{}", cfg.colors.paint_line(&line));
+                }
+            } else {
+                for (filename, line_number, line) in items {
+                    println!(
+                        "{}:{}: {}",
+                        cfg.colors.paint(Role::Filename, &filename),
+                        cfg.colors.paint(Role::LineNumber, &line_number.to_string()),
+                        cfg.colors.paint_line(&line)
+                    );
+                }
+            }
+        }
+        Some(Commands::Sweep { date, to }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'sweep' command.",
+                ));
+            }
+            if let Some(name) = to
+                && (name.eq_ignore_ascii_case("inbox")
+                    || section::open_marker_name(&format!("~{}~", name)).is_none())
+            {
+                return Err(usage_err(format!("Invalid section name '{}'.", name)));
+            }
+
+            let naive_date =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let mut swept = 0;
+            for path in date::day_paths(&cfg.layout, &cfg.dir, naive_date) {
+                let lock = file::acquire_lock(&path, &cfg.lock)?;
+                let before = fs::read_to_string(&path)?;
+                let snapshot = backup::snapshot(&path, &cfg.backups)?;
+                let moved = file::sweep_inbox(&cfg.layout, &path, to.as_deref(), &lock)?;
+                if moved > 0 {
+                    undo::record_write("sweep", &path, Some(&before));
+                } else if let Some(snapshot) = snapshot {
+                    // Nothing moved, so there is nothing to back up
+                    fs::remove_file(snapshot)?;
+                }
+                swept += moved;
+            }
+            match (swept, to) {
+                (0, _) => eprintln!("plan: the inbox is already empty"),
+                (n, Some(name)) => eprintln!("plan: swept {} item(s) into {}", n, name),
+                (n, None) => eprintln!("plan: swept {} item(s) below the separator", n),
+            }
+        }
+        Some(Commands::Continue { date }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'continue' command.",
+                ));
+            }
+
+            let naive_date =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            if !date::get_plan_path(&cfg.layout, &cfg.dir, naive_date).exists() {
+                return Err(usage_err(format!(
+                    "No plan file for that date: {}",
+                    date::format_filename(&cfg.layout, naive_date)
+                )));
+            }
+            let part = date::day_paths(&cfg.layout, &cfg.dir, naive_date).len() as u32 + 1;
+            let path = cfg
+                .dir
+                .join(date::format_part_filename(&cfg.layout, naive_date, part));
+            {
+                let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                if !path.exists() {
+                    file::write_atomic(
+                        &path,
+                        &date::generate_continuation_template(&cfg.layout, naive_date, part),
+                    )?;
+                }
+            }
+            eprintln!(
+                "plan: started {}",
+                date::format_part_filename(&cfg.layout, naive_date, part)
+            );
+            open_plan(&cfg, &path, None)?;
+        }
+        Some(Commands::Cat { dates }) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'cat' command."));
+            }
+
+            let targets = resolve_date_targets(&cfg, &names, base, &plan_entries, dates)?;
+
+            if print_days(&cfg, targets)? == 0 {
+                return Err(silent_exit(2));
+            }
+        }
+        Some(Commands::Attach {
+            action,
+            file: source,
+            date,
+            gc,
+            dry_run,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'attach' command.",
+                ));
+            }
+
+            if let Some(action) = action {
+                let (AttachAction::Ls { date } | AttachAction::Open { date, .. }) = action;
+                let day =
+                    resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+                let parts = date::day_paths(&cfg.layout, &cfg.dir, day);
+                if parts.is_empty() {
+                    return Err(usage_err(format!(
+                        "No plan file for that date: {}",
+                        date::format_filename(&cfg.layout, day)
+                    )));
+                }
+                let mut content = String::new();
+                for part in &parts {
+                    content.push_str(
+                        &fs::read_to_string(part)
+                            .context(format!("Error reading {}", part.display()))?,
+                    );
+                }
+                let assets = attach::referenced(&content);
+                match action {
+                    AttachAction::Ls { .. } => {
+                        if assets.is_empty() {
+                            eprintln!(
+                                "plan: no attachments in {}",
+                                date::format_filename(&cfg.layout, day)
+                            );
+                        }
+                        for (i, asset) in assets.iter().enumerate() {
+                            let size = match fs::metadata(cfg.dir.join(asset)) {
+                                Ok(meta) => opener::format_size(meta.len()),
+                                Err(_) => "missing".to_string(),
+                            };
+                            println!(
+                                "{:>3}  {:>9}  {:<8}  {}",
+                                i + 1,
+                                size,
+                                attach::kind(asset),
+                                asset
+                            );
+                        }
+                    }
+                    AttachAction::Open { target, .. } => {
+                        let asset = attach::pick(&assets, target).map_err(usage_err)?;
+                        let full = cfg.dir.join(asset);
+                        if !full.is_file() {
+                            return Err(usage_err(format!("No such file: {}", asset)));
+                        }
+                        opener::open_path(&full).context(format!("Error opening {}", asset))?;
+                    }
+                }
+                return Ok(());
+            }
+
+            if *gc {
+                // Dated and scratch files alike, plus the recurring rules
+                // and the template, whose links end up in days not yet written
+                let mut contents = Vec::new();
+                for entry in &plan_entries {
+                    contents.push(
+                        fs::read_to_string(entry.path())
+                            .context(format!("Error reading {}", entry.path().display()))?,
+                    );
+                }
+                let rules_path = plan::recur::rules_path(&cfg.dir);
+                for path in [rules_path.as_path(), cfg.template.as_path()] {
+                    match fs::read_to_string(path) {
+                        Ok(content) => contents.push(content),
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                        Err(e) => {
+                            return Err(e).context(format!("Error reading {}", path.display()));
+                        }
+                    }
+                }
+                for asset in attach::orphans(&cfg.dir, contents.iter().map(String::as_str))? {
+                    if !*dry_run {
+                        fs::remove_file(cfg.dir.join(&asset))
+                            .context(format!("Error removing {}", asset))?;
+                    }
+                    println!("{}", asset);
+                }
+                return Ok(());
+            }
+
+            let Some(source) = source else {
+                return Err(usage_err("Give a file to attach, or --gc."));
+            };
+            if !source.is_file() {
+                return Err(usage_err(format!("No such file: {}", source.display())));
+            }
+            let naive_date =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let path = date::current_plan_path(&cfg.layout, &cfg.dir, naive_date);
+            let lock = file::acquire_lock(&path, &cfg.lock)?;
+            let before = fs::read_to_string(&path).ok();
+            handle_file_exists(&cfg, &path, naive_date, cli.force)?;
+            let relative = attach::copy_in(&cfg.dir, source, naive_date)
+                .context(format!("Error copying {}", source.display()))?;
+            backup::snapshot(&path, &cfg.backups)?;
+            file::insert_into_inbox(
+                &cfg.layout,
+                &path,
+                &attach::reference(source, &relative),
+                &lock,
+            )?;
+            undo::record_write("attach", &path, before.as_deref());
+            println!("{}", relative);
+        }
+        Some(Commands::OnThisDay { date, months }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'onthisday' command.",
+                ));
+            }
+
+            let day =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let days: Vec<chrono::NaiveDate> = plan_entries
+                .iter()
+                .filter_map(|e| file::plan_date(&cfg.layout, &e.file_name().to_string_lossy()))
+                .collect();
+            if print_days(&cfg, date::on_this_day(day, &days, *months))? == 0 {
+                eprintln!(
+                    "plan: nothing written on {} in earlier years",
+                    day.format("%b %-d")
+                );
+            }
+        }
+        Some(Commands::Lint { dates }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'lint' command.",
+                ));
+            }
+
+            let mut paths: Vec<PathBuf> = if dates.is_empty() {
+                let mut entries: Vec<&fs::DirEntry> = plan_entries.iter().collect();
+                entries.sort_by_key(|e| e.file_name());
+                entries.iter().map(|e| e.path()).collect()
+            } else {
+                resolve_date_targets(&cfg, &names, base, &plan_entries, dates)?
+                    .into_iter()
+                    .flat_map(|day| date::day_paths(&cfg.layout, &cfg.dir, day))
+                    .collect()
+            };
+            paths.dedup();
+
+            let mut found = 0;
+            for path in paths {
+                let filename = path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+                let content = fs::read_to_string(&path)
+                    .context(format!("Error reading {}", path.display()))?;
+                let day = file::plan_part(&cfg.layout, &filename).map(|(day, _)| day);
+                for problem in lint::check(&cfg.layout, day, &content) {
+                    let location = if problem.line == 0 {
+                        cfg.colors.paint(Role::Filename, &filename)
+                    } else {
+                        format!(
+                            "{}:{}",
+                            cfg.colors.paint(Role::Filename, &filename),
+                            cfg.colors
+                                .paint(Role::LineNumber, &problem.line.to_string())
+                        )
+                    };
+                    println!("{}: {}", location, problem.message);
+                    found += 1;
+                }
+            }
+            if found > 0 {
+                return Err(silent_exit(1));
+            }
+        }
+        Some(Commands::Parse { date }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'parse' command.",
+                ));
+            }
+
+            let naive_date = resolve_date_arg(&cfg, &names, base, date.as_deref())?;
+            let paths = date::day_paths(&cfg.layout, &cfg.dir, naive_date);
+            if paths.is_empty() {
+                return Err(silent_exit(2));
+            }
+            let mut files = Vec::new();
+            for path in paths {
+                let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+                files.push(export::PlanFile {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    date: naive_date,
+                    content: fs::read_to_string(&path)?,
+                });
+            }
+            print_json(serde_json::json!({
+                "schema_version": schema::VERSION,
+                "files": export::to_json(&files),
+            }))?;
+        }
+        Some(Commands::Export { format, dates }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'export' command.",
+                ));
+            }
+
+            let targets = if dates.is_empty() {
+                plan_entries
+                    .iter()
+                    .filter_map(|e| file::plan_date(&cfg.layout, &e.file_name().to_string_lossy()))
+                    .collect()
+            } else {
+                resolve_date_targets(&cfg, &names, base, &plan_entries, dates)?
+            };
+            let mut files = Vec::new();
+            for naive_date in targets {
+                for path in date::day_paths(&cfg.layout, &cfg.dir, naive_date) {
+                    let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+                    files.push(export::PlanFile {
+                        name: path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned(),
+                        date: naive_date,
+                        content: fs::read_to_string(&path)?,
+                    });
+                }
+            }
+            match format {
+                ExportFormat::Markdown => print!("{}", export::to_markdown(&files)),
+                ExportFormat::Json => println!(
+                    "{}",
+                    serde_json::to_string_pretty(&export::to_json(&files))?
+                ),
+                ExportFormat::Csv => print!("{}", export::to_csv(&files)),
+                ExportFormat::TodoTxt => print!("{}", todotxt::to_todotxt(&files)),
+                ExportFormat::Ics => print!("{}", ics::to_ics(&files)),
+            }
+        }
+        Some(Commands::Import {
+            format,
+            file: source,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'import' command.",
+                ));
+            }
+
+            let content = if source == "-" {
+                io::read_to_string(io::stdin())?
+            } else {
+                fs::read_to_string(source).context(format!("Error reading {}", source))?
+            };
+            let entries: Vec<todotxt::Entry> = match format {
+                ImportFormat::TodoTxt => content.lines().filter_map(todotxt::parse_line).collect(),
+            };
+            let mut by_day: BTreeMap<chrono::NaiveDate, Vec<String>> = BTreeMap::new();
+            for entry in &entries {
+                by_day
+                    .entry(entry.day(base))
+                    .or_default()
+                    .push(entry.to_plan_line());
+            }
+            // Check every day before writing any, so a refusal leaves
+            // nothing half imported
+            let today = date::today();
+            let mut missing_past = Vec::new();
+            for day in by_day.keys() {
+                let path = date::current_plan_path(&cfg.layout, &cfg.dir, *day);
+                check_clock_skew(&cfg.layout, &path, *day, today, cli.force)?;
+                if *day < today && !path.exists() {
+                    missing_past.push(date::format_filename(&cfg.layout, *day));
+                }
+            }
+            if !missing_past.is_empty() && !cli.force {
+                return Err(usage_err(format!(
+                    "Refusing to create past plan files: {}. Pass --force to import into them.",
+                    missing_past.join(", ")
+                )));
+            }
+            let template = load_template(&cfg)?;
+            for (day, lines) in &by_day {
+                let path = date::current_plan_path(&cfg.layout, &cfg.dir, *day);
+                let lock = file::acquire_lock(&path, &cfg.lock)?;
+                let before = fs::read_to_string(&path).ok();
+                date::ensure_file_exists(&cfg.layout, template.as_deref(), &path, *day, false)
+                    .context("Error ensuring file exists")?;
+                backup::snapshot(&path, &cfg.backups)?;
+                for line in lines {
+                    file::insert_into_inbox(&cfg.layout, &path, line, &lock)?;
+                }
+                undo::record_write("import", &path, before.as_deref());
+            }
+            println!(
+                "Imported {} task{} into {} plan file{}.",
+                entries.len(),
+                if entries.len() == 1 { "" } else { "s" },
+                by_day.len(),
+                if by_day.len() == 1 { "" } else { "s" }
+            );
+        }
+        Some(Commands::Tasks {
+            json,
+            numbered,
+            porcelain,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'tasks' command.",
+                ));
+            }
+
+            let extra = extra_dir_entries(&cfg)?;
+            let mut dirs = vec![(None, &plan_entries)];
+            dirs.extend(
+                extra
+                    .iter()
+                    .map(|(_, label, entries)| (Some(label.as_str()), entries)),
+            );
+            // Tasks are numbered per day across its continuation files, the
+            // way 'plan check' counts them; other files are numbered alone.
+            // Each directory numbers its own days, so the plan directory's
+            // numbers are the ones 'plan check' takes.
+            let mut groups: BTreeMap<(usize, String), Vec<(u32, &fs::DirEntry)>> = BTreeMap::new();
+            for (i, (_, entries)) in dirs.iter().enumerate() {
+                for entry in entries.iter() {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let (key, part) = match file::plan_part(&cfg.layout, &name) {
+                        Some((naive_date, part)) => (naive_date.to_string(), part),
+                        None => (name, 1),
+                    };
+                    groups.entry((i, key)).or_default().push((part, entry));
+                }
+            }
+            let mut tasks = Vec::new();
+            for ((i, _), mut entries) in groups {
+                entries.sort_by_key(|(part, _)| *part);
+                let mut number = 0;
+                for (_, entry) in entries {
+                    let name = entry.file_name().to_string_lossy().into_owned();
+                    let content = fs::read_to_string(entry.path())
+                        .context(format!("Error reading {}", name))?;
+                    for t in task::parse_tasks(&content) {
+                        number += 1;
+                        if t.state.is_pending() {
+                            let line = content.lines().nth(t.line_number - 1).unwrap_or_default();
+                            tasks.push((t, (name.clone(), i), line.to_string(), number));
+                        }
+                    }
+                }
+            }
+            // Prioritized tasks first, then newest file first, then file order
+            tasks.sort_by(|(a, a_file, _, _), (b, b_file, _, _)| {
+                (a.priority.is_none(), a.priority)
+                    .cmp(&(b.priority.is_none(), b.priority))
+                    .then(b_file.0.cmp(&a_file.0))
+                    .then(a_file.1.cmp(&b_file.1))
+                    .then(a.line_number.cmp(&b.line_number))
+            });
+            let tasks: Vec<_> = tasks
+                .into_iter()
+                .map(|(t, (name, i), line, number)| (t, labeled(dirs[i].0, &name), line, number))
+                .collect();
+
+            if *json {
+                let tasks: Vec<serde_json::Value> = tasks
+                    .iter()
+                    .map(|(t, filename, _, number)| {
+                        serde_json::json!({
+                            "file": filename,
+                            "line": t.line_number,
+                            "number": number,
+                            "state": t.state.name(),
+                            "priority": t.priority.map(|p| p.letter().to_string()),
+                            "text": t.text,
+                        })
+                    })
+                    .collect();
+                print_json(serde_json::json!({
+                    "schema_version": schema::VERSION,
+                    "tasks": tasks,
+                }))?;
+                return Ok(());
+            }
+            if let Some(version) = porcelain {
+                for (t, filename, _, number) in &tasks {
+                    println!(
+                        "{}",
+                        porcelain::task(version.version(), filename, *number, t)
+                    );
+                }
+                return Ok(());
+            }
+            for (t, filename, line, number) in tasks {
+                let number = if *numbered {
+                    format!(
+                        "{} ",
+                        cfg.colors.paint(Role::LineNumber, &format!("[{}]", number))
+                    )
+                } else {
+                    String::new()
+                };
+                println!(
+                    "{}:{}: {}{}",
+                    cfg.colors.paint(Role::Filename, &filename),
+                    cfg.colors
+                        .paint(Role::LineNumber, &t.line_number.to_string()),
+                    number,
+                    cfg.colors.paint_line(&line)
+                );
+            }
+        }
+        Some(Commands::Tags) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'tags' command.",
+                ));
+            }
+
+            let mut counts = BTreeMap::new();
+            for entry in &plan_entries {
+                let content = fs::read_to_string(entry.path()).context(format!(
+                    "Error reading {}",
+                    entry.file_name().to_string_lossy()
+                ))?;
+                tags::count_tags(&content, &mut counts);
+            }
+            let mut by_count: Vec<(String, usize)> = counts.into_iter().collect();
+            by_count.sort_by(|(a_tag, a), (b_tag, b)| b.cmp(a).then(a_tag.cmp(b_tag)));
+            for (tag, count) in by_count {
+                println!("{:>5}  #{}", count, tag);
+            }
+        }
+        Some(
+            cmd @ (Commands::Links { range: args, .. }
+            | Commands::Contacts(args)
+            | Commands::Books(args)),
+        ) => {
+            let (kind, name) = match cmd {
+                Commands::Links { .. } => (annotations::Kind::Link, "links"),
+                Commands::Contacts(_) => (annotations::Kind::Contact, "contacts"),
+                _ => (annotations::Kind::Book, "books"),
+            };
+            if cli.last {
+                return Err(usage_err(format!(
+                    "--last is not supported with the '{}' command.",
+                    name
+                )));
+            }
+
+            let start = if args.week {
+                Some(base - chrono::Duration::days(base.weekday().num_days_from_monday().into()))
+            } else if args.month {
+                Some(base.with_day(1).unwrap_or(base))
+            } else if let Some(since) = &args.since {
+                Some(resolve_date_arg(&cfg, &names, base, Some(since))?)
+            } else {
+                None
+            };
+            let mut rows = Vec::new();
+            for entry in &plan_entries {
+                let filename = entry.file_name().to_string_lossy().into_owned();
+                let Some((day, part)) = file::plan_part(&cfg.layout, &filename) else {
+                    continue;
+                };
+                if start.is_some_and(|s| day < s) || (args.week || args.month) && day > base {
+                    continue;
+                }
+                let content = fs::read_to_string(entry.path())
+                    .context(format!("Error reading {}", filename))?;
+                for (i, line) in section::prose_lines(&content) {
+                    let found = annotations::extract(line);
+                    for a in found.iter().filter(|a| a.kind == kind) {
+                        rows.push((
+                            day,
+                            part,
+                            i,
+                            a.value.clone(),
+                            annotations::context(line, &found),
+                        ));
+                    }
+                }
+            }
+            // Newest day first, in file order within a day
+            rows.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+            if kind == annotations::Kind::Link {
+                // A link saved twice is listed where it was seen last
+                let mut seen = HashSet::new();
+                rows.retain(|row| seen.insert(row.3.clone()));
+            }
+            if let Commands::Links { open: Some(n), .. } = cmd {
+                let Some((_, _, _, url, _)) = n.checked_sub(1).and_then(|i| rows.get(i)) else {
+                    return Err(usage_err(format!(
+                        "No link {}; there are {} listed.",
+                        n,
+                        rows.len()
+                    )));
+                };
+                opener::open_path(Path::new(url)).context(format!("Error opening {}", url))?;
+                return Ok(());
+            }
+            let numbered = kind == annotations::Kind::Link;
+            for (i, (day, _, _, value, context)) in rows.into_iter().enumerate() {
+                let day = cfg
+                    .colors
+                    .paint(Role::Filename, &day.format("%Y-%m-%d").to_string());
+                // Links are numbered for --open
+                let number = if numbered {
+                    format!("{:>3}  ", i + 1)
+                } else {
+                    String::new()
+                };
+                if context.is_empty() {
+                    println!("{}{}  {}", number, day, value);
+                } else {
+                    println!("{}{}  {}  {}", number, day, value, context);
+                }
+            }
+        }
+        Some(Commands::Search {
+            query,
+            tag,
+            json,
+            count,
+            files_with_matches,
+            case_sensitive,
+            ignore_case,
+            porcelain,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'search' command.",
+                ));
+            }
+
+            let query = query.as_deref().unwrap_or_default();
+            let case = match (case_sensitive, ignore_case) {
+                (true, _) => search::Case::Sensitive,
+                (_, true) => search::Case::Insensitive,
+                _ => search::Case::Smart,
+            };
+            let ignore_case = case.ignores_case(query);
+            let search_dir = |dir: &Path, entries: &[fs::DirEntry]| -> Result<_> {
+                let external = match cfg.search.engine {
+                    config::SearchEngine::Ripgrep if dir.exists() && !query.is_empty() => {
+                        search::search_ripgrep(&cfg.layout, dir, query, ignore_case)
+                            .context("Error running ripgrep")?
+                    }
+                    config::SearchEngine::Index if dir.exists() => Some(
+                        index::search_indexed(
+                            &cfg.layout,
+                            &paths::cache_dir(),
+                            dir,
+                            entries,
+                            query,
+                            ignore_case,
+                        )
+                        .context("Error updating the search index")?,
+                    ),
+                    _ => None,
+                };
+                Ok(external.unwrap_or_else(|| {
+                    search::search_entries(&cfg.layout, entries, query, ignore_case)
+                }))
+            };
+            let mut matches = search_dir(&cfg.dir, &plan_entries)?;
+            let extra = extra_dir_entries(&cfg)?;
+            if !extra.is_empty() {
+                let mut all: Vec<_> = matches.into_iter().map(|m| (None, m)).collect();
+                for (dir, label, entries) in &extra {
+                    all.extend(
+                        search_dir(dir, entries)?
+                            .into_iter()
+                            .map(|m| (Some(label.as_str()), m)),
+                    );
+                }
+                all.sort_by(|(a_label, a), (b_label, b)| {
+                    file::newest_first_key(&cfg.layout, &a.filename)
+                        .cmp(&file::newest_first_key(&cfg.layout, &b.filename))
+                        .then(a_label.is_some().cmp(&b_label.is_some()))
+                        .then(a.line_number.cmp(&b.line_number))
+                });
+                matches = all
+                    .into_iter()
+                    .map(|(label, m)| search::SearchMatch {
+                        filename: labeled(label, &m.filename),
+                        ..m
+                    })
+                    .collect();
+            }
+            if let Some(tag) = tag {
+                matches.retain(|m| tags::has_tag(&m.line, tag));
+            }
+            if *json {
+                let matches: Vec<serde_json::Value> = matches
+                    .iter()
+                    .map(|m| {
+                        serde_json::json!({
+                            "file": m.filename,
+                            "line": m.line_number,
+                            "text": m.line,
+                        })
+                    })
+                    .collect();
+                print_json(serde_json::json!({
+                    "schema_version": schema::VERSION,
+                    "matches": matches,
+                }))?;
+                return Ok(());
+            }
+            if let Some(version) = porcelain {
+                for m in &matches {
+                    println!("{}", porcelain::search(version.version(), m));
+                }
+                return Ok(());
+            }
+            if *count || *files_with_matches {
+                // Files in the order their first match was found, like grep
+                let mut per_file: Vec<(String, usize)> = Vec::new();
+                for m in &matches {
+                    match per_file.iter_mut().find(|(name, _)| *name == m.filename) {
+                        Some((_, n)) => *n += 1,
+                        None => per_file.push((m.filename.clone(), 1)),
+                    }
+                }
+                for (name, n) in per_file {
+                    let name = cfg.colors.paint(Role::Filename, &name);
+                    if *count {
+                        println!("{}:{}", name, n);
+                    } else {
+                        println!("{}", name);
+                    }
+                }
+                return Ok(());
+            }
+
+            for m in matches {
+                println!(
+                    "{}:{}: {}",
+                    cfg.colors.paint(Role::Filename, &m.filename),
+                    cfg.colors
+                        .paint(Role::LineNumber, &m.line_number.to_string()),
+                    cfg.colors.paint_matches(&m.line, query, ignore_case)
+                );
+            }
+        }
+        Some(Commands::Backlinks {
+            date,
+            files_with_matches,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'backlinks' command.",
+                ));
+            }
+
+            let day =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let link = date::day_link(day);
+            let mut matches = search::search_entries(&cfg.layout, &plan_entries, &link, false);
+            for (_, label, entries) in extra_dir_entries(&cfg)? {
+                matches.extend(
+                    search::search_entries(&cfg.layout, &entries, &link, false)
+                        .into_iter()
+                        .map(|m| search::SearchMatch {
+                            filename: labeled(Some(&label), &m.filename),
+                            ..m
+                        }),
+                );
+            }
+            if matches.is_empty() {
+                eprintln!("plan: nothing links to {}", link);
+            }
+            let mut listed = HashSet::new();
+            for m in matches {
+                if !*files_with_matches {
+                    println!(
+                        "{}:{}: {}",
+                        cfg.colors.paint(Role::Filename, &m.filename),
+                        cfg.colors
+                            .paint(Role::LineNumber, &m.line_number.to_string()),
+                        cfg.colors.paint_matches(&m.line, &link, false)
+                    );
+                } else if listed.insert(m.filename.clone()) {
+                    println!("{}", cfg.colors.paint(Role::Filename, &m.filename));
+                }
+            }
+        }
+        Some(Commands::Replace {
+            old,
+            new,
+            since,
+            dry_run,
+            yes,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'replace' command.",
+                ));
+            }
+            if old.is_empty() {
+                return Err(usage_err("Search text cannot be empty."));
+            }
+            if old.contains('\n') || new.contains('\n') {
+                return Err(usage_err("Replacement text must be a single line."));
+            }
+            let since = match since {
+                Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                None => None,
+            };
+
+            plan_entries.sort_by_key(|e| e.file_name());
+            let mut targets = Vec::new();
+            for entry in &plan_entries {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if let Some(since) = since
+                    && file::plan_date(&cfg.layout, &name).is_none_or(|d| d < since)
+                {
+                    continue;
+                }
+                let content = fs::read_to_string(entry.path())?;
+                let changes = search::line_replacements(&content, old, new);
+                if changes.is_empty() {
+                    continue;
+                }
+                println!("--- {}", name);
+                println!("+++ {}", name);
+                for change in &changes {
+                    println!("@@ line {} @@", change.line_number);
+                    println!("-{}", change.before);
+                    println!("+{}", change.after);
+                }
+                targets.push(entry.path());
+            }
+
+            if targets.is_empty() {
+                eprintln!("plan: no matches for '{}'", old);
+                return Ok(());
+            }
+            if *dry_run {
+                return Ok(());
+            }
+            if !yes {
+                if !io::stdin().is_terminal() {
+                    return Err(usage_err(
+                        "Refusing to modify files without confirmation; pass --yes.",
+                    ));
+                }
+                if !confirm(&format!("Apply changes to {} file(s)?", targets.len()))? {
+                    return Err(silent_exit(1));
+                }
+            }
+
+            // Every file stays locked until the whole replace is recorded as
+            // one undo entry, taken in one order so writers can't deadlock
+            targets.sort();
+            let mut locks = Vec::new();
+            let mut befores = Vec::new();
+            for path in &targets {
+                locks.push(file::acquire_lock(path, &cfg.lock)?);
+                // Re-read under the lock in case the file changed since the preview
+                let content = fs::read_to_string(path)?;
+                backup::snapshot(path, &cfg.backups)?;
+                file::write_atomic(path, &content.replace(old.as_str(), new))?;
+                befores.push(content);
+            }
+            let written: Vec<(&Path, Option<&str>)> = targets
+                .iter()
+                .zip(&befores)
+                .map(|(path, before)| (path.as_path(), Some(before.as_str())))
+                .collect();
+            undo::record_writes("replace", &written);
+            eprintln!("plan: updated {} file(s)", targets.len());
+        }
+        Some(Commands::Name { date, name }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'name' command.",
+                ));
+            }
+            if date::parse_date_opt(Some(name)).is_ok() {
+                return Err(usage_err(format!(
+                    "Invalid name '{}': it already means a date.",
+                    name
+                )));
+            }
+            if !names::is_valid_name(name) {
+                return Err(usage_err(format!(
+                    "Invalid name '{}'. Use letters, digits, '-' or '_', starting with a letter.",
+                    name
+                )));
+            }
+            let naive_date = resolve_date_arg(&cfg, &names, base, Some(date))?;
+
+            let manifest = names::manifest_path(&cfg.dir);
+            let _lock = file::acquire_lock(&manifest, &cfg.lock)?;
+            // Reload under the lock so concurrent edits aren't lost
+            let mut names = names::Names::load(&cfg.dir)?;
+            if let Some(previous) = names.set(name, naive_date)
+                && previous != naive_date
+            {
+                eprintln!("plan: moved '{}' from {} to {}", name, previous, naive_date);
+            }
+            names.save(&cfg.dir)?;
+        }
+        Some(Commands::Names) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'names' command.",
+                ));
+            }
+            for (name, naive_date) in names.by_date() {
+                println!("{}  {}  {}", naive_date, naive_date.format("%a"), name);
+            }
+        }
+        Some(Commands::Mcp) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'mcp' command."));
+            }
+
+            let server = plan::mcp::Server { cfg: &cfg };
+            server
+                .run(io::stdin().lock(), io::stdout().lock())
+                .context("Error talking to the MCP client")?;
+        }
+        #[cfg(feature = "watch")]
+        Some(Commands::Watch { date, exec }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'watch' command.",
+                ));
+            }
+
+            let fixed = match date.as_deref().or(cli.date.as_deref()) {
+                Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                None => None,
+            };
+            let clear = exec.is_none() && io::stdout().is_terminal();
+            let refresh = || -> Result<bool> {
+                let naive_date = fixed.unwrap_or_else(date::today);
+                if let Some(command) = exec {
+                    let path = date::get_plan_path(&cfg.layout, &cfg.dir, naive_date);
+                    if let Err(e) = plan::watch::run_command(command, &path, naive_date) {
+                        diag::warn(Warning::HookFailed, &e.to_string());
+                    }
+                    return Ok(true);
+                }
+                if clear {
+                    // Clear the screen and move to the top-left corner
+                    print!("\x1b[2J\x1b[H");
+                }
+                let paths = date::day_paths(&cfg.layout, &cfg.dir, naive_date);
+                if paths.is_empty() {
+                    println!(
+                        "plan: no plan file for {} yet",
+                        date::format_filename(&cfg.layout, naive_date)
+                    );
+                }
+                for path in paths {
+                    let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+                    let content = fs::read_to_string(&path)
+                        .context(format!("Error reading {}", path.display()))?;
+                    print!("{}", cfg.colors.paint_plan(&content));
+                }
+                io::Write::flush(&mut io::stdout())?;
+                Ok(true)
+            };
+            plan::watch::watch(&cfg.layout, &cfg.dir, refresh)
+                .context(format!("Error watching {}", cfg.dir.display()))?;
+        }
+        #[cfg(feature = "watch")]
+        Some(Commands::Tail { date, lines }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'tail' command.",
+                ));
+            }
+
+            let fixed = match date.as_deref().or(cli.date.as_deref()) {
+                Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                None => None,
+            };
+            // What was last read, and from which day; None before the first read
+            let mut seen: Option<(chrono::NaiveDate, Vec<String>)> = None;
+            plan::watch::watch(&cfg.layout, &cfg.dir, || -> Result<bool> {
+                let naive_date = fixed.unwrap_or_else(date::today);
+                let current: Vec<String> = inbox_items(&cfg, naive_date)?
+                    .into_iter()
+                    .map(|(_, _, line)| line)
+                    .collect();
+                let shown = match &seen {
+                    None => current[current.len().saturating_sub(*lines)..].to_vec(),
+                    // A new day's inbox is all new
+                    Some((day, before)) if *day == naive_date => {
+                        plan::watch::new_lines(before, &current)
+                            .into_iter()
+                            .map(String::from)
+                            .collect()
+                    }
+                    Some(_) => current.clone(),
+                };
+                for line in shown {
+                    println!("{}", cfg.colors.paint_line(&line));
+                }
+                io::Write::flush(&mut io::stdout())?;
+                seen = Some((naive_date, current));
+                Ok(true)
+            })
+            .context(format!("Error watching {}", cfg.dir.display()))?;
+        }
+        #[cfg(unix)]
+        Some(Commands::Daemon { socket }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'daemon' command.",
+                ));
+            }
+
+            let socket = socket
+                .clone()
+                .unwrap_or_else(|| paths::socket_path(&cfg.dir));
+            plan::daemon::run(&cfg, &socket)
+                .context(format!("Error listening on {}", socket.display()))?;
+        }
+        #[cfg(feature = "serve")]
+        Some(Commands::Serve { addr }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'serve' command.",
+                ));
+            }
+
+            let addr = addr.as_deref().unwrap_or(&cfg.serve.addr);
+            plan::serve::run(&cfg, addr, cfg.serve.token.as_deref())
+                .context(format!("Error serving on {}", addr))?;
+        }
+        #[cfg(feature = "tui")]
+        Some(Commands::Tui) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'tui' command."));
+            }
+            if !io::stdout().is_terminal() {
+                return Err(usage_err("'plan tui' needs a terminal."));
+            }
+
+            plan::tui::run(&cfg, |path| {
+                open_plan(&cfg, path, None).map_err(io::Error::other)
+            })
+            .context("Error running the terminal UI")?;
+        }
+        #[cfg(feature = "tui")]
+        Some(Commands::Pick) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'pick' command.",
+                ));
+            }
+            if !io::stdout().is_terminal() {
+                return Err(usage_err("'plan pick' needs a terminal."));
+            }
+
+            let mut dated: Vec<(chrono::NaiveDate, std::path::PathBuf)> = plan_entries
+                .iter()
+                .filter_map(|e| {
+                    Some((
+                        file::plan_date(&cfg.layout, &e.file_name().to_string_lossy())?,
+                        e.path(),
+                    ))
+                })
+                .collect();
+            dated.sort_by_key(|(d, _)| Reverse(*d));
+            if dated.is_empty() {
+                bail!("No plan files found in {}", cfg.dir.display());
+            }
+
+            let by_date = names.by_date();
+            let labels = dated
+                .iter()
+                .map(|(naive_date, path)| {
+                    let mut label = format!("{}  {}", naive_date, naive_date.format("%a"));
+                    for (name, _) in by_date.iter().filter(|(_, d)| d == naive_date) {
+                        label.push_str("  ");
+                        label.push_str(name);
+                    }
+                    let content = fs::read_to_string(path).unwrap_or_default();
+                    let first =
+                        section::find_section(&cfg.layout, &content, "inbox").and_then(|s| {
+                            let body = s.body();
+                            content
+                                .lines()
+                                .skip(body.start)
+                                .take(body.len())
+                                .find(|l| !l.trim().is_empty())
+                        });
+                    if let Some(first) = first {
+                        label.push_str("  ");
+                        label.extend(first.trim().chars().take(50));
+                    }
+                    label
+                })
+                .collect();
+
+            let Some(choice) = plan::tui::pick(labels).context("Error running the picker")? else {
+                return Err(silent_exit(1));
+            };
+            open_plan(&cfg, &dated[choice].1, None)?;
+        }
+        Some(Commands::Recent { limit }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'recent' command.",
+                ));
+            }
+
+            for visit in history::recent(&state_dir)?.iter().take(*limit) {
+                // Files in the plan directory are shown by name, anything else in full
+                let shown = match visit.path.strip_prefix(&cfg.dir) {
+                    Ok(rel) => rel.display().to_string(),
+                    Err(_) => visit.path.display().to_string(),
+                };
+                println!(
+                    "{}  {:>3} opens  last {}",
+                    shown,
+                    visit.count,
+                    visit.last.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        Some(Commands::Scratch { name, action }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'scratch' command.",
+                ));
+            }
+            expire_scratch(&cfg, &plan_entries, cli.force)?;
+
+            match action {
+                Some(ScratchAction::Merge { name, date }) => {
+                    let Some(path) =
+                        scratch::path(&cfg.layout, &cfg.dir, name).filter(|p| p.exists())
+                    else {
+                        return Err(usage_err(format!("No scratch file named '{}'.", name)));
+                    };
+                    let day = resolve_date_arg(&cfg, &names, base, date.as_deref())?;
+                    let target = merge_scratch(&cfg, &path, name, day, cli.force)?;
+                    eprintln!(
+                        "plan: merged {} into {}",
+                        scratch::filename(&cfg.layout, name),
+                        target.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                }
+                None => {
+                    if !scratch::valid_name(name) {
+                        return Err(usage_err(format!(
+                            "Invalid scratch name '{}'. Use letters, digits, '-' and '_'.",
+                            name
+                        )));
+                    }
+                    // Names that read as dates could never be used in place of one
+                    if names.get(name).is_some()
+                        || date::parse_date_opt(Some(name)).is_ok()
+                        || chrono::NaiveDate::parse_from_str(name, "%Y-%m-%d").is_ok()
+                    {
+                        return Err(usage_err(format!(
+                            "'{}' already names a date; pick another scratch name.",
+                            name
+                        )));
+                    }
+                    let path = cfg.dir.join(scratch::filename(&cfg.layout, name));
+                    {
+                        let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                        if !path.exists() {
+                            file::write_atomic(&path, &scratch::template(&cfg.layout, name))?;
+                        }
+                    }
+                    open_plan(&cfg, &path, None)?;
+                }
+            }
+        }
+        Some(Commands::Index { action }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'index' command.",
+                ));
+            }
 
-            if let (Some(naive), Some(days)) = (target_date, days_ago) {
-                handle_file_exists(&path, naive, days)?;
+            match action {
+                IndexAction::Rebuild => {
+                    let index = index::rebuild(&paths::cache_dir(), &cfg.dir, &plan_entries)
+                        .context("Error rebuilding the search index")?;
+                    eprintln!("plan: indexed {} file(s)", index.len());
+                }
+            }
+        }
+        Some(Commands::CronTick) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'cron-tick' command.",
+                ));
             }
 
-            let final_text = if is_task {
-                format!("* {}", text)
-            } else {
-                text.to_string()
+            let now = date::now();
+            let exe = env::current_exe().context("Error locating the plan executable")?;
+            let mut failed = 0;
+            for rule in cfg.auto.iter().filter(|r| r.is_due(now)) {
+                if auto::was_applied(&state_dir, now.date(), &rule.name)? {
+                    continue;
+                }
+                let shown = shlex::try_join(rule.args.iter().map(String::as_str))
+                    .unwrap_or_else(|_| rule.args.join(" "));
+                eprintln!("plan: auto '{}': {}", rule.name, shown);
+                // Re-run ourselves so rules behave exactly like the typed command;
+                // --once keeps a rule from duplicating if the marker is lost
+                let status = ProcessCommand::new(&exe)
+                    .args(&rule.args)
+                    .arg("--once")
+                    .args(["--warnings", cli.warnings.name()])
+                    .args(cli.no_wait.then_some("--no-wait"))
+                    .args(cli.offline.then_some("--offline"))
+                    .env("PLAN_DIR", &cfg.dir)
+                    .status()
+                    .context(format!("Error running auto rule '{}'", rule.name))?;
+                match status.code() {
+                    Some(0) | Some(3) => auto::mark_applied(&state_dir, now.date(), &rule.name)?,
+                    _ => {
+                        diag::warn(
+                            Warning::AutoRuleFailed,
+                            &format!("auto rule '{}' failed ({})", rule.name, status),
+                        );
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(silent_exit(1));
+            }
+        }
+        Some(Commands::Review { days }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'review' command.",
+                ));
+            }
+
+            let today = date::today();
+            let (mut completed, mut dropped) = (0, 0);
+            let mut snoozed = Vec::new();
+            // Each day's continuation files are reviewed after it. Extra
+            // directories are never written, so they are left out.
+            'days: for offset in (0..*days).rev() {
+                let Some(day) = date::days_before(base, offset) else {
+                    continue;
+                };
+                for path in date::day_paths(&cfg.layout, &cfg.dir, day) {
+                    let content = match fs::read_to_string(&path) {
+                        Ok(c) => c,
+                        Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                        Err(e) => {
+                            return Err(e).context(format!("Error reading {}", path.display()));
+                        }
+                    };
+                    let pending: Vec<task::Task> = task::parse_tasks(&content)
+                        .into_iter()
+                        .filter(|t| t.state.is_pending())
+                        .collect();
+                    if pending.is_empty() {
+                        continue;
+                    }
+
+                    eprintln!(
+                        "==> {} <==",
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    );
+                    let mut decisions = Vec::new();
+                    let mut stop = false;
+                    for t in pending {
+                        let line = content.lines().nth(t.line_number - 1).unwrap_or_default();
+                        match ask_review_decision(line)? {
+                            Some(ReviewDecision::Keep) => {}
+                            Some(decision) => {
+                                decisions.push((t.line_number, line.to_string(), decision))
+                            }
+                            None => {
+                                stop = true;
+                                break;
+                            }
+                        }
+                    }
+                    if !decisions.is_empty() {
+                        for (_, _, decision) in &decisions {
+                            match decision {
+                                ReviewDecision::Complete => completed += 1,
+                                ReviewDecision::Drop => dropped += 1,
+                                ReviewDecision::Keep | ReviewDecision::Snooze => {}
+                            }
+                        }
+                        snoozed.extend(apply_review(&cfg, &path, &decisions, today)?);
+                    }
+                    if stop {
+                        break 'days;
+                    }
+                }
+            }
+
+            if !snoozed.is_empty() {
+                let tomorrow = today
+                    .succ_opt()
+                    .ok_or_else(|| usage_err("Date calculation is out of bounds."))?;
+                let path = date::get_plan_path(&cfg.layout, &cfg.dir, tomorrow);
+                let lock = file::acquire_lock(&path, &cfg.lock)?;
+                let before = fs::read_to_string(&path).ok();
+                handle_file_exists(&cfg, &path, tomorrow, cli.force)?;
+                backup::snapshot(&path, &cfg.backups)?;
+                for line in &snoozed {
+                    file::insert_into_inbox(&cfg.layout, &path, line, &lock)?;
+                }
+                undo::record_write("review", &path, before.as_deref());
+            }
+            eprintln!(
+                "plan: {} completed, {} dropped, {} snoozed",
+                completed,
+                dropped,
+                snoozed.len()
+            );
+        }
+        Some(Commands::Upcoming { days }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'upcoming' command.",
+                ));
+            }
+
+            let end = base
+                .checked_add_signed(chrono::Duration::days(*days as i64))
+                .ok_or_else(|| usage_err("Date calculation is out of bounds."))?;
+            let files: BTreeMap<chrono::NaiveDate, &fs::DirEntry> = plan_entries
+                .iter()
+                .filter_map(|e| {
+                    Some((
+                        file::plan_date(&cfg.layout, &e.file_name().to_string_lossy())?,
+                        e,
+                    ))
+                })
+                .filter(|(d, _)| *d > base && *d <= end)
+                .collect();
+            let rules = plan::recur::load(&cfg.dir).context(format!(
+                "Error reading {}",
+                plan::recur::rules_path(&cfg.dir).display()
+            ))?;
+
+            for naive_date in base.iter_days().skip(1).take_while(|d| *d <= end) {
+                // A day's file already holds the recurring entries it was
+                // created with; days without one show what they will get
+                let content;
+                let items: Vec<&str> = match files.get(&naive_date) {
+                    Some(entry) => {
+                        content = fs::read_to_string(entry.path()).context(format!(
+                            "Error reading {}",
+                            entry.file_name().to_string_lossy()
+                        ))?;
+                        task::scheduled_items(&cfg.layout, &content)
+                            .into_iter()
+                            .map(|(_, line)| line)
+                            .collect()
+                    }
+                    None => plan::recur::due_on(&rules, naive_date),
+                };
+                if items.is_empty() {
+                    continue;
+                }
+                let heading = format!("{}  {}", naive_date, naive_date.format("%a"));
+                println!("{}", cfg.colors.paint(Role::Header, &heading));
+                for line in items {
+                    println!("  {}", cfg.colors.paint_line(line));
+                }
+            }
+        }
+        Some(Commands::Standup { date }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'standup' command.",
+                ));
+            }
+
+            let today =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let mut files: Vec<(chrono::NaiveDate, u32, &fs::DirEntry)> = plan_entries
+                .iter()
+                .filter_map(|e| {
+                    let (day, part) =
+                        file::plan_part(&cfg.layout, &e.file_name().to_string_lossy())?;
+                    Some((day, part, e))
+                })
+                .filter(|(day, _, _)| *day <= today)
+                .collect();
+            files.sort_by_key(|(day, part, _)| (*day, *part));
+            let days: Vec<chrono::NaiveDate> = files.iter().map(|(day, _, _)| *day).collect();
+
+            let mut update = standup::Standup {
+                previous: standup::previous_working_day(&days, today),
+                ..Default::default()
             };
-            file::insert_into_inbox(&path, &final_text, &lock)?;
+            for (day, _, entry) in files {
+                let content = fs::read_to_string(entry.path()).context(format!(
+                    "Error reading {}",
+                    entry.file_name().to_string_lossy()
+                ))?;
+                for t in task::parse_tasks(&content) {
+                    let text = task::without_close_date(&t.text).to_string();
+                    let blocked = tags::has_tag(&t.text, standup::BLOCKED_TAG);
+                    if t.state.is_pending() && blocked {
+                        update.blocked.push(text);
+                    } else if t.state.is_pending() && day == today {
+                        update.today.push(text);
+                    } else if t.state == task::TaskState::Done && Some(day) == update.previous {
+                        update.done.push(text);
+                    }
+                }
+            }
+            print!("{}", standup::render(&update));
         }
-        Some(Commands::Ls) => {
+        Some(Commands::Rollup {
+            date,
+            week,
+            month: _,
+            curate,
+            print,
+        }) => {
             if cli.last {
-                return Err(usage_err("--last is not supported with the 'ls' command."));
+                return Err(usage_err(
+                    "--last is not supported with the 'rollup' command.",
+                ));
             }
 
-            plan_entries.sort_by_key(|e| e.file_name());
-            plan_entries.reverse();
+            let day =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let period = if *week {
+                rollup::Period::Week
+            } else {
+                rollup::Period::Month
+            };
+            let (start, end) = period.bounds(day);
+            let mut files: Vec<(chrono::NaiveDate, u32, &fs::DirEntry)> = plan_entries
+                .iter()
+                .filter_map(|e| {
+                    let (d, part) = file::plan_part(&cfg.layout, &e.file_name().to_string_lossy())?;
+                    Some((d, part, e))
+                })
+                .filter(|(d, _, _)| *d >= start && *d <= end)
+                .collect();
+            files.sort_by_key(|(d, part, _)| (*d, *part));
 
-            for entry in plan_entries.iter().take(30) {
-                let path = entry.path();
-                let name = entry.file_name().to_string_lossy().to_string();
-                let date_str = &name[..name.len() - 5];
-                if let Ok(parsed) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                    let day_of_week = parsed.format("%a").to_string();
-                    let content = fs::read_to_string(&path)?;
-                    let lines = content.lines().count();
-                    println!("{}  {}  {:>2} lines", date_str, day_of_week, lines);
+            let mut summary = rollup::Rollup {
+                title: period.title(day),
+                ..Default::default()
+            };
+            let mut candidates = Vec::new();
+            let mut last_day = None;
+            for (d, _, entry) in files {
+                let content = fs::read_to_string(entry.path()).context(format!(
+                    "Error reading {}",
+                    entry.file_name().to_string_lossy()
+                ))?;
+                if last_day != Some(d) {
+                    summary.days += 1;
+                    last_day = Some(d);
                 }
+                summary.counts.add(&content);
+                summary.completed.extend(
+                    task::parse_tasks(&content)
+                        .into_iter()
+                        .filter(|t| t.state == task::TaskState::Done)
+                        .filter_map(|t| content.lines().nth(t.line_number - 1))
+                        .map(str::to_string),
+                );
+                candidates.extend(rollup::highlight_candidates(d, &content));
+            }
+            summary.highlights = if *curate {
+                rollup::rank(&mut candidates);
+                rollup::curate(candidates, io::stdin().lock(), io::stderr())?
+            } else {
+                candidates.into_iter().filter(|c| c.score >= 2).collect()
+            };
+
+            let rendered = rollup::render(&summary);
+            if *print {
+                print!("{}", rendered);
+                return Ok(());
             }
+            let dir = cfg.rollup.dir(&cfg.dir);
+            fs::create_dir_all(&dir).context(format!("Error creating {}", dir.display()))?;
+            let path = dir.join(format!("{}.{}", period.stem(day), cfg.layout.extension()));
+            backup::snapshot(&path, &cfg.backups)?;
+            file::write_atomic(&path, &rendered)
+                .context(format!("Error writing {}", path.display()))?;
+            eprintln!("plan: wrote {}", path.display());
         }
-        Some(Commands::Show { date }) => {
-            let actual_date = date.as_deref().or(cli.date.as_deref());
-            if actual_date.is_some() && cli.last {
-                return Err(usage_err("Cannot use --last with a specific date."));
+        Some(Commands::Nag { email }) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'nag' command."));
             }
 
-            let path = if cli.last {
-                if let Some(p) = latest_plan {
-                    p
-                } else {
-                    bail!("No plan files found in {}", cfg.dir.display());
+            let mut items = Vec::new();
+            for entry in &plan_entries {
+                let filename = entry.file_name().to_string_lossy().into_owned();
+                let Some(day) = file::plan_date(&cfg.layout, &filename) else {
+                    continue;
+                };
+                let content = fs::read_to_string(entry.path())
+                    .context(format!("Error reading {}", filename))?;
+                for t in task::parse_tasks(&content) {
+                    if nag::wanted(&t, day, base, cfg.nag.include_inbox) {
+                        items.push(nag::Item {
+                            date: day,
+                            filename: filename.clone(),
+                            line_number: t.line_number,
+                            line: content
+                                .lines()
+                                .nth(t.line_number - 1)
+                                .unwrap_or_default()
+                                .to_string(),
+                        });
+                    }
                 }
+            }
+            let count = items.len();
+            let body = nag::render(&nag::group(items, base));
+            match email {
+                Some(_) if count == 0 => {}
+                Some(_) if cli.offline || cfg.offline => {
+                    eprintln!("plan: offline; not sending the nag email");
+                }
+                Some(to) => {
+                    let subject = format!(
+                        "plan: {} open task{} ({})",
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        base.format("%Y-%m-%d")
+                    );
+                    nag::send(&cfg.nag.sendmail, &nag::message(to, &subject, &body))
+                        .context("Error sending nag email")?;
+                }
+                None if count == 0 => println!("No open tasks."),
+                None => print!("{}", body),
+            }
+        }
+        Some(Commands::Sync { dry_run }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'sync' command.",
+                ));
+            }
+            if cli.offline || cfg.offline {
+                eprintln!("plan: offline; not syncing");
+                return Ok(());
+            }
+
+            let report = sync::sync(&cfg, &paths::data_dir(), *dry_run).context("Error syncing")?;
+            for name in &report.pulled {
+                println!("pull      {}", name);
+            }
+            for name in &report.pushed {
+                println!("push      {}", name);
+            }
+            for c in &report.conflicts {
+                println!("conflict  {} (remote copy: {})", c.file, c.copy);
+            }
+            if report == sync::Report::default() {
+                println!("Already in sync.");
+            }
+            if *dry_run {
+                eprintln!("plan: dry run; nothing was changed");
+            }
+        }
+        Some(Commands::Resolve { date }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'resolve' command.",
+                ));
+            }
+
+            let day = match date.as_deref().or(cli.date.as_deref()) {
+                Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                None => None,
+            };
+            let mut siblings = conflict::siblings(&cfg.layout, &cfg.dir)?;
+            if let Some(day) = day {
+                siblings.retain(|s| {
+                    s.original
+                        .file_name()
+                        .and_then(|n| file::plan_date(&cfg.layout, &n.to_string_lossy()))
+                        == Some(day)
+                });
+            }
+            if siblings.is_empty() {
+                eprintln!("plan: no conflict files to resolve");
+                return Ok(());
+            }
+            for sibling in &siblings {
+                let display = |p: &Path| {
+                    p.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned()
+                };
+                conflict::resolve(&cfg, sibling)
+                    .context(format!("Error resolving {}", display(&sibling.copy)))?;
+                eprintln!(
+                    "plan: merged {} into {}",
+                    display(&sibling.copy),
+                    display(&sibling.original)
+                );
+            }
+        }
+        Some(Commands::Check { number, date }) | Some(Commands::Uncheck { number, date }) => {
+            let checking = matches!(cli.command, Some(Commands::Check { .. }));
+            let name = if checking { "check" } else { "uncheck" };
+            if cli.last {
+                return Err(usage_err(format!(
+                    "--last is not supported with the '{}' command.",
+                    name
+                )));
+            }
+
+            let naive_date =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            // Every part stays locked until the write, so the numbering
+            // can't shift between finding the task and changing it
+            let mut locks = Vec::new();
+            let mut seen = 0;
+            let mut target = None;
+            for path in date::day_paths(&cfg.layout, &cfg.dir, naive_date) {
+                locks.push(file::acquire_lock(&path, &cfg.lock)?);
+                let content = fs::read_to_string(&path)
+                    .context(format!("Error reading {}", path.display()))?;
+                let tasks = task::parse_tasks(&content);
+                if let Some(t) = tasks.get(number.wrapping_sub(seen + 1)) {
+                    target = Some((t.line_number, path, content));
+                    break;
+                }
+                seen += tasks.len();
+            }
+            let Some((line_number, path, content)) = target else {
+                return Err(usage_err(format!(
+                    "{} has {} task(s); see 'plan show --number'.",
+                    naive_date.format("%Y-%m-%d"),
+                    seen
+                )));
+            };
+
+            let mut lines: Vec<&str> = content.lines().collect();
+            let Some(original) = line_number
+                .checked_sub(1)
+                .and_then(|i| lines.get(i))
+                .copied()
+            else {
+                bail!("Task {} is no longer in {}", number, path.display());
+            };
+            let changed = if checking {
+                task::close_task(original, task::TaskState::Done, date::get_date(0)?)
             } else {
-                let days_ago = parse_date_arg_or_error(actual_date)?;
-                let naive_date = date::get_date(days_ago).map_err(|e| usage_err(e.to_string()))?;
-                date::get_plan_path(&cfg.dir, naive_date)
+                task::reopen_task(original)
+            };
+            let Some(changed) = changed else {
+                eprintln!(
+                    "plan: task {} is already {}",
+                    number,
+                    if checking { "closed" } else { "open" }
+                );
+                return Ok(());
             };
+            lines[line_number - 1] = &changed;
+            let mut updated = lines.join("\n");
+            if content.ends_with('\n') {
+                updated.push('\n');
+            }
+            backup::snapshot(&path, &cfg.backups)?;
+            file::write_atomic(
+                &path,
+                &file::with_line_ending(&updated, file::line_ending(&content)),
+            )?;
+            undo::record_write(name, &path, Some(&content));
+            println!("{}", cfg.colors.paint_line(&changed));
+        }
+        Some(Commands::Template { action }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'template' command.",
+                ));
+            }
 
-            if !path.exists() {
-                return Err(silent_exit(2));
+            let template_path = &cfg.template;
+            let report = |problems: Vec<template::Problem>| {
+                for problem in &problems {
+                    let location = if problem.line == 0 {
+                        template_path.display().to_string()
+                    } else {
+                        format!("{}:{}", template_path.display(), problem.line)
+                    };
+                    diag::warn(
+                        Warning::ConfigProblem,
+                        &format!("{}: {}", location, problem.message),
+                    );
+                }
+                problems.is_empty()
+            };
+            match action {
+                TemplateAction::Show { date } => {
+                    let day = resolve_date_arg(
+                        &cfg,
+                        &names,
+                        base,
+                        date.as_deref().or(cli.date.as_deref()),
+                    )?;
+                    let template = load_template(&cfg)?;
+                    print!(
+                        "{}",
+                        date::generate_template(&cfg.layout, template.as_deref(), day)
+                    );
+                }
+                TemplateAction::Edit => {
+                    if !template_path.exists() {
+                        if let Some(parent) = template_path.parent() {
+                            fs::create_dir_all(parent)?;
+                        }
+                        fs::write(template_path, template::default_template(&cfg.layout))?;
+                    }
+                    open_editor(template_path, None, None)?;
+                    let content = fs::read_to_string(template_path)?;
+                    report(template::check(&cfg.layout, &content, base));
+                }
+                TemplateAction::Check => {
+                    let Some(content) = load_template(&cfg)? else {
+                        eprintln!(
+                            "plan: no template at {}; new files use the built-in layout",
+                            template_path.display()
+                        );
+                        return Ok(());
+                    };
+                    if !report(template::check(&cfg.layout, &content, base)) {
+                        return Err(silent_exit(1));
+                    }
+                }
             }
-            let _lock = file::acquire_shared_lock(&path)?;
-            let content = fs::read_to_string(&path)?;
-            print!("{}", content);
         }
-        Some(Commands::Search { query }) => {
+        Some(Commands::Undo) => {
             if cli.last {
                 return Err(usage_err(
-                    "--last is not supported with the 'search' command.",
+                    "--last is not supported with the 'undo' command.",
                 ));
             }
 
-            let q_lower = query.to_lowercase();
-            plan_entries.sort_by_key(|e| e.file_name());
-            plan_entries.reverse();
+            let name = |p: &Path| {
+                p.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            match undo::undo(&cfg, &paths::state_dir(), cli.force)? {
+                undo::Undone::Reverted(entry) => {
+                    let (restored, created): (Vec<_>, Vec<_>) =
+                        entry.files.iter().partition(|f| f.before.is_some());
+                    let names = |files: Vec<&undo::FileChange>| {
+                        files
+                            .iter()
+                            .map(|f| name(&f.path))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    };
+                    let mut message = format!("plan: undid '{}'", entry.command);
+                    if !restored.is_empty() {
+                        message += &format!(" in {}", names(restored));
+                    }
+                    if !created.is_empty() {
+                        message += &format!("; removed {}, which it created", names(created));
+                    }
+                    eprintln!("{}", message);
+                }
+                undo::Undone::Nothing => eprintln!("plan: nothing to undo"),
+            }
+        }
+        Some(Commands::Backups { action }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'backups' command.",
+                ));
+            }
 
-            for entry in plan_entries {
-                let path = entry.path();
-                let filename = entry.file_name().to_string_lossy().into_owned();
-                if let Ok(content) = fs::read_to_string(&path) {
-                    for (i, line) in content.lines().enumerate() {
-                        if line.to_lowercase().contains(&q_lower) {
-                            println!("{}:{}: {}", filename, i + 1, line);
+            match action {
+                BackupsAction::List { date } => {
+                    let day = match date {
+                        Some(arg) => Some(resolve_date_arg(&cfg, &names, base, Some(arg))?),
+                        None => None,
+                    };
+                    let mut files = backup::backed_up_files(&cfg.layout, &cfg.dir)?;
+                    if let Some(day) = day {
+                        files.retain(|f| file::plan_date(&cfg.layout, f) == Some(day));
+                    }
+                    if files.is_empty() {
+                        eprintln!("plan: no backups");
+                        return Ok(());
+                    }
+                    for name in files {
+                        println!("{}", cfg.colors.paint(Role::Filename, &name));
+                        for (i, b) in backup::list(&cfg.dir, &name)?.iter().enumerate() {
+                            let size = fs::metadata(&b.path).map(|m| m.len()).unwrap_or(0);
+                            println!(
+                                "  {:>2}  {}  {}",
+                                i + 1,
+                                b.taken.format("%Y-%m-%d %H:%M:%S"),
+                                opener::format_size(size)
+                            );
                         }
                     }
                 }
+                BackupsAction::Restore { date, number } => {
+                    let day = resolve_date_arg(&cfg, &names, base, Some(date))?;
+                    let path = date::get_plan_path(&cfg.layout, &cfg.dir, day);
+                    let name = date::format_filename(&cfg.layout, day);
+                    let backups = backup::list(&cfg.dir, &name)?;
+                    let Some(chosen) = number.checked_sub(1).and_then(|i| backups.get(i)) else {
+                        return Err(usage_err(format!(
+                            "{} has {} backup(s); see 'plan backups list {}'.",
+                            name,
+                            backups.len(),
+                            day.format("%Y-%m-%d")
+                        )));
+                    };
+                    let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                    let before = fs::read_to_string(&path).ok();
+                    let content = fs::read_to_string(&chosen.path)
+                        .context(format!("Error reading {}", chosen.path.display()))?;
+                    backup::snapshot(&path, &cfg.backups)?;
+                    file::write_atomic(&path, &content)?;
+                    undo::record_write("backups restore", &path, before.as_deref());
+                    eprintln!(
+                        "plan: restored {} from the backup taken {}",
+                        name,
+                        chosen.taken.format("%Y-%m-%d %H:%M:%S")
+                    );
+                }
+            }
+        }
+        Some(Commands::Stats { svg, months, json }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'stats' command.",
+                ));
+            }
+
+            let activity = stats::collect_activity(&cfg.layout, &plan_entries);
+            if let Some(target) = svg {
+                let end = base;
+                let start = stats::heatmap_start(end, *months);
+                let rendered = stats::render_heatmap_svg(&activity, start, end);
+                if target == "-" {
+                    print!("{}", rendered);
+                } else {
+                    fs::write(target, rendered).context(format!("Error writing {}", target))?;
+                }
+                return Ok(());
+            }
+
+            let Some(summary) = stats::summarize(&activity) else {
+                bail!("No plan files found in {}", cfg.dir.display());
+            };
+            if *json {
+                print_json(serde_json::json!({
+                    "schema_version": schema::VERSION,
+                    "days": summary.days,
+                    "lines": summary.lines,
+                    "first": summary.first.format("%Y-%m-%d").to_string(),
+                    "last": summary.last.format("%Y-%m-%d").to_string(),
+                    "longest_streak": summary.longest_streak,
+                }))?;
+                return Ok(());
+            }
+            println!("days:            {}", summary.days);
+            println!("lines:           {}", summary.lines);
+            println!("first:           {}", summary.first);
+            println!("last:            {}", summary.last);
+            println!("longest streak:  {} days", summary.longest_streak);
+        }
+        Some(Commands::Cal { months, tasks }) => {
+            if cli.last {
+                return Err(usage_err("--last is not supported with the 'cal' command."));
+            }
+
+            let activity = stats::collect_activity(&cfg.layout, &plan_entries);
+            let end = base;
+            let start = stats::heatmap_start(end, *months);
+            print!(
+                "{}",
+                stats::render_heatmap_text(&activity, start, end, *tasks, &cfg.colors)
+            );
+        }
+        Some(Commands::Gaps {
+            date,
+            since,
+            skip_weekends,
+        }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'gaps' command.",
+                ));
+            }
+
+            let written: HashSet<chrono::NaiveDate> = plan_entries
+                .iter()
+                .filter_map(|e| file::plan_part(&cfg.layout, &e.file_name().to_string_lossy()))
+                .map(|(naive_date, _)| naive_date)
+                .collect();
+            let end =
+                resolve_date_arg(&cfg, &names, base, date.as_deref().or(cli.date.as_deref()))?;
+            let start = match since {
+                Some(arg) => resolve_date_arg(&cfg, &names, base, Some(arg))?,
+                None => match written.iter().min() {
+                    Some(first) => *first,
+                    None => bail!("No plan files found in {}", cfg.dir.display()),
+                },
+            };
+            if start > end {
+                return Err(usage_err("--since must not be after the date."));
+            }
+            let skip_weekends = *skip_weekends || cfg.gaps.skip_weekends;
+            for day in start.iter_days().take_while(|d| *d <= end) {
+                let weekend = matches!(day.weekday(), chrono::Weekday::Sat | chrono::Weekday::Sun);
+                if written.contains(&day) || (skip_weekends && weekend) {
+                    continue;
+                }
+                println!("{}", day.format("%Y-%m-%d  %a"));
+            }
+        }
+        Some(Commands::Backfill { date, edit }) => {
+            if cli.last {
+                return Err(usage_err(
+                    "--last is not supported with the 'backfill' command.",
+                ));
+            }
+
+            let naive_date = resolve_date_arg(&cfg, &names, base, Some(date))?;
+            let path = date::current_plan_path(&cfg.layout, &cfg.dir, naive_date);
+            let name = date::format_filename(&cfg.layout, naive_date);
+            {
+                // Check under the lock so a concurrent write can't land between
+                // the check and the create
+                let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                if path.exists() {
+                    eprintln!("plan: {} already exists", name);
+                } else {
+                    date::ensure_file_exists(
+                        &cfg.layout,
+                        load_template(&cfg)?.as_deref(),
+                        &path,
+                        naive_date,
+                        false,
+                    )
+                    .context("Error ensuring file exists")?;
+                    undo::record_write("backfill", &path, None);
+                    eprintln!("plan: created {}", name);
+                }
             }
+            if *edit {
+                open_plan(&cfg, &path, None)?;
+            }
+        }
+        Some(Commands::Completions { .. })
+        | Some(Commands::Complete { .. })
+        | Some(Commands::Config { .. })
+        | Some(Commands::Schema { .. })
+        | Some(Commands::SelfTest) => {
+            unreachable!("handled before config load")
         }
+        Some(Commands::GrepToday { .. }) => unreachable!("handled before the directory scan"),
         None => {
             let actual_date = cli.date.as_deref();
             if actual_date.is_some() && cli.last {
                 return Err(usage_err("Cannot use --last with a specific date."));
             }
+            if cli.back && (actual_date.is_some() || cli.last) {
+                return Err(usage_err(
+                    "--back cannot be combined with a date or --last.",
+                ));
+            }
 
-            if cli.last {
+            if cli.back {
+                let Some(visit) = history::recent(&state_dir)?.into_iter().next() else {
+                    return Err(usage_err("No plan file has been opened yet."));
+                };
+                if !visit.path.exists() {
+                    bail!("{} no longer exists", visit.path.display());
+                }
+                if path_only {
+                    println!("{}", visit.path.display());
+                } else {
+                    open_plan(&cfg, &visit.path, cli.section.as_deref())?;
+                }
+            } else if cli.last {
                 if let Some(path) = latest_plan {
-                    if cli.path {
+                    let path = current_part(&cfg, path);
+                    warn_if_oversized(&cfg, &path);
+                    if path_only {
                         println!("{}", path.display());
                     } else {
-                        open_editor(&path)?;
+                        open_plan(&cfg, &path, cli.section.as_deref())?;
                     }
                 } else {
                     bail!("No plan files found in {}", cfg.dir.display());
                 }
             } else {
-                let days_ago = parse_date_arg_or_error(actual_date)?;
-                let naive_date = date::get_date(days_ago).map_err(|e| usage_err(e.to_string()))?;
-                let path = date::get_plan_path(&cfg.dir, naive_date);
+                let naive_date = resolve_date_arg(&cfg, &names, base, actual_date)?;
+                let path = date::current_plan_path(&cfg.layout, &cfg.dir, naive_date);
                 {
-                    let _lock = file::acquire_lock(&path)?;
-                    handle_file_exists(&path, naive_date, days_ago)?;
+                    let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                    handle_file_exists(&cfg, &path, naive_date, cli.force)?;
                 }
-                if cli.path {
+                warn_if_oversized(&cfg, &path);
+                if path_only {
                     println!("{}", path.display());
                 } else {
-                    open_editor(&path)?;
+                    open_plan(&cfg, &path, cli.section.as_deref())?;
                 }
             }
         }
@@ -349,21 +4609,107 @@ fn run() -> Result<()> {
     Ok(())
 }
 
+/// Dates to offer when completing a DATE: days with a plan file, newest
+/// first, then named dates. Empty when the plan directory isn't configured
+/// or can't be read, since completion has nowhere to report errors.
+fn completion_dates() -> Vec<String> {
+    let Some(dir) = config::configured_dir() else {
+        return Vec::new();
+    };
+    let Ok(cfg) = config::Config::load_in(Some(dir)) else {
+        return Vec::new();
+    };
+    let Ok(scan) = file::scan_plan_dir(&cfg.layout, &cfg.dir, &[]) else {
+        return Vec::new();
+    };
+    let days: std::collections::BTreeSet<chrono::NaiveDate> = scan
+        .plan_entries
+        .iter()
+        .filter_map(|e| file::plan_date(&cfg.layout, &e.file_name().to_string_lossy()))
+        .collect();
+    let mut dates: Vec<String> = days
+        .iter()
+        .rev()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect();
+    if let Ok(names) = names::Names::load(&cfg.dir) {
+        dates.extend(
+            names
+                .by_date()
+                .into_iter()
+                .map(|(name, _)| name.to_string()),
+        );
+    }
+    dates
+}
+
+/// The CLI for `COMPLETE=<shell> plan` dynamic completion: the usual
+/// command, with every DATE value completing from [`completion_dates`].
+fn completion_command() -> clap::Command {
+    fn add_dates(mut command: clap::Command) -> clap::Command {
+        let date_args: Vec<String> = command
+            .get_arguments()
+            .filter(|a| {
+                a.get_value_names()
+                    .is_some_and(|v| v.iter().any(|n| n == "DATE"))
+            })
+            .map(|a| a.get_id().to_string())
+            .collect();
+        for id in date_args {
+            command = command.mut_arg(id, |a| {
+                a.add(clap_complete::engine::ArgValueCandidates::new(|| {
+                    completion_dates()
+                        .into_iter()
+                        .map(clap_complete::CompletionCandidate::new)
+                        .collect()
+                }))
+            });
+        }
+        let subcommands: Vec<String> = command
+            .get_subcommands()
+            .map(|s| s.get_name().to_string())
+            .collect();
+        for name in subcommands {
+            command = command.mut_subcommand(name, add_dates);
+        }
+        command
+    }
+    add_dates(Cli::command())
+}
+
 fn main() {
-    if let Err(e) = run() {
-        if let Some(plan_err) = e.downcast_ref::<PlanError>() {
-            match plan_err {
-                PlanError::Usage(msg) => {
-                    eprintln!("plan: {}", msg);
-                    std::process::exit(2);
-                }
-                PlanError::SilentExit(code) => {
-                    std::process::exit(*code);
-                }
+    // Answers the shell and exits when COMPLETE is set
+    clap_complete::CompleteEnv::with_factory(completion_command).complete();
+    let invocation = match parse_args() {
+        Ok(invocation) => invocation,
+        Err(e) => exit_with_error(e, 1),
+    };
+    // grep reserves exit status 1 for "no match", so grep-today reports every
+    // failure as 2, including a bad config or plan directory
+    let failure = if matches!(invocation.cli.command, Some(Commands::GrepToday { .. })) {
+        2
+    } else {
+        1
+    };
+    if let Err(e) = run(invocation) {
+        exit_with_error(e, failure);
+    }
+}
+
+/// Report `e` and exit: 2 for usage errors, the given status for silent
+/// exits, and `failure` for anything else.
+fn exit_with_error(e: anyhow::Error, failure: i32) -> ! {
+    if let Some(plan_err) = e.downcast_ref::<PlanError>() {
+        match plan_err {
+            PlanError::Usage(msg) => {
+                eprintln!("plan: {}", msg);
+                std::process::exit(2);
+            }
+            PlanError::SilentExit(code) => {
+                std::process::exit(*code);
             }
-        } else {
-            eprintln!("Error: {:#}", e);
-            std::process::exit(1);
         }
     }
+    eprintln!("Error: {:#}", e);
+    std::process::exit(failure);
 }
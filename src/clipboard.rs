@@ -0,0 +1,75 @@
+use std::io;
+use std::process::{Command, Stdio};
+
+/// Commands that print the clipboard, tried in order: `pbpaste` on macOS,
+/// PowerShell on Windows, and elsewhere `wl-paste`, `xclip`, then `xsel`.
+fn paste_commands() -> Vec<Vec<&'static str>> {
+    if cfg!(target_os = "macos") {
+        vec![vec!["pbpaste"]]
+    } else if cfg!(windows) {
+        vec![vec![
+            "powershell",
+            "-NoProfile",
+            "-Command",
+            "Get-Clipboard",
+        ]]
+    } else {
+        vec![
+            vec!["wl-paste", "--no-newline"],
+            vec!["xclip", "-selection", "clipboard", "-o"],
+            vec!["xsel", "--clipboard", "--output"],
+        ]
+    }
+}
+
+/// Run one paste command and return what it printed.
+fn paste(program: &str, args: &[impl AsRef<std::ffi::OsStr>]) -> io::Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "'{}' exited with {}",
+            program, output.status
+        )));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "clipboard is not UTF-8 text"))
+}
+
+/// The clipboard's text. `command`, from `capture.clipboard`, replaces the
+/// platform commands; otherwise the first one installed is used.
+pub fn read(command: Option<&str>) -> io::Result<String> {
+    if let Some(command) = command {
+        let args = shlex::split(command).unwrap_or_default();
+        let Some((program, rest)) = args.split_first() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid clipboard command: '{}'", command),
+            ));
+        };
+        return paste(program, rest);
+    }
+    for args in paste_commands() {
+        match paste(args[0], &args[1..]) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            result => return result,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard command found; install one or set capture.clipboard",
+    ))
+}
+
+/// `text` as one inbox entry: a single line as it is, several lines inside
+/// a ``` fence so they stay together.
+pub fn fence(text: &str) -> String {
+    if text.contains('\n') {
+        format!("```\n{}\n```", text)
+    } else {
+        text.to_string()
+    }
+}
@@ -0,0 +1,348 @@
+use crate::task::{self, TaskState};
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+
+/// Semantic roles in terminal output, each mapped to a color by a [`Palette`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Header,
+    Section,
+    Inbox,
+    Open,
+    Done,
+    Cancelled,
+    Event,
+    Filename,
+    LineNumber,
+    Match,
+}
+
+impl Role {
+    pub const ALL: [Role; 10] = [
+        Role::Header,
+        Role::Section,
+        Role::Inbox,
+        Role::Open,
+        Role::Done,
+        Role::Cancelled,
+        Role::Event,
+        Role::Filename,
+        Role::LineNumber,
+        Role::Match,
+    ];
+
+    /// Name used for the role in the `[colors]` config section.
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Header => "header",
+            Role::Section => "section",
+            Role::Inbox => "inbox",
+            Role::Open => "open",
+            Role::Done => "done",
+            Role::Cancelled => "cancelled",
+            Role::Event => "event",
+            Role::Filename => "filename",
+            Role::LineNumber => "line_number",
+            Role::Match => "match",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.name() == name)
+    }
+}
+
+/// Built-in color schemes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    #[default]
+    Default,
+    /// Avoids red/green pairs: blue for done, orange for cancelled
+    Colorblind,
+    /// No colors at all
+    None,
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Theme::Default),
+            "colorblind" => Some(Theme::Colorblind),
+            "none" => Some(Theme::None),
+            _ => None,
+        }
+    }
+}
+
+const BASIC_COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// Parse a color spec into SGR parameters: a basic color name (`red`,
+/// `bright-blue`), `bold`, `dim`, `underline`, a 256-color index, or several
+/// of these joined with `+` (`bold+yellow`). `none` means uncolored.
+pub fn parse_color(spec: &str) -> Option<Option<String>> {
+    let spec = spec.trim().to_lowercase();
+    if spec == "none" {
+        return Some(None);
+    }
+    let params = spec
+        .split('+')
+        .map(|part| {
+            let part = part.trim();
+            if let Some(i) = BASIC_COLORS.iter().position(|c| *c == part) {
+                return Some((30 + i).to_string());
+            }
+            if let Some(name) = part.strip_prefix("bright-")
+                && let Some(i) = BASIC_COLORS.iter().position(|c| *c == name)
+            {
+                return Some((90 + i).to_string());
+            }
+            match part {
+                "bold" => Some("1".to_string()),
+                "dim" => Some("2".to_string()),
+                "underline" => Some("4".to_string()),
+                _ => part.parse::<u8>().ok().map(|n| format!("38;5;{}", n)),
+            }
+        })
+        .collect::<Option<Vec<String>>>()?;
+    Some(Some(params.join(";")))
+}
+
+/// SGR parameters for each [`Role`]. A disabled palette paints nothing.
+#[derive(Debug, Clone)]
+pub struct Palette {
+    colors: [Option<String>; Role::ALL.len()],
+    enabled: bool,
+    /// Syntax-highlight `--- :lang` code zones (off for the `none` theme)
+    #[cfg_attr(not(feature = "highlight"), allow(dead_code))]
+    highlight: bool,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::theme(Theme::Default)
+    }
+}
+
+impl Palette {
+    pub fn theme(theme: Theme) -> Self {
+        let specs: [&str; Role::ALL.len()] = match theme {
+            Theme::Default => ["1", "35", "36", "33", "32", "31", "34", "35", "32", "1;31"],
+            Theme::Colorblind => [
+                "1", "35", "36", "33", "34", "38;5;208", "35", "35", "34", "1;4",
+            ],
+            Theme::None => [""; Role::ALL.len()],
+        };
+        Self {
+            colors: specs.map(|s| Some(s.to_string()).filter(|s| !s.is_empty())),
+            enabled: true,
+            highlight: theme != Theme::None,
+        }
+    }
+
+    /// Override the color for one role; `None` leaves it uncolored.
+    pub fn set(&mut self, role: Role, sgr: Option<String>) {
+        self.colors[role as usize] = sgr;
+    }
+
+    /// SGR parameters configured for `role`, if it is colored at all.
+    pub fn sgr(&self, role: Role) -> Option<&str> {
+        self.colors[role as usize].as_deref()
+    }
+
+    /// Turn painting on or off, e.g. when stdout is not a terminal.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Wrap `text` in the escape codes for `role`.
+    pub fn paint(&self, role: Role, text: &str) -> String {
+        match &self.colors[role as usize] {
+            Some(sgr) if self.enabled && !text.is_empty() => {
+                format!("\x1b[{}m{}\x1b[0m", sgr, text)
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// Highlight every occurrence of `query` in `line`, ignoring case when
+    /// `ignore_case` is set.
+    pub fn paint_matches(&self, line: &str, query: &str, ignore_case: bool) -> String {
+        let (lower, q) = if ignore_case {
+            (line.to_lowercase(), query.to_lowercase())
+        } else {
+            (line.to_string(), query.to_string())
+        };
+        // Lowercasing can change byte lengths outside ASCII; don't guess offsets then
+        if !self.enabled || query.is_empty() || lower.len() != line.len() {
+            return line.to_string();
+        }
+        let mut out = String::new();
+        let mut last = 0;
+        for (start, _) in lower.match_indices(&q) {
+            if start < last || !line.is_char_boundary(start + q.len()) {
+                continue;
+            }
+            out.push_str(&line[last..start]);
+            out.push_str(&self.paint(Role::Match, &line[start..start + q.len()]));
+            last = start + q.len();
+        }
+        out.push_str(&line[last..]);
+        out
+    }
+
+    /// Color a single plan file line by what it records. Task and event
+    /// sigils are painted, not the text after them.
+    pub fn paint_line(&self, line: &str) -> String {
+        let role = if let Some(t) = task::parse_task(line, 0) {
+            match t.state {
+                TaskState::Inbox => Role::Inbox,
+                TaskState::Open => Role::Open,
+                TaskState::Done => Role::Done,
+                TaskState::Cancelled => Role::Cancelled,
+            }
+        } else if task::parse_event(line, 0).is_some() {
+            Role::Event
+        } else if crate::section::open_marker_name(line).is_some()
+            || crate::section::is_close_marker(line)
+        {
+            return self.paint(Role::Section, line);
+        } else {
+            return line.to_string();
+        };
+        let (sigil, rest) = line.split_at(1);
+        format!("{}{}", self.paint(role, sigil), rest)
+    }
+
+    /// Color a whole plan file: the header line and every line outside code
+    /// zones. Code zones in a known language are syntax highlighted.
+    pub fn paint_plan(&self, content: &str) -> String {
+        if !self.enabled {
+            return content.to_string();
+        }
+        let prose: HashSet<usize> = crate::section::prose_lines(content)
+            .map(|(i, _)| i)
+            .collect();
+        let code = self.highlight_zones(content);
+        let mut out = String::new();
+        for (i, line) in content.lines().enumerate() {
+            if is_header(i, line) {
+                out.push_str(&self.paint(Role::Header, line));
+            } else if let Some(highlighted) = code.get(&i) {
+                out.push_str(highlighted);
+            } else if prose.contains(&i) {
+                out.push_str(&self.paint_line(line));
+            } else {
+                out.push_str(line);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Render a whole plan file for reading rather than editing: the header
+    /// is underlined, sections are drawn as boxes and task and event sigils
+    /// become glyphs. Without painting the content is returned unchanged, so
+    /// piped output stays raw.
+    pub fn render_plan(&self, content: &str) -> String {
+        if !self.enabled {
+            return content.to_string();
+        }
+        let painted = self.paint_plan(content);
+        let prose: HashSet<usize> = crate::section::prose_lines(content)
+            .map(|(i, _)| i)
+            .collect();
+        let mut boxed = HashSet::new();
+        let mut closes = HashSet::new();
+        for span in crate::section::parse_sections(content) {
+            boxed.extend(span.body());
+            closes.extend(span.close);
+        }
+        let width = content
+            .lines()
+            .filter(|l| crate::section::is_close_marker(l))
+            .map(|l| l.trim().chars().count())
+            .max()
+            .unwrap_or(23);
+
+        let mut out = String::new();
+        for (i, (line, painted)) in content.lines().zip(painted.lines()).enumerate() {
+            if is_header(i, line) {
+                let rule = "═".repeat(line.chars().count());
+                out.push_str(&format!(
+                    "{}\n{}\n",
+                    painted,
+                    self.paint(Role::Header, &rule)
+                ));
+                continue;
+            }
+            let rendered = if closes.contains(&i) {
+                self.paint(Role::Section, &format!("└{}", "─".repeat(width - 1)))
+            } else if let Some(name) = crate::section::open_marker_name(line)
+                && prose.contains(&i)
+            {
+                let title = format!("┌─ {} ", name);
+                let fill = width.saturating_sub(title.chars().count()).max(1);
+                self.paint(Role::Section, &format!("{}{}", title, "─".repeat(fill)))
+            } else if line.starts_with("---") && line[3..].trim().is_empty() {
+                self.paint(Role::Section, &"─".repeat(width))
+            } else if prose.contains(&i) {
+                self.glyph_line(line).unwrap_or_else(|| painted.to_string())
+            } else {
+                painted.to_string()
+            };
+            if boxed.contains(&i) {
+                out.push_str(&self.paint(Role::Section, "│ "));
+            }
+            out.push_str(&rendered);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A task or event line with its sigil swapped for a painted glyph.
+    fn glyph_line(&self, line: &str) -> Option<String> {
+        let (role, glyph) = if let Some(t) = task::parse_task(line, 0) {
+            match t.state {
+                TaskState::Inbox => (Role::Inbox, "•"),
+                TaskState::Open => (Role::Open, "○"),
+                TaskState::Done => (Role::Done, "✓"),
+                TaskState::Cancelled => (Role::Cancelled, "✗"),
+            }
+        } else if task::parse_event(line, 0).is_some() {
+            (Role::Event, "◆")
+        } else {
+            return None;
+        };
+        Some(format!("{}{}", self.paint(role, glyph), &line[1..]))
+    }
+
+    /// Highlighted code zone lines by 0-based index.
+    #[cfg(feature = "highlight")]
+    fn highlight_zones(&self, content: &str) -> HashMap<usize, String> {
+        let mut code = HashMap::new();
+        if !self.highlight {
+            return code;
+        }
+        let lines: Vec<&str> = content.lines().collect();
+        for zone in crate::section::code_zones(content) {
+            if let Some(highlighted) =
+                crate::highlight::highlight_code(&zone.lang, &lines[zone.body.clone()])
+            {
+                code.extend(zone.body.zip(highlighted));
+            }
+        }
+        code
+    }
+
+    #[cfg(not(feature = "highlight"))]
+    fn highlight_zones(&self, _content: &str) -> HashMap<usize, String> {
+        HashMap::new()
+    }
+}
+
+/// Whether line `i` is the `2026, Feb 19 - Thursday` header of a plan file.
+fn is_header(i: usize, line: &str) -> bool {
+    i == 0 && NaiveDate::parse_from_str(line, "%Y, %b %d - %A").is_ok()
+}
@@ -1,7 +1,12 @@
+use crate::auto::{self, AutoRule};
+use crate::color::{self, Palette, Role, Theme};
+use crate::diag::Warning;
+use crate::layout::{self, Layout};
 use std::env;
 use std::fs;
 use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 pub struct ScanConfig {
     pub warn_unexpected: bool,
@@ -17,9 +22,218 @@ impl Default for ScanConfig {
     }
 }
 
+/// Which implementation `plan search` uses to scan plan files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchEngine {
+    #[default]
+    Builtin,
+    Ripgrep,
+    /// The persistent trigram index kept in the data directory
+    Index,
+}
+
+/// Where `plan sync` sends and fetches plan files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncBackend {
+    /// Commit, merge, and push with git; `sync.remote` names the git remote
+    Git,
+    /// Copy files with rsync; `sync.remote` is a path or `host:path`
+    Rsync,
+    /// Copy files with curl; `sync.remote` is the collection URL
+    WebDav,
+}
+
+/// How DATE arguments the built-in grammar rejects are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateParser {
+    /// Only the built-in grammar: `@~N`, `N days ago`, `YYYY-MM-DD`, names
+    #[default]
+    Builtin,
+    /// Fall back to free-form expressions such as "the 3rd of last month"
+    /// (needs the `natural-dates` feature)
+    Natural,
+}
+
+#[derive(Default)]
+pub struct DatesConfig {
+    pub parser: DateParser,
+}
+
+#[derive(Default)]
+pub struct SearchConfig {
+    pub engine: SearchEngine,
+}
+
+/// External commands run at points in a plan file's lifecycle.
+#[derive(Default)]
+pub struct HooksConfig {
+    /// Command whose stdout is appended the first time a file is opened in the editor.
+    pub first_open: Option<String>,
+}
+
+/// How plan files are handed to the editor.
+#[derive(Default)]
+pub struct EditorConfig {
+    /// Argument template for jumping to a line, e.g. `+{line}`.
+    pub line_arg: Option<String>,
+}
+
+/// Limits on individual plan files.
+#[derive(Default)]
+pub struct FilesConfig {
+    /// Size in bytes past which plan suggests starting a continuation file.
+    pub max_size: Option<u64>,
+}
+
+/// Defaults for `plan ls`.
+pub struct LsConfig {
+    /// How many days `plan ls` shows without --limit or --all.
+    pub limit: usize,
+}
+
+impl Default for LsConfig {
+    fn default() -> Self {
+        Self { limit: 30 }
+    }
+}
+
+/// Defaults for `plan gaps`.
+#[derive(Default)]
+pub struct GapsConfig {
+    /// Don't report Saturdays and Sundays as missing.
+    pub skip_weekends: bool,
+}
+
+/// Snapshots taken before plan files are changed.
+#[derive(Default)]
+pub struct BackupsConfig {
+    /// Snapshots kept per plan file; 0 turns backups off.
+    pub keep: usize,
+}
+
+/// How `log` and `jot` write new entries.
+#[derive(Default)]
+pub struct CaptureConfig {
+    /// Prefix entries with the time they were captured, as `HH:MM`.
+    pub timestamp: bool,
+    /// Command that prints the clipboard for `jot --clipboard`, in place of
+    /// the platform's own.
+    pub clipboard: Option<String>,
+}
+
+/// How long to wait for a plan file another process has locked.
+pub struct LockConfig {
+    pub timeout: Duration,
+}
+
+impl Default for LockConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// `plan rollup`, the weekly and monthly summaries.
+#[derive(Default)]
+pub struct RollupConfig {
+    /// Where rollup files are written; relative paths are inside the plan
+    /// directory. Defaults to `rollups` there.
+    pub dir: Option<PathBuf>,
+}
+
+impl RollupConfig {
+    pub fn dir(&self, plan_dir: &Path) -> PathBuf {
+        match &self.dir {
+            Some(dir) => plan_dir.join(dir),
+            None => plan_dir.join("rollups"),
+        }
+    }
+}
+
+/// Scratch files (`plan scratch`).
+#[derive(Default)]
+pub struct ScratchConfig {
+    /// Scratch files untouched for this many days are merged into today's file.
+    pub expire_days: Option<u32>,
+}
+
+/// `plan nag`, the open-task reminder.
+pub struct NagConfig {
+    /// Also nag about unprioritized inbox items left from earlier days.
+    pub include_inbox: bool,
+    /// Command that takes a complete message on stdin and sends it.
+    pub sendmail: String,
+}
+
+impl Default for NagConfig {
+    fn default() -> Self {
+        Self {
+            include_inbox: false,
+            sendmail: "sendmail -t".to_string(),
+        }
+    }
+}
+
+/// `plan serve`, the HTTP API.
+pub struct ServeConfig {
+    /// Address to listen on when --addr isn't given.
+    pub addr: String,
+    /// Bearer token every API request must carry, when set.
+    pub token: Option<String>,
+}
+
+impl Default for ServeConfig {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:8080".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// `plan sync`. Nothing is synced until a backend is chosen.
+#[derive(Default)]
+pub struct SyncConfig {
+    pub backend: Option<SyncBackend>,
+    pub remote: Option<String>,
+}
+
+/// Which warnings are silenced.
+#[derive(Default)]
+pub struct WarningsConfig {
+    pub suppress: Vec<Warning>,
+}
+
 pub struct Config {
     pub dir: PathBuf,
+    /// More plan directories that `ls`, `search`, and `tasks` read along
+    /// with `dir`, from repeated `extra_dirs` keys. Nothing writes to them.
+    pub extra_dirs: Vec<PathBuf>,
+    pub layout: Layout,
+    /// The template new plan files start from; see [`get_template_path`].
+    pub template: PathBuf,
+    /// Skip anything that would reach the network, e.g. emailing a nag.
+    pub offline: bool,
     pub scan: ScanConfig,
+    pub files: FilesConfig,
+    pub ls: LsConfig,
+    pub gaps: GapsConfig,
+    pub lock: LockConfig,
+    pub backups: BackupsConfig,
+    pub capture: CaptureConfig,
+    pub dates: DatesConfig,
+    pub scratch: ScratchConfig,
+    pub rollup: RollupConfig,
+    pub nag: NagConfig,
+    pub serve: ServeConfig,
+    pub sync: SyncConfig,
+    pub search: SearchConfig,
+    pub hooks: HooksConfig,
+    pub editor: EditorConfig,
+    pub colors: Palette,
+    pub auto: Vec<AutoRule>,
+    pub warnings: WarningsConfig,
 }
 
 /// Strip surrounding quotes from a value (handles both `"val"` and `'val'`).
@@ -35,29 +249,575 @@ fn strip_quotes(s: &str) -> &str {
 }
 
 /// Parse all `key = value` pairs from INI-style content.
-fn parse_ini(content: &str) -> Vec<(&str, &str)> {
+///
+/// Keys below a `[section]` header are qualified as `section.key`, so
+/// `[search]` followed by `engine = ripgrep` is equivalent to a top-level
+/// `search.engine = ripgrep`.
+fn parse_ini(content: &str) -> Vec<(String, &str)> {
+    let mut section: Option<&str> = None;
     content
         .lines()
         .filter_map(|line| {
             let line = line.trim();
-            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                section = header
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty());
                 return None;
             }
             let (key, val) = line.split_once('=')?;
-            Some((key.trim(), strip_quotes(val.trim())))
+            let key = match section {
+                Some(sec) => format!("{}.{}", sec, key.trim()),
+                None => key.trim().to_string(),
+            };
+            Some((key, strip_quotes(val.trim())))
         })
         .collect()
 }
 
-fn scan_config_from_pairs(pairs: &[(&str, &str)]) -> ScanConfig {
+/// Config file inside a plan directory whose keys override the global
+/// config for that directory, so the settings travel with it when synced.
+pub const LOCAL_CONFIG: &str = ".plan.conf";
+
+/// Keys a [`LOCAL_CONFIG`] may set: how files are named, laid out and
+/// scanned. Keys that run commands, reach the network, or point plan at
+/// other directories stay in the global config, so a synced directory can't
+/// make plan run what it carries.
+const LOCAL_KEYS: &[&str] = &[
+    "format",
+    "obsidian.inbox_heading",
+    "template",
+    "warn_unexpected",
+    "ignore",
+];
+
+/// Keys understood by [`Config::load`]; anything else is flagged by [`validate`].
+const KNOWN_KEYS: &[&str] = &[
+    "dir",
+    "offline",
+    "format",
+    "obsidian.inbox_heading",
+    "template",
+    "warn_unexpected",
+    "ignore",
+    "extra_dirs",
+    "search.engine",
+    "hooks.first_open",
+    "editor.line_arg",
+    "colors.theme",
+    "files.max_size",
+    "ls.limit",
+    "gaps.skip_weekends",
+    "lock.timeout",
+    "backups.keep",
+    "capture.timestamp",
+    "capture.clipboard",
+    "dates.parser",
+    "scratch.expire_days",
+    "rollup.dir",
+    "nag.include_inbox",
+    "nag.sendmail",
+    "serve.addr",
+    "serve.token",
+    "sync.backend",
+    "sync.remote",
+    "warnings.suppress",
+];
+
+/// A problem found in config file content, with its 1-based line number.
+pub struct ConfigProblem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Check config content for lines that [`Config::load`] would silently ignore
+/// or misread: malformed lines, unknown keys, and invalid values.
+pub fn validate(content: &str) -> Vec<ConfigProblem> {
+    validate_with(content, false)
+}
+
+/// Like [`validate`], for a [`LOCAL_CONFIG`] file, which can't move the
+/// directory it lives in.
+pub fn validate_local(content: &str) -> Vec<ConfigProblem> {
+    validate_with(content, true)
+}
+
+fn validate_with(content: &str, local: bool) -> Vec<ConfigProblem> {
+    let mut problems = Vec::new();
+    let mut section: Option<String> = None;
+    let mut has_dir = false;
+
+    for (i, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+        let mut problem = |message: String| {
+            problems.push(ConfigProblem {
+                line: i + 1,
+                message,
+            })
+        };
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            match header.strip_suffix(']').map(str::trim) {
+                Some(name) if !name.is_empty() => section = Some(name.to_string()),
+                _ => problem(format!("malformed section header '{}'", line)),
+            }
+            continue;
+        }
+        let Some((key, val)) = line.split_once('=') else {
+            problem(format!("expected 'key = value', found '{}'", line));
+            continue;
+        };
+        let key = match &section {
+            Some(sec) => format!("{}.{}", sec, key.trim()),
+            None => key.trim().to_string(),
+        };
+        let val = strip_quotes(val.trim());
+
+        match key.as_str() {
+            "dir" if local => problem(format!("'dir' has no effect in {}", LOCAL_CONFIG)),
+            k if local
+                && !LOCAL_KEYS.contains(&k)
+                && (KNOWN_KEYS.contains(&k)
+                    || k.starts_with("colors.")
+                    || k.starts_with("auto.")) =>
+            {
+                problem(format!(
+                    "'{}' has no effect in {}; only {} can be set there",
+                    k,
+                    LOCAL_CONFIG,
+                    LOCAL_KEYS.join(", ")
+                ))
+            }
+            "dir" => {
+                has_dir = true;
+                if val.is_empty() {
+                    problem("'dir' is empty".to_string());
+                }
+            }
+            "extra_dirs" if val.is_empty() => problem("'extra_dirs' is empty".to_string()),
+            "warn_unexpected" if !matches!(val, "true" | "false") => {
+                problem(format!(
+                    "'warn_unexpected' must be true or false, found '{}'",
+                    val
+                ));
+            }
+            "offline" if !matches!(val, "true" | "false") => {
+                problem(format!("'offline' must be true or false, found '{}'", val));
+            }
+            "format" if !matches!(val, "plan" | "obsidian") => {
+                problem(format!(
+                    "'format' must be plan or obsidian, found '{}'",
+                    val
+                ));
+            }
+            "obsidian.inbox_heading" if layout::parse_heading(val).is_none() => {
+                problem(format!(
+                    "'obsidian.inbox_heading' must be a Markdown heading such as '## Inbox', found '{}'",
+                    val
+                ));
+            }
+            "search.engine" if !matches!(val, "builtin" | "ripgrep" | "rg" | "index") => {
+                problem(format!(
+                    "'search.engine' must be builtin, ripgrep, or index, found '{}'",
+                    val
+                ));
+            }
+            "files.max_size" if parse_size(val).is_none() => {
+                problem(format!(
+                    "'files.max_size' must be a size such as 512K or 2M, found '{}'",
+                    val
+                ));
+            }
+            "warnings.suppress" => {
+                for name in val.split(',').filter(|n| !n.trim().is_empty()) {
+                    if Warning::from_name(name).is_none() {
+                        problem(format!(
+                            "unknown warning '{}' in 'warnings.suppress'",
+                            name.trim()
+                        ));
+                    }
+                }
+            }
+            "backups.keep" if val.parse::<usize>().is_err() => {
+                problem(format!(
+                    "'backups.keep' must be a number of snapshots, found '{}'",
+                    val
+                ));
+            }
+            "ls.limit" if val.parse::<usize>().is_err() => {
+                problem(format!("'ls.limit' must be a number, found '{}'", val));
+            }
+            "gaps.skip_weekends" if !matches!(val, "true" | "false") => {
+                problem(format!(
+                    "'gaps.skip_weekends' must be true or false, found '{}'",
+                    val
+                ));
+            }
+            "dates.parser" if !matches!(val, "builtin" | "natural") => {
+                problem(format!(
+                    "'dates.parser' must be builtin or natural, found '{}'",
+                    val
+                ));
+            }
+            "dates.parser" if val == "natural" && !cfg!(feature = "natural-dates") => {
+                problem(
+                    "'dates.parser = natural' needs plan built with the natural-dates feature"
+                        .to_string(),
+                );
+            }
+            "scratch.expire_days" if val.parse::<u32>().is_err() => {
+                problem(format!(
+                    "'scratch.expire_days' must be a number of days, found '{}'",
+                    val
+                ));
+            }
+            "capture.timestamp" if !matches!(val, "true" | "false") => {
+                problem(format!(
+                    "'capture.timestamp' must be true or false, found '{}'",
+                    val
+                ));
+            }
+            "nag.include_inbox" if !matches!(val, "true" | "false") => {
+                problem(format!(
+                    "'nag.include_inbox' must be true or false, found '{}'",
+                    val
+                ));
+            }
+            "capture.clipboard" if shlex::split(val).is_none_or(|args| args.is_empty()) => {
+                problem(format!(
+                    "'capture.clipboard' is not a valid command: '{}'",
+                    val
+                ));
+            }
+            "nag.sendmail" if shlex::split(val).is_none_or(|args| args.is_empty()) => {
+                problem(format!("'nag.sendmail' is not a valid command: '{}'", val));
+            }
+            "serve.addr" if val.parse::<std::net::SocketAddr>().is_err() => {
+                problem(format!(
+                    "'serve.addr' must be an address such as 127.0.0.1:8080, found '{}'",
+                    val
+                ));
+            }
+            "sync.backend" if !matches!(val, "git" | "rsync" | "webdav") => {
+                problem(format!(
+                    "'sync.backend' must be git, rsync, or webdav, found '{}'",
+                    val
+                ));
+            }
+            "sync.remote" if val.starts_with('-') => {
+                problem(format!(
+                    "'sync.remote' must not start with '-', found '{}'",
+                    val
+                ));
+            }
+            "lock.timeout" if parse_seconds(val).is_none() => {
+                problem(format!(
+                    "'lock.timeout' must be a number of seconds, found '{}'",
+                    val
+                ));
+            }
+            "editor.line_arg" if !val.contains("{line}") => {
+                problem("'editor.line_arg' must contain a {line} placeholder".to_string());
+            }
+            "colors.theme" if Theme::from_name(val).is_none() => {
+                problem(format!(
+                    "'colors.theme' must be default, colorblind, or none, found '{}'",
+                    val
+                ));
+            }
+            k if k != "colors.theme" && k.starts_with("colors.") => {
+                let role = &k["colors.".len()..];
+                if Role::from_name(role).is_none() {
+                    problem(format!("unknown color role '{}'", role));
+                } else if color::parse_color(val).is_none() {
+                    problem(format!("invalid color '{}' for '{}'", val, k));
+                }
+            }
+            k if k.starts_with("alias.") => {
+                if local {
+                    problem(format!(
+                        "aliases in {} have no effect; put '{}' in the global config",
+                        LOCAL_CONFIG, k
+                    ));
+                } else if shlex::split(val).is_none_or(|words| words.is_empty()) {
+                    problem(format!("'{}' is not a valid command: '{}'", k, val));
+                }
+            }
+            k if k.starts_with("auto.") => {
+                let name = &k["auto.".len()..];
+                if let Err(e) = auto::parse_rule(name, val) {
+                    problem(format!("invalid rule '{}': {}", k, e));
+                }
+            }
+            k if !KNOWN_KEYS.contains(&k) => problem(format!("unknown key '{}'", k)),
+            _ => {}
+        }
+    }
+
+    if !has_dir && !local {
+        problems.push(ConfigProblem {
+            line: 0,
+            message: "no 'dir' set; plan will prompt for one unless PLAN_DIR is set".to_string(),
+        });
+    }
+    problems
+}
+
+/// `format = obsidian` switches to Markdown daily notes; anything else keeps
+/// plan's own layout.
+fn layout_from_pairs(pairs: &[(String, &str)]) -> Layout {
+    let find = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+    match find("format") {
+        Some("obsidian") => Layout::Obsidian {
+            inbox_heading: find("obsidian.inbox_heading")
+                .filter(|h| layout::parse_heading(h).is_some())
+                .unwrap_or(layout::DEFAULT_INBOX_HEADING)
+                .to_string(),
+        },
+        _ => Layout::Plan,
+    }
+}
+
+fn search_config_from_pairs(pairs: &[(String, &str)]) -> SearchConfig {
+    let engine = match pairs
+        .iter()
+        .find(|(k, _)| k == "search.engine")
+        .map(|(_, v)| *v)
+    {
+        Some("ripgrep") | Some("rg") => SearchEngine::Ripgrep,
+        Some("index") => SearchEngine::Index,
+        _ => SearchEngine::Builtin,
+    };
+    SearchConfig { engine }
+}
+
+fn hooks_config_from_pairs(pairs: &[(String, &str)]) -> HooksConfig {
+    let first_open = pairs
+        .iter()
+        .find(|(k, _)| k == "hooks.first_open")
+        .map(|(_, v)| v.to_string())
+        .filter(|v| !v.is_empty());
+    HooksConfig { first_open }
+}
+
+fn editor_config_from_pairs(pairs: &[(String, &str)]) -> EditorConfig {
+    let line_arg = pairs
+        .iter()
+        .find(|(k, _)| k == "editor.line_arg")
+        .map(|(_, v)| v.to_string())
+        .filter(|v| !v.is_empty());
+    EditorConfig { line_arg }
+}
+
+/// Start from `colors.theme`, then apply per-role overrides. Invalid entries are
+/// ignored here and reported by [`validate`].
+fn colors_config_from_pairs(pairs: &[(String, &str)]) -> Palette {
+    let theme = pairs
+        .iter()
+        .find(|(k, _)| k == "colors.theme")
+        .and_then(|(_, v)| Theme::from_name(v))
+        .unwrap_or_default();
+    let mut palette = Palette::theme(theme);
+    for (key, val) in pairs {
+        if let Some(role) = key.strip_prefix("colors.").and_then(Role::from_name)
+            && let Some(sgr) = color::parse_color(val)
+        {
+            palette.set(role, sgr);
+        }
+    }
+    palette
+}
+
+/// Every valid `auto.NAME` rule, in file order. Invalid rules are skipped
+/// here and reported by [`validate`].
+fn auto_config_from_pairs(pairs: &[(String, &str)]) -> Vec<AutoRule> {
+    pairs
+        .iter()
+        .filter_map(|(k, v)| auto::parse_rule(k.strip_prefix("auto.")?, v).ok())
+        .collect()
+}
+
+/// Parse a byte count with an optional K, M, or G suffix (powers of 1024).
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (digits, multiplier) = match s.char_indices().last()? {
+        (i, 'k' | 'K') => (&s[..i], 1 << 10),
+        (i, 'm' | 'M') => (&s[..i], 1 << 20),
+        (i, 'g' | 'G') => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    digits
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .filter(|n| *n > 0)?
+        .checked_mul(multiplier)
+}
+
+fn files_config_from_pairs(pairs: &[(String, &str)]) -> FilesConfig {
+    let max_size = pairs
+        .iter()
+        .find(|(k, _)| k == "files.max_size")
+        .and_then(|(_, v)| parse_size(v));
+    FilesConfig { max_size }
+}
+
+/// Every recognized warning in `warnings.suppress` (a comma list of codes or
+/// names); unknown entries are reported by [`validate`].
+fn warnings_config_from_pairs(pairs: &[(String, &str)]) -> WarningsConfig {
+    let suppress = pairs
+        .iter()
+        .filter(|(k, _)| k == "warnings.suppress")
+        .flat_map(|(_, v)| v.split(','))
+        .filter_map(Warning::from_name)
+        .collect();
+    WarningsConfig { suppress }
+}
+
+fn ls_config_from_pairs(pairs: &[(String, &str)]) -> LsConfig {
+    pairs
+        .iter()
+        .find(|(k, _)| k == "ls.limit")
+        .and_then(|(_, v)| v.parse().ok())
+        .map(|limit| LsConfig { limit })
+        .unwrap_or_default()
+}
+
+fn gaps_config_from_pairs(pairs: &[(String, &str)]) -> GapsConfig {
+    GapsConfig {
+        skip_weekends: pairs
+            .iter()
+            .any(|(k, v)| k == "gaps.skip_weekends" && *v == "true"),
+    }
+}
+
+/// Parse a non-negative number of seconds, with an optional `s` suffix.
+fn parse_seconds(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let secs: f64 = s.strip_suffix('s').unwrap_or(s).trim().parse().ok()?;
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+fn dates_config_from_pairs(pairs: &[(String, &str)]) -> DatesConfig {
+    let parser = match pairs
+        .iter()
+        .find(|(k, _)| k == "dates.parser")
+        .map(|(_, v)| *v)
+    {
+        Some("natural") => DateParser::Natural,
+        _ => DateParser::Builtin,
+    };
+    DatesConfig { parser }
+}
+
+fn scratch_config_from_pairs(pairs: &[(String, &str)]) -> ScratchConfig {
+    let expire_days = pairs
+        .iter()
+        .find(|(k, _)| k == "scratch.expire_days")
+        .and_then(|(_, v)| v.parse().ok());
+    ScratchConfig { expire_days }
+}
+
+fn rollup_config_from_pairs(pairs: &[(String, &str)]) -> RollupConfig {
+    RollupConfig {
+        dir: pairs
+            .iter()
+            .find(|(k, v)| k == "rollup.dir" && !v.is_empty())
+            .map(|(_, v)| expand_tilde(v)),
+    }
+}
+
+fn nag_config_from_pairs(pairs: &[(String, &str)]) -> NagConfig {
+    let find = |key: &str| pairs.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+    let defaults = NagConfig::default();
+    NagConfig {
+        include_inbox: find("nag.include_inbox") == Some("true"),
+        sendmail: find("nag.sendmail")
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+            .unwrap_or(defaults.sendmail),
+    }
+}
+
+fn serve_config_from_pairs(pairs: &[(String, &str)]) -> ServeConfig {
+    let find = |key: &str| {
+        pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.to_string())
+            .filter(|v| !v.is_empty())
+    };
+    ServeConfig {
+        addr: find("serve.addr").unwrap_or_else(|| ServeConfig::default().addr),
+        token: find("serve.token"),
+    }
+}
+
+fn sync_config_from_pairs(pairs: &[(String, &str)]) -> SyncConfig {
+    let find = |key: &str| {
+        pairs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| *v)
+            .filter(|v| !v.is_empty())
+    };
+    let backend = match find("sync.backend") {
+        Some("git") => Some(SyncBackend::Git),
+        Some("rsync") => Some(SyncBackend::Rsync),
+        Some("webdav") => Some(SyncBackend::WebDav),
+        _ => None,
+    };
+    SyncConfig {
+        backend,
+        remote: find("sync.remote").map(str::to_string),
+    }
+}
+
+fn backups_config_from_pairs(pairs: &[(String, &str)]) -> BackupsConfig {
+    let keep = pairs
+        .iter()
+        .find(|(k, _)| k == "backups.keep")
+        .and_then(|(_, v)| v.parse().ok())
+        .unwrap_or_default();
+    BackupsConfig { keep }
+}
+
+fn capture_config_from_pairs(pairs: &[(String, &str)]) -> CaptureConfig {
+    CaptureConfig {
+        timestamp: pairs
+            .iter()
+            .any(|(k, v)| k == "capture.timestamp" && *v == "true"),
+        clipboard: pairs
+            .iter()
+            .find(|(k, _)| k == "capture.clipboard")
+            .map(|(_, v)| v.to_string()),
+    }
+}
+
+fn lock_config_from_pairs(pairs: &[(String, &str)]) -> LockConfig {
+    pairs
+        .iter()
+        .find(|(k, _)| k == "lock.timeout")
+        .and_then(|(_, v)| parse_seconds(v))
+        .map(|timeout| LockConfig { timeout })
+        .unwrap_or_default()
+}
+
+fn scan_config_from_pairs(pairs: &[(String, &str)]) -> ScanConfig {
     let warn = pairs
         .iter()
-        .find(|(k, _)| *k == "warn_unexpected")
+        .find(|(k, _)| k == "warn_unexpected")
         .map(|(_, v)| *v != "false")
         .unwrap_or(true);
     let ignored: Vec<String> = pairs
         .iter()
-        .filter(|(k, _)| *k == "ignore")
+        .filter(|(k, _)| k == "ignore")
         .map(|(_, v)| v.to_string())
         .collect();
     ScanConfig {
@@ -66,34 +826,120 @@ fn scan_config_from_pairs(pairs: &[(&str, &str)]) -> ScanConfig {
     }
 }
 
-fn config_from_pairs(pairs: &[(&str, &str)]) -> Option<Config> {
-    let dir = pairs.iter().find(|(k, _)| *k == "dir")?.1;
-    Some(Config {
-        dir: expand_tilde(dir),
+/// Build a config for `dir` with every section read from `pairs`.
+/// `template = PATH`, relative to the config file's directory, or the
+/// default next to the config file.
+fn template_from_pairs(pairs: &[(String, &str)]) -> PathBuf {
+    match pairs.iter().find(|(k, v)| k == "template" && !v.is_empty()) {
+        Some((_, path)) => match get_config_path().parent() {
+            Some(config_dir) => config_dir.join(expand_tilde(path)),
+            None => expand_tilde(path),
+        },
+        None => get_template_path(),
+    }
+}
+
+fn config_with_dir(dir: PathBuf, pairs: &[(String, &str)]) -> Config {
+    Config {
+        dir,
+        extra_dirs: pairs
+            .iter()
+            .filter(|(k, v)| k == "extra_dirs" && !v.is_empty())
+            .map(|(_, v)| expand_tilde(v))
+            .collect(),
+        layout: layout_from_pairs(pairs),
+        template: template_from_pairs(pairs),
+        offline: pairs.iter().any(|(k, v)| k == "offline" && *v == "true"),
         scan: scan_config_from_pairs(pairs),
-    })
+        files: files_config_from_pairs(pairs),
+        ls: ls_config_from_pairs(pairs),
+        gaps: gaps_config_from_pairs(pairs),
+        lock: lock_config_from_pairs(pairs),
+        backups: backups_config_from_pairs(pairs),
+        capture: capture_config_from_pairs(pairs),
+        dates: dates_config_from_pairs(pairs),
+        scratch: scratch_config_from_pairs(pairs),
+        rollup: rollup_config_from_pairs(pairs),
+        nag: nag_config_from_pairs(pairs),
+        serve: serve_config_from_pairs(pairs),
+        sync: sync_config_from_pairs(pairs),
+        search: search_config_from_pairs(pairs),
+        hooks: hooks_config_from_pairs(pairs),
+        editor: editor_config_from_pairs(pairs),
+        colors: colors_config_from_pairs(pairs),
+        auto: auto_config_from_pairs(pairs),
+        warnings: warnings_config_from_pairs(pairs),
+    }
+}
+
+/// `global` with every key that `local` sets replaced by the local values.
+/// Only [`LOCAL_KEYS`] are taken from `local`; the rest is ignored.
+fn merge_pairs<'a>(
+    global: &[(String, &'a str)],
+    local: &[(String, &'a str)],
+) -> Vec<(String, &'a str)> {
+    let local: Vec<&(String, &str)> = local
+        .iter()
+        .filter(|(k, _)| LOCAL_KEYS.contains(&k.as_str()))
+        .collect();
+    global
+        .iter()
+        .filter(|(k, _)| !local.iter().any(|(l, _)| l == k))
+        .chain(local.iter().copied())
+        .cloned()
+        .collect()
+}
+
+/// The config for `dir`: the global pairs, overridden by the directory's
+/// [`LOCAL_CONFIG`] when it has one. A local `template` is relative to
+/// `dir`.
+fn config_for_dir(dir: PathBuf, pairs: &[(String, &str)]) -> Config {
+    let local_content = fs::read_to_string(dir.join(LOCAL_CONFIG)).ok();
+    let local: Vec<(String, &str)> = local_content.as_deref().map(parse_ini).unwrap_or_default();
+    let local_template = local
+        .iter()
+        .find(|(k, v)| k == "template" && !v.is_empty())
+        .map(|(_, path)| dir.join(expand_tilde(path)));
+    let mut cfg = config_with_dir(dir, &merge_pairs(pairs, &local));
+    if let Some(template) = local_template {
+        cfg.template = template;
+    }
+    cfg
 }
 
 impl Config {
     pub fn load() -> io::Result<Self> {
+        Self::load_in(None)
+    }
+
+    /// The built-in defaults for plan files in `dir`, without reading any
+    /// config file.
+    pub fn with_defaults(dir: PathBuf) -> Self {
+        config_with_dir(dir, &[])
+    }
+
+    /// Like [`Config::load`], but for `dir` instead of the configured
+    /// directory when one is given (`--dir`).
+    pub fn load_in(dir: Option<PathBuf>) -> io::Result<Self> {
         // Load config file content (if it exists) for scan settings
         let config_path = get_config_path();
         let config_content = fs::read_to_string(&config_path).ok();
-        let pairs: Vec<(&str, &str)> = config_content.as_deref().map(parse_ini).unwrap_or_default();
+        let pairs: Vec<(String, &str)> =
+            config_content.as_deref().map(parse_ini).unwrap_or_default();
 
-        // 1. Env var overrides directory
+        // 1. An explicit directory, then the env var, override the config
+        if let Some(dir) = dir {
+            return Ok(config_for_dir(dir, &pairs));
+        }
         if let Ok(dir) = env::var("PLAN_DIR")
             && !dir.is_empty()
         {
-            return Ok(Self {
-                dir: expand_tilde(&dir),
-                scan: scan_config_from_pairs(&pairs),
-            });
+            return Ok(config_for_dir(expand_tilde(&dir), &pairs));
         }
 
         // 2. Config file
-        if let Some(cfg) = config_from_pairs(&pairs) {
-            return Ok(cfg);
+        if let Some((_, dir)) = pairs.iter().find(|(k, _)| k == "dir") {
+            return Ok(config_for_dir(expand_tilde(dir), &pairs));
         }
 
         // 3. Prompt on first run
@@ -114,10 +960,7 @@ impl Config {
         }
         fs::write(&config_path, format!("dir = {dir_str}\n"))?;
 
-        Ok(Self {
-            dir: dir_path,
-            scan: ScanConfig::default(),
-        })
+        Ok(config_with_dir(dir_path, &[]))
     }
 
     pub fn init(dir_str: &str) -> io::Result<Self> {
@@ -128,13 +971,39 @@ impl Config {
             fs::create_dir_all(parent)?;
         }
         fs::write(&config_path, format!("dir = {dir_str}\n"))?;
-        Ok(Self {
-            dir: dir_path,
-            scan: ScanConfig::default(),
-        })
+        Ok(config_with_dir(dir_path, &[]))
     }
 }
 
+/// The plan directory from `PLAN_DIR` or the config file, or `None` where
+/// [`Config::load`] would prompt for one. For shell completion, which must
+/// never stop to ask.
+pub fn configured_dir() -> Option<PathBuf> {
+    if let Ok(dir) = env::var("PLAN_DIR")
+        && !dir.is_empty()
+    {
+        return Some(expand_tilde(&dir));
+    }
+    let content = fs::read_to_string(get_config_path()).ok()?;
+    parse_ini(&content)
+        .into_iter()
+        .find(|(k, _)| k == "dir")
+        .map(|(_, dir)| expand_tilde(dir))
+}
+
+/// `[alias]` entries from the global config file as (name, expansion).
+/// They are read before the command line is parsed, so a plan directory's
+/// own config can't add any.
+pub fn aliases() -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(get_config_path()) else {
+        return Vec::new();
+    };
+    parse_ini(&content)
+        .into_iter()
+        .filter_map(|(k, v)| Some((k.strip_prefix("alias.")?.to_string(), v.to_string())))
+        .collect()
+}
+
 pub fn get_config_path() -> PathBuf {
     if let Ok(xdg) = env::var("XDG_CONFIG_HOME")
         && !xdg.is_empty()
@@ -142,20 +1011,61 @@ pub fn get_config_path() -> PathBuf {
         return PathBuf::from(xdg).join("plan").join("config");
     }
 
-    // fallback to ~/.config/plan/config
-    let mut path = expand_tilde("~/.config");
+    // fallback to ~/.config/plan/config, or %APPDATA%\plan\config on Windows
+    let mut path = platform_dir(dirs::config_dir, "~/.config");
     path.push("plan");
     path.push("config");
     path
 }
 
+/// The template new plan files start from, next to the config file.
+pub fn get_template_path() -> PathBuf {
+    get_config_path().with_file_name("template")
+}
+
+/// The user's home directory. `HOME` wins when set, so it can be overridden
+/// on every platform; Windows then falls back to `USERPROFILE` and finally
+/// the known-folder lookup.
+pub fn home_dir() -> Option<PathBuf> {
+    let from_env = |name| {
+        env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+    from_env("HOME")
+        .or_else(|| {
+            if cfg!(windows) {
+                from_env("USERPROFILE")
+            } else {
+                None
+            }
+        })
+        .or_else(dirs::home_dir)
+}
+
+/// A well-known per-user directory. Unix keeps the XDG layout under the home
+/// directory so `HOME` overrides keep working; Windows asks the OS.
+pub(crate) fn platform_dir(windows: fn() -> Option<PathBuf>, unix: &str) -> PathBuf {
+    if cfg!(windows)
+        && let Some(dir) = windows()
+    {
+        return dir;
+    }
+    expand_tilde(unix)
+}
+
 pub fn expand_tilde(path: &str) -> PathBuf {
-    if (path.starts_with("~/") || path == "~")
-        && let Ok(home) = env::var("HOME")
+    let rest = if path == "~" {
+        Some("")
+    } else {
+        path.strip_prefix("~/")
+            .or_else(|| path.strip_prefix("~\\").filter(|_| cfg!(windows)))
+    };
+    if let Some(rest) = rest
+        && let Some(mut buf) = home_dir()
     {
-        let mut buf = PathBuf::from(home);
-        if path.len() > 2 {
-            buf.push(&path[2..]);
+        if !rest.is_empty() {
+            buf.push(rest);
         }
         return buf;
     }
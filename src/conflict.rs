@@ -0,0 +1,116 @@
+use crate::file;
+use crate::layout::Layout;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Marker that Syncthing (and `plan sync`) put in the name of a conflict
+/// copy: `2026-02-19.sync-conflict-20260219-101500-ABCDEFG.plan`.
+const MARKER: &str = ".sync-conflict-";
+
+/// The plan file a conflict copy belongs to, e.g. `2026-02-19.plan` for
+/// `2026-02-19.sync-conflict-20260219-101500-ABCDEFG.plan`.
+pub fn original_name(layout: &Layout, name: &str) -> Option<String> {
+    let (stem, rest) = name.split_once(MARKER)?;
+    let (_, ext) = rest.rsplit_once('.')?;
+    let original = format!("{}.{}", stem, ext);
+    file::is_plan_file(layout, &original).then_some(original)
+}
+
+/// A conflict copy and the plan file it should be merged into.
+pub struct Sibling {
+    pub original: PathBuf,
+    pub copy: PathBuf,
+}
+
+/// Every conflict copy in `dir`, oldest copy first for each plan file, so
+/// merging them in order replays the conflicts as they happened.
+pub fn siblings(layout: &Layout, dir: &Path) -> crate::Result<Vec<Sibling>> {
+    let mut found: Vec<(String, String)> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            Some((original_name(layout, &name)?, name))
+        })
+        .collect();
+    found.sort();
+    Ok(found
+        .into_iter()
+        .map(|(original, copy)| Sibling {
+            original: dir.join(original),
+            copy: dir.join(copy),
+        })
+        .collect())
+}
+
+/// Merge two versions of a file line by line, keeping every line from
+/// both. Lines the versions share anchor the merge; where they differ,
+/// `ours` comes first, then whatever `theirs` adds that `ours` doesn't
+/// already have at that spot. Two devices adding to the same inbox
+/// therefore keep both sets of additions.
+pub fn union_merge(ours: &str, theirs: &str) -> String {
+    let a: Vec<&str> = ours.lines().collect();
+    let b: Vec<&str> = theirs.lines().collect();
+
+    // lcs[i][j]: length of the longest common subsequence of a[i..], b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out: Vec<&str> = Vec::with_capacity(a.len().max(b.len()));
+    let (mut i, mut j) = (0, 0);
+    let mut hunk_ours: Vec<&str> = Vec::new();
+    let mut hunk_theirs: Vec<&str> = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            flush(&mut out, &mut hunk_ours, &mut hunk_theirs);
+            out.push(a[i]);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            hunk_ours.push(a[i]);
+            i += 1;
+        } else {
+            hunk_theirs.push(b[j]);
+            j += 1;
+        }
+    }
+    flush(&mut out, &mut hunk_ours, &mut hunk_theirs);
+
+    let mut merged = out.join("\n");
+    if !merged.is_empty() {
+        merged.push('\n');
+    }
+    file::with_line_ending(&merged, file::line_ending(ours))
+}
+
+/// Emit one differing stretch: our lines, then their lines we don't have.
+fn flush<'a>(out: &mut Vec<&'a str>, ours: &mut Vec<&'a str>, theirs: &mut Vec<&'a str>) {
+    out.extend(ours.iter());
+    out.extend(theirs.iter().filter(|line| !ours.contains(line)));
+    ours.clear();
+    theirs.clear();
+}
+
+/// Merge a conflict copy into its plan file under the file's lock, then
+/// delete the copy. A copy whose plan file is gone simply takes its place.
+pub fn resolve(cfg: &crate::config::Config, sibling: &Sibling) -> crate::Result<()> {
+    let _lock = file::acquire_lock(&sibling.original, &cfg.lock)?;
+    let theirs = fs::read_to_string(&sibling.copy)?;
+    let merged = match fs::read_to_string(&sibling.original) {
+        Ok(ours) => union_merge(&ours, &theirs),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => theirs,
+        Err(e) => return Err(e.into()),
+    };
+    crate::backup::snapshot(&sibling.original, &cfg.backups)?;
+    file::write_atomic(&sibling.original, &merged)?;
+    Ok(fs::remove_file(&sibling.copy)?)
+}
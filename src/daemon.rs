@@ -0,0 +1,166 @@
+use crate::config::Config;
+use crate::{backup, date, file, paths, template, undo};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+
+/// How long a client waits for the greeting before writing the file itself.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Add `entry` to today's inbox and return the file's name. The write is
+/// recorded under `command`, so `plan undo` can take it back.
+fn append(cfg: &Config, command: &str, entry: &str) -> io::Result<String> {
+    let day = date::today();
+    let path = date::current_plan_path(&cfg.layout, &cfg.dir, day);
+    let lock = file::acquire_lock(&path, &cfg.lock)?;
+    let before = fs::read_to_string(&path).ok();
+    let template = template::load(&cfg.template)?;
+    date::ensure_file_exists(&cfg.layout, template.as_deref(), &path, day, false)?;
+    backup::snapshot(&path, &cfg.backups)?;
+    file::insert_into_inbox(&cfg.layout, &path, entry, &lock)?;
+    undo::record_write(command, &path, before.as_deref());
+    Ok(path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned())
+}
+
+/// The greeting's reply: which plan directory this daemon writes to.
+fn greeting(dir: &Path) -> String {
+    format!("ok plan {}", paths::dir_key(dir))
+}
+
+/// Run one command line: `log TEXT` adds `* TEXT` and `jot TEXT` adds TEXT
+/// as written, both to today's inbox. The reply is `ok FILE` or
+/// `error MESSAGE`. `hello` answers `ok plan KEY`, KEY identifying the plan
+/// directory, so a client can check it is talking to the right daemon.
+pub fn handle(cfg: &Config, line: &str) -> String {
+    let (verb, text) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+    let text = text.trim();
+    let entry = match verb {
+        "hello" => return greeting(&cfg.dir),
+        "log" => format!("* {}", text),
+        "jot" => text.to_string(),
+        _ => return format!("error unknown command '{}'; use log or jot", verb),
+    };
+    if text.is_empty() {
+        return "error nothing to add".to_string();
+    }
+    match append(cfg, verb, &entry) {
+        Ok(name) => format!("ok {}", name),
+        Err(e) => format!("error {}", e),
+    }
+}
+
+/// A connection to the daemon writing to a client's plan directory.
+pub struct Client {
+    stream: UnixStream,
+    replies: BufReader<UnixStream>,
+}
+
+impl Client {
+    /// Connect to the daemon on `socket` if it writes to `dir`. No socket, a
+    /// dead one, or a daemon for another directory are all `None`, and the
+    /// caller writes the file itself.
+    pub fn connect(socket: &Path, dir: &Path) -> Option<Client> {
+        let stream = UnixStream::connect(socket).ok()?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).ok()?;
+        let mut client = Client {
+            replies: BufReader::new(stream.try_clone().ok()?),
+            stream,
+        };
+        if client.send("hello").ok()? != greeting(dir) {
+            return None;
+        }
+        // Past the greeting a reply can wait on the plan file's lock
+        client.stream.set_read_timeout(None).ok()?;
+        Some(client)
+    }
+
+    /// Send one command line and return the daemon's reply.
+    pub fn send(&mut self, line: &str) -> io::Result<String> {
+        writeln!(self.stream, "{}", line)?;
+        let mut reply = String::new();
+        if self.replies.read_line(&mut reply)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "the daemon hung up",
+            ));
+        }
+        Ok(reply.trim_end().to_string())
+    }
+}
+
+/// Answer each line a client sends until it hangs up. `writes` is held for
+/// every command, so only one thread touches the plan files at a time.
+fn serve_client(cfg: &Config, stream: UnixStream, writes: &Mutex<()>) -> io::Result<()> {
+    let mut replies = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = {
+            let _guard = writes.lock().unwrap_or_else(PoisonError::into_inner);
+            handle(cfg, &line)
+        };
+        writeln!(replies, "{}", reply)?;
+    }
+    Ok(())
+}
+
+/// Bind `socket` without it ever being reachable by other users: anyone who
+/// can connect can write to the plan files, so it is created inside a
+/// private directory, restricted, then moved into place.
+fn bind_private(socket: &Path) -> io::Result<UnixListener> {
+    let parent = socket.parent().unwrap_or(Path::new("."));
+    let staging = parent.join(format!(".plan-daemon-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&staging);
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("sock");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&staged, socket)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_dir_all(&staging);
+    bound
+}
+
+/// Listen on `socket` until the process is stopped. A socket left behind by
+/// a daemon that died is replaced; one that still answers is an error.
+pub fn run(cfg: &Config, socket: &Path) -> io::Result<()> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already listening on {}", socket.display()),
+            ));
+        }
+        fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let listener = bind_private(socket)?;
+    eprintln!("plan: listening on {}", socket.display());
+
+    let writes = Mutex::new(());
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let writes = &writes;
+            scope.spawn(move || {
+                // A client that hung up doesn't stop the daemon
+                let _ = serve_client(cfg, stream, writes);
+            });
+        }
+    });
+    Ok(())
+}
@@ -1,42 +1,96 @@
-use chrono::{Duration, Local, NaiveDate};
-use std::fs::{self, File};
-use std::io::{self, Write};
+use crate::Error;
+use crate::layout::{self, Layout};
+use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime};
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process;
 
-/// Get the date for N days ago. If N = 0, today. Takes injectable mock time into account.
-pub fn get_date_opt(days_ago: u32) -> Option<NaiveDate> {
+#[cfg(feature = "testing")]
+thread_local! {
+    /// Per-thread clock set by [`crate::testing::FixedClock`].
+    pub(crate) static CLOCK_OVERRIDE: std::cell::Cell<Option<NaiveDateTime>> =
+        const { std::cell::Cell::new(None) };
+}
+
+/// Current local date and time. Takes injectable mock time into account:
+/// `PLAN_MOCK_TIME` is either `YYYY-MM-DD` (midnight) or `YYYY-MM-DDTHH:MM`.
+pub fn now() -> NaiveDateTime {
+    #[cfg(feature = "testing")]
+    if let Some(fixed) = CLOCK_OVERRIDE.get() {
+        return fixed;
+    }
+
     #[cfg(not(feature = "test-clock"))]
-    let today = Local::now().naive_local().date();
+    let now = Local::now().naive_local();
 
     #[cfg(feature = "test-clock")]
-    let today = {
-        if let Ok(mock_time) = std::env::var("PLAN_MOCK_TIME")
-            && let Ok(parsed) = NaiveDate::parse_from_str(&mock_time, "%Y-%m-%d")
-        {
+    let now = {
+        let mock_time = std::env::var("PLAN_MOCK_TIME").unwrap_or_default();
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(&mock_time, "%Y-%m-%dT%H:%M") {
             parsed
+        } else if let Ok(parsed) = NaiveDate::parse_from_str(&mock_time, "%Y-%m-%d") {
+            parsed.and_time(chrono::NaiveTime::MIN)
         } else {
-            Local::now().naive_local().date()
+            Local::now().naive_local()
         }
     };
 
-    today.checked_sub_signed(Duration::days(days_ago as i64))
+    now
+}
+
+/// Today's date. Takes injectable mock time into account.
+pub fn today() -> NaiveDate {
+    now().date()
+}
+
+/// Get the date for N days ago. If N = 0, today. Takes injectable mock time into account.
+pub fn get_date_opt(days_ago: u32) -> Option<NaiveDate> {
+    days_before(today(), days_ago)
+}
+
+/// Get the date N days before `base`, or `None` if that underflows the calendar.
+pub fn days_before(base: NaiveDate, days_ago: u32) -> Option<NaiveDate> {
+    base.checked_sub_signed(Duration::days(days_ago as i64))
 }
 
-pub fn get_date(days_ago: u32) -> anyhow::Result<NaiveDate> {
-    get_date_opt(days_ago)
-        .ok_or_else(|| anyhow::anyhow!("Date calculation is out of bounds (too far in the past)."))
+/// Days among `days` that fall on the same month and day as `date` in an
+/// earlier year, plus the same day `months_ago` months back when given,
+/// oldest first. Feb 29 only matches leap years; a month without the day
+/// falls back to its last day.
+pub fn on_this_day(date: NaiveDate, days: &[NaiveDate], months_ago: Option<u32>) -> Vec<NaiveDate> {
+    let months_back = months_ago.and_then(|n| date.checked_sub_months(Months::new(n)));
+    let mut found: Vec<NaiveDate> = days
+        .iter()
+        .copied()
+        .filter(|d| {
+            (d.year() < date.year() && d.month() == date.month() && d.day() == date.day())
+                || Some(*d) == months_back
+        })
+        .collect();
+    found.sort();
+    found.dedup();
+    found
 }
 
-pub fn parse_date_opt(arg: Option<&str>) -> anyhow::Result<u32> {
+pub fn get_date(days_ago: u32) -> crate::Result<NaiveDate> {
+    get_date_from(today(), days_ago)
+}
+
+/// Like [`get_date`], but relative to an anchor date instead of the clock.
+pub fn get_date_from(base: NaiveDate, days_ago: u32) -> crate::Result<NaiveDate> {
+    days_before(base, days_ago).ok_or_else(|| {
+        Error::InvalidDate("Date calculation is out of bounds (too far in the past).".to_string())
+    })
+}
+
+pub fn parse_date_opt(arg: Option<&str>) -> crate::Result<u32> {
     if let Some(d) = arg {
         let d_lower = d.trim().to_lowercase();
         if let Some(stripped) = d_lower.strip_prefix("@~") {
             stripped.parse::<u32>().map_err(|_| {
-                anyhow::anyhow!(
+                Error::InvalidDate(format!(
                     "Invalid relative date '@~{}'. Expected unsigned integer.",
                     stripped
-                )
+                ))
             })
         } else if d_lower == "@" || d_lower == "today" {
             Ok(0)
@@ -47,14 +101,14 @@ pub fn parse_date_opt(arg: Option<&str>) -> anyhow::Result<u32> {
             .or_else(|| d_lower.strip_suffix(" day ago"))
         {
             num_str.trim().parse::<u32>().map_err(|_| {
-                anyhow::anyhow!(
+                Error::InvalidDate(format!(
                     "Invalid date format '{}'. Expected unsigned integer before 'days ago'.",
                     d
-                )
+                ))
             })
         } else {
-            Err(anyhow::anyhow!(
-                "Invalid date format. Use @, @~N, today, yesterday, or 'N days ago'."
+            Err(Error::InvalidDate(
+                "Invalid date format. Use YYYY-MM-DD, @, @~N, today, yesterday, 'N days ago', or a name from 'plan names'.".to_string(),
             ))
         }
     } else {
@@ -62,19 +116,163 @@ pub fn parse_date_opt(arg: Option<&str>) -> anyhow::Result<u32> {
     }
 }
 
-/// Format the date as a filename: YYYY-MM-DD.plan
-pub fn format_filename(date: NaiveDate) -> String {
-    format!("{}.plan", date.format("%Y-%m-%d"))
+/// Interpret a free-form expression such as "the 3rd of last month" or
+/// "next friday", relative to `base` at the current time of day. Bare times
+/// of day don't name a day and are rejected.
+#[cfg(feature = "natural-dates")]
+pub fn parse_natural(input: &str, base: NaiveDate) -> Option<NaiveDate> {
+    use human_date_parser::ParseResult;
+    match human_date_parser::from_human_time(input.trim(), base.and_time(now().time())).ok()? {
+        ParseResult::Date(date) => Some(date),
+        ParseResult::DateTime(moment) => Some(moment.date()),
+        ParseResult::Time(_) => None,
+    }
+}
+
+/// Without the `natural-dates` feature nothing beyond the built-in grammar
+/// is understood.
+#[cfg(not(feature = "natural-dates"))]
+pub fn parse_natural(_input: &str, _base: NaiveDate) -> Option<NaiveDate> {
+    None
+}
+
+/// Levenshtein distance over chars, used to rank near-miss date expressions.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Suggest the closest valid date expression for a rejected DATE argument.
+///
+/// Candidates come from the built-in grammar; any number in the input is
+/// carried over into the `@~N` and `N days ago` forms. Returns `None` when
+/// nothing is close enough to be a plausible typo.
+pub fn suggest_date(input: &str) -> Option<String> {
+    let lower = input.trim().to_lowercase();
+    if lower.is_empty() {
+        return None;
+    }
+
+    let mut candidates = vec!["today".to_string(), "yesterday".to_string()];
+    // The first number, sign included: "~-3" must not become "@~3", which
+    // names a day on the other side of today
+    if let Some(start) = lower.find(|c: char| c.is_ascii_digit()) {
+        let digits: String = lower[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let sign = if lower[..start].ends_with('-') {
+            "-"
+        } else {
+            ""
+        };
+        // Kept as text, since parsing "-0" would drop the sign
+        let n = format!("{}{}", sign, digits);
+        if n.parse::<i64>().is_ok() {
+            candidates.push(format!("@~{}", n));
+            candidates.push(format!("{} days ago", n));
+        }
+    }
+
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(&lower, &c), c))
+        .filter(|(dist, c)| *dist > 0 && *dist <= c.chars().count() / 2)
+        .min_by_key(|(dist, _)| *dist)
+        .map(|(_, c)| c)
+        .filter(|c| parse_date_opt(Some(c)).is_ok())
+}
+
+/// Format the date as a filename: YYYY-MM-DD.plan (or .md for Obsidian notes)
+pub fn format_filename(layout: &Layout, date: NaiveDate) -> String {
+    format!("{}.{}", date.format("%Y-%m-%d"), layout.extension())
+}
+
+/// A link to a day from inside a plan file: `[[YYYY-MM-DD]]`.
+pub fn day_link(date: NaiveDate) -> String {
+    format!("[[{}]]", date.format("%Y-%m-%d"))
 }
 
 /// Get the absolute path to a plan file
-pub fn get_plan_path(dir: &Path, date: NaiveDate) -> PathBuf {
-    dir.join(format_filename(date))
+pub fn get_plan_path(layout: &Layout, dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format_filename(layout, date))
+}
+
+/// Filename of one part of a day. Part 1 is the day's own file; later parts
+/// are continuation files such as `2026-02-19.2.plan`.
+pub fn format_part_filename(layout: &Layout, date: NaiveDate, part: u32) -> String {
+    if part <= 1 {
+        format_filename(layout, date)
+    } else {
+        format!(
+            "{}.{}.{}",
+            date.format("%Y-%m-%d"),
+            part,
+            layout.extension()
+        )
+    }
+}
+
+/// Existing files for a day in reading order: the day's own file, then its
+/// continuation files. Continuations are numbered without gaps, so probing
+/// stops at the first missing part.
+pub fn day_paths(layout: &Layout, dir: &Path, date: NaiveDate) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let primary = get_plan_path(layout, dir, date);
+    if primary.exists() {
+        paths.push(primary);
+    }
+    for part in 2.. {
+        let path = dir.join(format_part_filename(layout, date, part));
+        if !path.exists() {
+            break;
+        }
+        paths.push(path);
+    }
+    paths
+}
+
+/// The file new entries for a day go into: its last continuation file if it
+/// has one, otherwise the day's own file.
+pub fn current_plan_path(layout: &Layout, dir: &Path, date: NaiveDate) -> PathBuf {
+    day_paths(layout, dir, date)
+        .pop()
+        .unwrap_or_else(|| get_plan_path(layout, dir, date))
+}
+
+/// Generate the initial content for a new plan file, from the user's
+/// `template` when there is one
+pub fn generate_template(layout: &Layout, template: Option<&str>, date: NaiveDate) -> String {
+    if let Some(template) = template {
+        return crate::template::render(layout, template, date);
+    }
+    match layout {
+        Layout::Plan => template_with_header(layout, date.format("%Y, %b %d - %A").to_string()),
+        Layout::Obsidian { inbox_heading } => layout::obsidian_template(date, inbox_heading),
+    }
 }
 
-/// Generate the initial content for a new plan file
-pub fn generate_template(date: NaiveDate) -> String {
-    let formatted_date = date.format("%Y, %b %d - %A").to_string();
+/// Generate the initial content for part `part` of a day
+pub fn generate_continuation_template(layout: &Layout, date: NaiveDate, part: u32) -> String {
+    template_with_header(
+        layout,
+        format!("{} (part {})", date.format("%Y, %b %d - %A"), part),
+    )
+}
+
+pub(crate) fn template_with_header(layout: &Layout, formatted_date: String) -> String {
+    if let Layout::Obsidian { inbox_heading } = layout {
+        return layout::obsidian_template_with_title(&formatted_date, inbox_heading);
+    }
     let inbox_line = crate::file::make_inbox_line(formatted_date.len());
     let close_line = "~".repeat(formatted_date.len());
     format!(
@@ -87,34 +285,31 @@ pub fn generate_template(date: NaiveDate) -> String {
     )
 }
 
-/// Retrieve the template or read existing content
-pub fn ensure_file_exists(path: &Path, date: NaiveDate, is_past: bool) -> io::Result<()> {
+/// Create the day's file from `template` (or the built-in layout), with any
+/// recurring entries due that day in its inbox, unless it already exists
+pub fn ensure_file_exists(
+    layout: &Layout,
+    template: Option<&str>,
+    path: &Path,
+    date: NaiveDate,
+    is_past: bool,
+) -> crate::Result<()> {
     if path.exists() {
         return Ok(());
     }
 
     if is_past {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("Plan file for past date does not exist: {}", path.display()),
-        ));
+        return Err(Error::FileMissing(path.to_path_buf()));
     }
 
-    let template = generate_template(date);
+    let mut content = generate_template(layout, template, date);
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
+        for entry in crate::recur::due_on(&crate::recur::load(parent)?, date) {
+            content = crate::file::insert_into_content(layout, &content, "inbox", entry);
+        }
     }
 
-    // Atomic write
-    let tmp_path = path.with_extension(format!("tmp-{}", process::id()));
-    let mut tmp_guard = crate::file::TempFileGuard::new(tmp_path.clone());
-    {
-        let mut file = File::create(&tmp_path)?;
-        file.write_all(template.as_bytes())?;
-        file.sync_all()?;
-    }
-    fs::rename(&tmp_path, path)?;
-    tmp_guard.persist();
-
+    crate::file::write_atomic(path, &content)?;
     Ok(())
 }
@@ -0,0 +1,119 @@
+use std::sync::Mutex;
+
+/// Every kind of warning plan emits. Codes are stable across releases so
+/// scripts and `warnings.suppress` can rely on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    UnexpectedFiles,
+    ConfigProblem,
+    HistoryUnavailable,
+    HookFailed,
+    ReviewConflict,
+    FileTooLarge,
+    AutoRuleFailed,
+    ClockSkew,
+    SyncConflict,
+}
+
+impl Warning {
+    pub const ALL: [Warning; 9] = [
+        Warning::UnexpectedFiles,
+        Warning::ConfigProblem,
+        Warning::HistoryUnavailable,
+        Warning::HookFailed,
+        Warning::ReviewConflict,
+        Warning::FileTooLarge,
+        Warning::AutoRuleFailed,
+        Warning::ClockSkew,
+        Warning::SyncConflict,
+    ];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Warning::UnexpectedFiles => "W001",
+            Warning::ConfigProblem => "W002",
+            Warning::HistoryUnavailable => "W003",
+            Warning::HookFailed => "W004",
+            Warning::ReviewConflict => "W005",
+            Warning::FileTooLarge => "W006",
+            Warning::AutoRuleFailed => "W007",
+            Warning::ClockSkew => "W008",
+            Warning::SyncConflict => "W009",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Warning::UnexpectedFiles => "unexpected-files",
+            Warning::ConfigProblem => "config-problem",
+            Warning::HistoryUnavailable => "history-unavailable",
+            Warning::HookFailed => "hook-failed",
+            Warning::ReviewConflict => "review-conflict",
+            Warning::FileTooLarge => "file-too-large",
+            Warning::AutoRuleFailed => "auto-rule-failed",
+            Warning::ClockSkew => "clock-skew",
+            Warning::SyncConflict => "sync-conflict",
+        }
+    }
+
+    /// Look up a warning by code (`W001`, any case) or name (`unexpected-files`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        Self::ALL
+            .into_iter()
+            .find(|w| w.code().eq_ignore_ascii_case(name) || w.name() == name)
+    }
+}
+
+/// How warnings are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// `plan: warning[W001]: message`
+    #[default]
+    Text,
+    /// One JSON object per line with `code`, `name`, and `message`
+    Json,
+}
+
+struct Settings {
+    format: Format,
+    suppressed: Vec<Warning>,
+}
+
+static SETTINGS: Mutex<Settings> = Mutex::new(Settings {
+    format: Format::Text,
+    suppressed: Vec::new(),
+});
+
+/// Set the output format and which warnings to drop. Called at startup, and
+/// again once the config has been read.
+pub fn configure(format: Format, suppressed: &[Warning]) {
+    let mut settings = SETTINGS.lock().unwrap_or_else(|e| e.into_inner());
+    settings.format = format;
+    settings.suppressed = suppressed.to_vec();
+}
+
+/// Render a warning as it would be printed, or `None` when it is suppressed.
+pub fn render(warning: Warning, message: &str) -> Option<String> {
+    let settings = SETTINGS.lock().unwrap_or_else(|e| e.into_inner());
+    if settings.suppressed.contains(&warning) {
+        return None;
+    }
+    Some(match settings.format {
+        Format::Text => format!("plan: warning[{}]: {}", warning.code(), message),
+        Format::Json => serde_json::json!({
+            "level": "warning",
+            "code": warning.code(),
+            "name": warning.name(),
+            "message": message,
+        })
+        .to_string(),
+    })
+}
+
+/// Print a warning to stderr unless it is suppressed.
+pub fn warn(warning: Warning, message: &str) {
+    if let Some(line) = render(warning, message) {
+        eprintln!("{}", line);
+    }
+}
@@ -0,0 +1,77 @@
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors from the plan library. The CLI wraps these with context; library
+/// users can match on the kind of failure.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// A DATE expression that isn't understood or falls off the calendar
+    #[error("{0}")]
+    InvalidDate(String),
+    /// A plan file that has to exist already, e.g. one for a past day
+    #[error("Plan file for past date does not exist: {}", .0.display())]
+    FileMissing(PathBuf),
+    /// Another process held a lock for longer than we were willing to wait
+    #[error("{}", lock_message(.path, *.waited))]
+    LockContended { path: PathBuf, waited: Duration },
+    /// A write to a plan directory that is mounted or marked read-only
+    #[error(
+        "{} is read-only; commands that only read plan files still work",
+        .0.display()
+    )]
+    ReadOnly(PathBuf),
+    /// Malformed input, such as an `auto.*` rule in the config
+    #[error("{0}")]
+    ParseError(String),
+    /// A setting that is missing or can't be used, such as `sync.remote`
+    #[error("{0}")]
+    InvalidConfig(String),
+    /// A plan file edited since the write `plan undo` was asked to reverse
+    #[error(
+        "{} changed after the last '{}'; not undoing it (pass --force to undo anyway)",
+        .path.display(),
+        .command
+    )]
+    ChangedSince { path: PathBuf, command: String },
+    /// An external program, such as git or a hook, that couldn't be run or
+    /// exited non-zero
+    #[error("{0}")]
+    CommandFailed(String),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+fn lock_message(path: &std::path::Path, waited: Duration) -> String {
+    if waited.is_zero() {
+        format!("{} is locked by another process", path.display())
+    } else {
+        format!(
+            "gave up after {}s waiting for {}; another plan process or a hook it started may be holding it",
+            waited.as_secs_f64(),
+            path.display()
+        )
+    }
+}
+
+/// Lets library code that still deals in `io::Result` use `?` on plan errors.
+impl From<Error> for io::Error {
+    fn from(error: Error) -> Self {
+        let kind = match &error {
+            Error::Io(e) => e.kind(),
+            Error::FileMissing(_) => io::ErrorKind::NotFound,
+            Error::LockContended { .. } => io::ErrorKind::WouldBlock,
+            Error::ReadOnly(_) => io::ErrorKind::ReadOnlyFilesystem,
+            Error::InvalidDate(_) => io::ErrorKind::InvalidInput,
+            Error::ParseError(_) => io::ErrorKind::InvalidData,
+            Error::InvalidConfig(_) => io::ErrorKind::InvalidInput,
+            Error::ChangedSince { .. } | Error::CommandFailed(_) => io::ErrorKind::Other,
+        };
+        match error {
+            Error::Io(e) => e,
+            other => io::Error::new(kind, other),
+        }
+    }
+}
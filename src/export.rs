@@ -0,0 +1,206 @@
+use crate::section;
+use crate::task::{self, TaskState};
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+
+/// One plan file to export. A day with continuation files contributes one
+/// of these per file, in part order.
+pub struct PlanFile {
+    pub name: String,
+    pub date: NaiveDate,
+    pub content: String,
+}
+
+/// Whether `line` is the dated header `plan` writes at the top of a file.
+fn is_header(line: &str, date: NaiveDate) -> bool {
+    line.starts_with(&date.format("%Y, %b %d").to_string())
+}
+
+/// A task as a Markdown list item. Inbox and in-progress tasks are both
+/// unchecked; cancelled ones are struck through.
+fn markdown_task(t: &task::Task) -> String {
+    let text = match t.priority {
+        Some(p) => format!("{} {}", p, t.text),
+        None => t.text.clone(),
+    };
+    match t.state {
+        TaskState::Inbox | TaskState::Open => format!("- [ ] {}", text),
+        TaskState::Done => format!("- [x] {}", text),
+        TaskState::Cancelled => format!("- [ ] ~~{}~~", text),
+    }
+}
+
+/// End the current paragraph with a blank line, unless one is already there.
+fn push_blank(out: &mut String) {
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Render one file's body as Markdown: sections become `###` headings, tasks
+/// and events list items, and `--- :lang` code zones fenced code blocks.
+fn markdown_body(file: &PlanFile, out: &mut String) {
+    let mut in_code = false;
+    for (i, line) in file.content.lines().enumerate() {
+        if i == 0 && is_header(line, file.date) {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("---") {
+            if in_code {
+                out.push_str("```\n");
+                in_code = false;
+            }
+            if let Some(lang) = rest.trim_start().strip_prefix(':') {
+                out.push_str(&format!("```{}\n", lang.trim()));
+                in_code = true;
+            }
+            continue;
+        }
+        if in_code {
+            out.push_str(line);
+        } else if line.trim().is_empty() {
+            push_blank(out);
+            continue;
+        } else if let Some(name) = section::open_marker_name(line) {
+            push_blank(out);
+            out.push_str(&format!("### {}\n\n", name));
+            continue;
+        } else if section::is_close_marker(line) {
+            push_blank(out);
+            continue;
+        } else if let Some(t) = task::parse_task(line, i + 1) {
+            out.push_str(&markdown_task(&t));
+        } else if let Some(e) = task::parse_event(line, i + 1) {
+            match e.time {
+                Some(time) => out.push_str(&format!("- {} {}", time.format("%H:%M"), e.text)),
+                None => out.push_str(&format!("- {}", e.text)),
+            }
+        } else {
+            out.push_str(line);
+        }
+        out.push('\n');
+    }
+    if in_code {
+        out.push_str("```\n");
+    }
+}
+
+/// All files as one Markdown document with a `##` heading per day.
+pub fn to_markdown(files: &[PlanFile]) -> String {
+    let mut out = String::new();
+    let mut current: Option<NaiveDate> = None;
+    for file in files {
+        if current != Some(file.date) {
+            if current.is_some() {
+                out.truncate(out.trim_end().len());
+                out.push_str("\n\n");
+            }
+            out.push_str(&format!("## {}\n\n", file.date.format("%Y-%m-%d (%A)")));
+            current = Some(file.date);
+        }
+        markdown_body(file, &mut out);
+    }
+    out.truncate(out.trim_end().len());
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Name of the section holding 0-based line `index`, if any.
+fn section_at(spans: &[section::SectionSpan], index: usize) -> Option<&str> {
+    spans
+        .iter()
+        .find(|s| s.body().contains(&index))
+        .map(|s| s.name.as_str())
+}
+
+/// All files as a JSON array, one object per file with its sections, tasks,
+/// and events alongside the raw content.
+pub fn to_json(files: &[PlanFile]) -> Value {
+    Value::Array(
+        files
+            .iter()
+            .map(|file| {
+                let lines: Vec<&str> = file.content.lines().collect();
+                let spans = section::parse_sections(&file.content);
+                let sections: Vec<Value> = spans
+                    .iter()
+                    .map(|s| {
+                        json!({
+                            "name": s.name,
+                            "lines": lines[s.body()],
+                        })
+                    })
+                    .collect();
+                let tasks: Vec<Value> = task::parse_tasks(&file.content)
+                    .iter()
+                    .map(|t| {
+                        json!({
+                            "line": t.line_number,
+                            "section": section_at(&spans, t.line_number - 1),
+                            "state": t.state.name(),
+                            "priority": t.priority.map(|p| p.letter().to_string()),
+                            "text": t.text,
+                        })
+                    })
+                    .collect();
+                let events: Vec<Value> = section::prose_lines(&file.content)
+                    .filter_map(|(i, line)| task::parse_event(line, i + 1))
+                    .map(|e| {
+                        json!({
+                            "line": e.line_number,
+                            "time": e.time.map(|t| t.format("%H:%M").to_string()),
+                            "text": e.text,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "file": file.name,
+                    "date": file.date.format("%Y-%m-%d").to_string(),
+                    "weekday": file.date.format("%A").to_string(),
+                    "sections": sections,
+                    "tasks": tasks,
+                    "events": events,
+                    "content": file.content,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Quote a CSV field when it needs it (RFC 4180).
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Every task in `files` as CSV, one row per task, with a header row.
+pub fn to_csv(files: &[PlanFile]) -> String {
+    let mut out = String::from("date,file,line,section,state,priority,text\n");
+    for file in files {
+        let spans = section::parse_sections(&file.content);
+        for t in task::parse_tasks(&file.content) {
+            let row = [
+                file.date.format("%Y-%m-%d").to_string(),
+                file.name.clone(),
+                t.line_number.to_string(),
+                section_at(&spans, t.line_number - 1)
+                    .unwrap_or_default()
+                    .to_string(),
+                t.state.name().to_string(),
+                t.priority
+                    .map(|p| p.letter().to_string())
+                    .unwrap_or_default(),
+                t.text.clone(),
+            ];
+            let row: Vec<String> = row.iter().map(|f| csv_field(f)).collect();
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+    }
+    out
+}
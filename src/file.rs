@@ -1,43 +1,113 @@
+use crate::config::LockConfig;
+use crate::layout::Layout;
 use fs4::fs_std::FileExt;
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, Write};
 use std::path::Path;
 use std::process;
+use std::time::{Duration, Instant};
 
+/// Held while a plan file is read or written; the lock is released on drop.
+/// A guard from a read-only directory holds no lock at all.
 pub struct LockGuard {
-    _file: File,
+    file: Option<File>,
 }
 
 impl Drop for LockGuard {
     fn drop(&mut self) {
-        let _ = self._file.unlock();
+        if let Some(file) = &self.file {
+            let _ = file.unlock();
+        }
     }
 }
 
-/// Acquire an exclusive lock around the target file ensuring serialized IO
-pub fn acquire_lock(path: &Path) -> io::Result<LockGuard> {
-    let lock_path = path.with_extension("lock");
-    let lock_file = OpenOptions::new()
+/// How often a contended lock is retried while waiting.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+fn open_lock_file(lock_path: &Path) -> io::Result<File> {
+    OpenOptions::new()
         .read(true)
         .write(true)
         .create(true)
         .truncate(false)
-        .open(&lock_path)?;
-    lock_file.lock_exclusive()?;
-    Ok(LockGuard { _file: lock_file })
+        .open(lock_path)
+}
+
+/// Retry `try_lock` until it succeeds or `timeout` passes; zero fails
+/// immediately. The error names the lock file so a stuck holder can be
+/// tracked down.
+fn lock_with_timeout(
+    file: &File,
+    lock_path: &Path,
+    timeout: Duration,
+    try_lock: fn(&File) -> io::Result<bool>,
+) -> crate::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if try_lock(file)? {
+            return Ok(());
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(crate::Error::LockContended {
+                path: lock_path.to_path_buf(),
+                waited: timeout,
+            });
+        }
+        std::thread::sleep(LOCK_RETRY_INTERVAL.min(deadline - now));
+    }
 }
 
-/// Acquire a shared lock for read-only operations (allows concurrent readers)
-pub fn acquire_shared_lock(path: &Path) -> io::Result<LockGuard> {
+/// Acquire an exclusive lock around the target file ensuring serialized IO,
+/// waiting as long as `options` allows for another process to let go
+pub fn acquire_lock(path: &Path, options: &LockConfig) -> crate::Result<LockGuard> {
     let lock_path = path.with_extension("lock");
-    let lock_file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .truncate(false)
-        .open(&lock_path)?;
-    lock_file.lock_shared()?;
-    Ok(LockGuard { _file: lock_file })
+    let lock_file = match open_lock_file(&lock_path) {
+        Ok(file) => file,
+        Err(e) if is_read_only(&e) => {
+            let dir = lock_path.parent().unwrap_or(Path::new("."));
+            return Err(crate::Error::ReadOnly(dir.to_path_buf()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    lock_with_timeout(
+        &lock_file,
+        &lock_path,
+        options.timeout,
+        FileExt::try_lock_exclusive,
+    )?;
+    Ok(LockGuard {
+        file: Some(lock_file),
+    })
+}
+
+/// Acquire a shared lock for read-only operations (allows concurrent readers).
+///
+/// When the lock file can't be created because the filesystem is read-only
+/// (a mounted snapshot, a backup someone is browsing), the read goes ahead
+/// without a lock: nothing can be writing there either.
+pub fn acquire_shared_lock(path: &Path, options: &LockConfig) -> crate::Result<LockGuard> {
+    let lock_path = path.with_extension("lock");
+    let lock_file = match open_lock_file(&lock_path) {
+        Ok(file) => file,
+        Err(e) if is_read_only(&e) => return Ok(LockGuard { file: None }),
+        Err(e) => return Err(e.into()),
+    };
+    lock_with_timeout(
+        &lock_file,
+        &lock_path,
+        options.timeout,
+        FileExt::try_lock_shared,
+    )?;
+    Ok(LockGuard {
+        file: Some(lock_file),
+    })
+}
+
+/// Whether creating a file failed because the filesystem is read-only. A
+/// directory the user may not write to is a permissions problem, not this.
+fn is_read_only(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::ReadOnlyFilesystem
 }
 
 pub struct TempFileGuard {
@@ -67,28 +137,52 @@ impl Drop for TempFileGuard {
 
 /// Build a centered `~~~~~inbox~~~~~` line of the given total width.
 pub fn make_inbox_line(width: usize) -> String {
-    let label = "inbox";
-    let remaining = width.saturating_sub(label.len());
+    make_section_line("inbox", width)
+}
+
+/// Build a centered `~~~~~name~~~~~` section marker of the given total width.
+pub fn make_section_line(name: &str, width: usize) -> String {
+    let remaining = width.saturating_sub(name.len());
     let left = remaining / 2;
     let right = remaining - left;
-    format!("{}inbox{}", "~".repeat(left), "~".repeat(right))
+    format!("{}{}{}", "~".repeat(left), name, "~".repeat(right))
 }
 
-fn is_inbox_open(line: &str) -> bool {
-    let t = line.trim();
-    t.starts_with('~') && t.ends_with('~') && t.contains("inbox") && t.replace('~', "") == "inbox"
+/// Whether `name` is a file plan manages. Any `.plan` file is one, but in an
+/// Obsidian vault only dated notes and scratch files are, since the vault
+/// holds the user's other notes too.
+pub fn is_plan_file(layout: &Layout, name: &str) -> bool {
+    if name.contains(".sync-conflict") {
+        return false;
+    }
+    match layout {
+        Layout::Plan => strip_extension(layout, name).is_some(),
+        Layout::Obsidian { .. } => {
+            plan_part(layout, name).is_some() || crate::scratch::name_of(layout, name).is_some()
+        }
+    }
 }
 
-fn is_tilde_line(line: &str) -> bool {
-    let t = line.trim();
-    !t.is_empty() && t.chars().all(|c| c == '~')
+/// `name` without the plan file extension of `layout`.
+fn strip_extension<'a>(layout: &Layout, name: &'a str) -> Option<&'a str> {
+    name.strip_suffix(layout.extension())?.strip_suffix('.')
 }
 
-pub fn is_plan_file(name: &str) -> bool {
-    name.ends_with(".plan") && !name.starts_with(".sync-conflict")
-}
+/// Marker file written by `plan --init` to vouch for a plan directory.
+pub const ROOT_MARKER: &str = ".plan-root";
 
-const IGNORED_NAMES: &[&str] = &[".DS_Store", "Thumbs.db"];
+/// Unmarked directories with no plan files are refused once they hold this
+/// many other entries; a fresh or nearly empty directory is fine.
+const FOREIGN_ENTRY_LIMIT: usize = 20;
+
+const IGNORED_NAMES: &[&str] = &[
+    ".DS_Store",
+    "Thumbs.db",
+    crate::names::MANIFEST_NAME,
+    crate::recur::RULES_NAME,
+    crate::config::LOCAL_CONFIG,
+    ROOT_MARKER,
+];
 const IGNORED_EXTENSIONS: &[&str] = &[".lock", ".swp", ".tmp"];
 const IGNORED_SUFFIXES: &[&str] = &["~"];
 
@@ -125,7 +219,11 @@ pub struct ScanResult {
 
 /// Scan a plan directory, separating plan files from unexpected files.
 /// Only flags regular files; directories are always ignored.
-pub fn scan_plan_dir(dir: &Path, user_ignores: &[String]) -> io::Result<ScanResult> {
+pub fn scan_plan_dir(
+    layout: &Layout,
+    dir: &Path,
+    user_ignores: &[String],
+) -> crate::Result<ScanResult> {
     let mut plan_entries = Vec::new();
     let mut unexpected = Vec::new();
 
@@ -138,7 +236,7 @@ pub fn scan_plan_dir(dir: &Path, user_ignores: &[String]) -> io::Result<ScanResu
             continue;
         }
         let name = entry.file_name().to_string_lossy().into_owned();
-        if is_plan_file(&name) {
+        if is_plan_file(layout, &name) {
             plan_entries.push(entry);
         } else if !should_ignore(&name, user_ignores) {
             unexpected.push(name);
@@ -151,6 +249,75 @@ pub fn scan_plan_dir(dir: &Path, user_ignores: &[String]) -> io::Result<ScanResu
     })
 }
 
+/// Check that `dir` looks like a plan directory before anything scans or
+/// writes to it. Returns why it doesn't, e.g. when `PLAN_DIR` points at
+/// `$HOME` by mistake. A directory passes if it has the [`ROOT_MARKER`],
+/// contains any plan file, or has few other entries.
+pub fn check_plan_root(layout: &Layout, dir: &Path) -> crate::Result<Option<String>> {
+    let mut foreign = 0;
+    for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == ROOT_MARKER || is_plan_file(layout, &name) {
+            return Ok(None);
+        }
+        foreign += 1;
+    }
+    if foreign < FOREIGN_ENTRY_LIMIT {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "{} has no plan files and no {} marker, but {} other entries",
+        dir.display(),
+        ROOT_MARKER,
+        foreign
+    )))
+}
+
+/// A plan file that says the system clock has jumped back: the newest-dated
+/// file in the directory, dated after `today`, that was last modified later
+/// than the clock's current time. Files scheduled ahead are dated in the
+/// future too, but were written in the past, so they don't count.
+pub struct ClockSkew {
+    pub newest: chrono::NaiveDate,
+    pub name: String,
+}
+
+/// Look for signs that the system clock is behind the plan directory.
+pub fn clock_skew(
+    layout: &Layout,
+    dir: &Path,
+    today: chrono::NaiveDate,
+) -> crate::Result<Option<ClockSkew>> {
+    let newest = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            let (date, _) = plan_part(layout, &name)?;
+            Some((date, name, e))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    let Some((date, name, entry)) = newest else {
+        return Ok(None);
+    };
+    if date <= today {
+        return Ok(None);
+    }
+    let modified = entry.metadata()?.modified()?;
+    if modified <= std::time::SystemTime::now() {
+        return Ok(None);
+    }
+    Ok(Some(ClockSkew { newest: date, name }))
+}
+
+/// Mark `dir` as a plan directory. An existing marker is left alone.
+pub fn write_root_marker(dir: &Path) -> crate::Result<()> {
+    let marker = dir.join(ROOT_MARKER);
+    if !marker.exists() {
+        fs::write(marker, "")?;
+    }
+    Ok(())
+}
+
 pub fn warn_unexpected_files(unexpected: &[String]) {
     if unexpected.is_empty() {
         return;
@@ -158,19 +325,77 @@ pub fn warn_unexpected_files(unexpected: &[String]) {
     let mut sorted: Vec<&str> = unexpected.iter().map(|s| s.as_str()).collect();
     sorted.sort();
     let names = sorted.join(", ");
-    eprintln!(
-        "plan: warning: unexpected files in plan directory: {} (suppress with warn_unexpected = false)",
-        names
+    crate::diag::warn(
+        crate::diag::Warning::UnexpectedFiles,
+        &format!(
+            "unexpected files in plan directory: {} (suppress with warn_unexpected = false)",
+            names
+        ),
     );
 }
 
 /// Append a line to the inbox in a plan file.
 /// Performs an atomic write to a tempfile, then renames.
-pub fn insert_into_inbox(path: &Path, new_line: &str, _guard: &LockGuard) -> io::Result<()> {
+pub fn insert_into_inbox(
+    layout: &Layout,
+    path: &Path,
+    new_line: &str,
+    guard: &LockGuard,
+) -> crate::Result<()> {
+    insert_into_section(layout, path, "inbox", new_line, guard)
+}
+
+/// Whether the named section (matched case-insensitively) already holds `line`,
+/// ignoring surrounding whitespace. Callers should hold the file lock.
+pub fn section_contains(
+    layout: &Layout,
+    path: &Path,
+    name: &str,
+    line: &str,
+    _guard: &LockGuard,
+) -> crate::Result<bool> {
     let content = fs::read_to_string(path)?;
+    let Some(span) = crate::section::find_section(layout, &content, name) else {
+        return Ok(false);
+    };
+    Ok(content
+        .lines()
+        .skip(span.body().start)
+        .take(span.body().len())
+        .any(|l| l.trim() == line.trim()))
+}
+
+/// Append a line to the end of a named section (matched case-insensitively).
+/// If the section is missing or never closed, it is reconstructed at the end of the file.
+pub fn insert_into_section(
+    layout: &Layout,
+    path: &Path,
+    name: &str,
+    new_line: &str,
+    _guard: &LockGuard,
+) -> crate::Result<()> {
+    let raw = fs::read_to_string(path)?;
+    // Work in LF and convert back on write, so CRLF files stay CRLF (a file
+    // mixing both comes out in whichever ending most of its lines use)
+    let eol = line_ending(&raw);
+    let content = raw.replace("\r\n", "\n");
+    let new_content = insert_into_content(layout, &content, name, new_line);
+    write_atomic(path, &with_line_ending(&new_content, eol))
+}
+
+/// [`insert_into_section`] on LF-terminated `content` in memory.
+pub(crate) fn insert_into_content(
+    layout: &Layout,
+    content: &str,
+    name: &str,
+    new_line: &str,
+) -> String {
+    if let Layout::Obsidian { inbox_heading } = layout {
+        return insert_under_heading(layout, content, inbox_heading, name, new_line);
+    }
 
-    // Find the inbox markers:
-    //   open:  ^~+inbox~+$
+    // Find the section markers:
+    //   open:  ^~+name~+$
     //   close: first ^~+$ (all tildes) after open
     let mut lines: Vec<&str> = content.split('\n').collect();
     // remove the last empty split if it exists because of trailing newline
@@ -178,14 +403,19 @@ pub fn insert_into_inbox(path: &Path, new_line: &str, _guard: &LockGuard) -> io:
         lines.pop();
     }
 
-    let mut inbox_start = None;
-    let mut inbox_end = None;
+    let mut section_start = None;
+    let mut section_end = None;
 
     for (i, line) in lines.iter().enumerate() {
-        if inbox_start.is_none() && is_inbox_open(line) {
-            inbox_start = Some(i);
-        } else if inbox_start.is_some() && inbox_end.is_none() && is_tilde_line(line) {
-            inbox_end = Some(i);
+        let opens =
+            crate::section::open_marker_name(line).is_some_and(|n| n.eq_ignore_ascii_case(name));
+        if section_start.is_none() && opens {
+            section_start = Some(i);
+        } else if section_start.is_some()
+            && section_end.is_none()
+            && crate::section::is_close_marker(line)
+        {
+            section_end = Some(i);
         }
     }
 
@@ -194,66 +424,211 @@ pub fn insert_into_inbox(path: &Path, new_line: &str, _guard: &LockGuard) -> io:
     // Determine width from the first line (header) or use a default
     let width = lines.first().map_or(21, |l| l.len().max(21));
 
-    match (inbox_start, inbox_end) {
+    match (section_start, section_end) {
         (Some(_), Some(end_idx)) => {
-            // Standard case: Inbox is present, inject directly before the closing tilde line
+            // Standard case: Section is present, inject directly before the closing tilde line
             lines.insert(end_idx, new_line);
         }
         _ => {
-            // Edge case: User manually wiped the inbox entirely.
+            // Edge case: User manually wiped the section entirely (or never had one).
             // Dynamically reconstruct it at the exact end of the file.
             if file_needs_newline {
                 lines.push("");
             }
             // Use a collected String so we can reference it as &str in the lines vec
-            let inbox_open = make_inbox_line(width);
-            let inbox_close = "~".repeat(width);
+            let section_open = make_section_line(name, width);
+            let section_close = "~".repeat(width);
             let mut new_lines = Vec::with_capacity(lines.len() + 3);
             new_lines.extend_from_slice(&lines);
-            new_lines.push(&inbox_open);
+            new_lines.push(&section_open);
             new_lines.push(new_line);
-            new_lines.push(&inbox_close);
-            let new_content = new_lines.join("\n") + "\n";
-
-            let tmp_path = path.with_extension(format!("tmp-{}", process::id()));
-            let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
-            {
-                let mut file = File::create(&tmp_path)?;
-                file.write_all(new_content.as_bytes())?;
-                file.sync_all()?;
+            new_lines.push(&section_close);
+            return new_lines.join("\n") + "\n";
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Move every non-blank line out of the inbox, leaving it empty: into the
+/// section `target` when given (created if missing), otherwise to the end
+/// of the file below the `---` separator. One write; returns how many
+/// lines moved.
+pub fn sweep_inbox(
+    layout: &Layout,
+    path: &Path,
+    target: Option<&str>,
+    _guard: &LockGuard,
+) -> crate::Result<usize> {
+    let raw = fs::read_to_string(path)?;
+    let eol = line_ending(&raw);
+    let content = raw.replace("\r\n", "\n");
+    let Some(span) = crate::section::find_section(layout, &content, "inbox") else {
+        return Ok(0);
+    };
+    let body = span.body();
+    let lines: Vec<&str> = content.lines().collect();
+    let items: Vec<&str> = lines[body.clone()]
+        .iter()
+        .copied()
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    let mut kept: Vec<&str> = lines[..body.start].to_vec();
+    kept.extend_from_slice(&lines[body.end..]);
+    let mut swept = kept.join("\n") + "\n";
+    let block = items.join("\n");
+    match target {
+        Some(name) => swept = insert_into_content(layout, &swept, name, &block),
+        None => {
+            let plan_layout = *layout == Layout::Plan;
+            if plan_layout && !kept.iter().any(|l| l.trim_end() == "---") {
+                swept.push_str("---\n");
             }
-            fs::rename(&tmp_path, path)?;
-            tmp_guard.persist();
+            swept.push_str(&block);
+            swept.push('\n');
+        }
+    }
+    write_atomic(path, &with_line_ending(&swept, eol))?;
+    Ok(items.len())
+}
 
-            return Ok(());
+/// Markdown-layout counterpart of [`insert_into_section`]: add `new_line`
+/// after the last non-blank line under the section's heading, or append the
+/// heading and line when the section is missing.
+fn insert_under_heading(
+    layout: &Layout,
+    content: &str,
+    inbox_heading: &str,
+    name: &str,
+    new_line: &str,
+) -> String {
+    let mut lines: Vec<&str> = content.lines().collect();
+    match crate::section::find_section(layout, content, name).and_then(|s| s.close) {
+        Some(end) => lines.insert(end, new_line),
+        None => {
+            while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                lines.pop();
+            }
+            let heading = crate::layout::heading_for(inbox_heading, name);
+            let mut out = lines.join("\n");
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            return format!("{}{}\n{}\n", out, heading, new_line);
         }
     }
+    lines.join("\n") + "\n"
+}
 
-    let new_content = lines.join("\n") + "\n";
+/// Append a block of text to the end of a plan file, keeping one trailing newline.
+pub fn append_to_file(path: &Path, text: &str, _guard: &LockGuard) -> crate::Result<()> {
+    let raw = fs::read_to_string(path)?;
+    let eol = line_ending(&raw);
+    let mut content = raw.replace("\r\n", "\n");
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(&text.replace("\r\n", "\n"));
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    write_atomic(path, &with_line_ending(&content, eol))
+}
 
-    // Atomic write
+/// The line ending most lines of `content` use: `"\r\n"` for files saved on
+/// Windows, otherwise `"\n"`.
+pub fn line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > lf { "\r\n" } else { "\n" }
+}
+
+/// Convert LF-terminated `content` to end every line with `eol`.
+pub fn with_line_ending(content: &str, eol: &str) -> String {
+    if eol == "\n" {
+        content.to_string()
+    } else {
+        content.replace('\n', eol)
+    }
+}
+
+/// Write `content` to a sibling tempfile, fsync it, then rename over `path`.
+///
+/// This only writes; callers changing a plan file take a
+/// [`crate::backup::snapshot`] first.
+pub fn write_atomic(path: &Path, content: &str) -> crate::Result<()> {
     let tmp_path = path.with_extension(format!("tmp-{}", process::id()));
     let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
     {
         let mut file = File::create(&tmp_path)?;
-        file.write_all(new_content.as_bytes())?;
+        file.write_all(content.as_bytes())?;
         file.sync_all()?;
     }
-    fs::rename(&tmp_path, path)?;
+    replace_file(&tmp_path, path)?;
     tmp_guard.persist();
-
     Ok(())
 }
 
+/// Rename `from` over `to`. Windows refuses while another process (an
+/// editor, a virus scanner) briefly holds `to` open, so retry for a moment.
+#[cfg(windows)]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    let mut attempts = 0;
+    loop {
+        match fs::rename(from, to) {
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied && attempts < 10 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn replace_file(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+}
+
+/// Parse the date out of a `YYYY-MM-DD.plan` filename.
+pub fn plan_date(layout: &Layout, name: &str) -> Option<chrono::NaiveDate> {
+    let date_str = strip_extension(layout, name)?;
+    chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()
+}
+
+/// Parse the date and part number out of a plan filename: `YYYY-MM-DD.plan`
+/// is part 1 and `YYYY-MM-DD.N.plan` (N of 2 or more) a continuation file.
+pub fn plan_part(layout: &Layout, name: &str) -> Option<(chrono::NaiveDate, u32)> {
+    if let Some(date) = plan_date(layout, name) {
+        return Some((date, 1));
+    }
+    let (date_str, part) = strip_extension(layout, name)?.split_once('.')?;
+    let part: u32 = part.parse().ok().filter(|p| *p >= 2)?;
+    let date = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()?;
+    Some((date, part))
+}
+
+/// Sort key listing plan files newest day first, with each day's
+/// continuation files following it in order.
+pub fn newest_first_key(layout: &Layout, name: &str) -> (std::cmp::Reverse<String>, u32) {
+    match plan_part(layout, name) {
+        Some((date, part)) => (
+            std::cmp::Reverse(crate::date::format_filename(layout, date)),
+            part,
+        ),
+        None => (std::cmp::Reverse(name.to_string()), 1),
+    }
+}
+
 /// Find the most recent plan file from pre-scanned entries.
-pub fn find_latest(entries: &[fs::DirEntry]) -> Option<std::path::PathBuf> {
+pub fn find_latest(layout: &Layout, entries: &[fs::DirEntry]) -> Option<std::path::PathBuf> {
     entries
         .iter()
-        .filter(|e| {
-            let name = e.file_name().to_string_lossy().into_owned();
-            let date_str = &name[..name.len() - 5];
-            chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d").is_ok()
-        })
+        .filter(|e| plan_date(layout, &e.file_name().to_string_lossy()).is_some())
         .max_by_key(|e| e.file_name())
         .map(|e| e.path())
 }
@@ -0,0 +1,33 @@
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+
+const THEME: &str = "base16-ocean.dark";
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+    &THEME_SET.get_or_init(ThemeSet::load_defaults).themes[THEME]
+}
+
+/// Highlight the lines of a code zone as terminal escapes. `lang` is a
+/// language name or file extension (`rust`, `rs`, `sh`). Returns `None` for
+/// languages syntect doesn't know, so the caller can print the lines as-is.
+pub fn highlight_code(lang: &str, lines: &[&str]) -> Option<Vec<String>> {
+    let syntax = syntaxes().find_syntax_by_token(lang)?;
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    let mut out = Vec::with_capacity(lines.len());
+    for line in lines {
+        let line = format!("{}\n", line);
+        let ranges = highlighter.highlight_line(&line, syntaxes()).ok()?;
+        let escaped = as_24_bit_terminal_escaped(&ranges, false);
+        out.push(format!("{}\x1b[0m", escaped.trim_end_matches('\n')));
+    }
+    Some(out)
+}
@@ -0,0 +1,83 @@
+use crate::config::LockConfig;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const HISTORY: &str = "history";
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Once the log passes this many lines, it is trimmed to the newest half.
+const MAX_ENTRIES: usize = 2000;
+
+/// How often and how recently a plan file was opened in the editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visit {
+    pub path: PathBuf,
+    pub count: usize,
+    pub last: NaiveDateTime,
+}
+
+fn read_log(state_dir: &Path) -> crate::Result<String> {
+    match fs::read_to_string(state_dir.join(HISTORY)) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Append an editor open of `plan_path` at `at` to the history log.
+pub fn record_open(state_dir: &Path, plan_path: &Path, at: NaiveDateTime) -> crate::Result<()> {
+    crate::migrate::ensure_current(state_dir)?;
+    let log = state_dir.join(HISTORY);
+    let lock = crate::file::acquire_lock(&log, &LockConfig::default())?;
+    let entry = format!("{}\t{}", at.format(TIMESTAMP_FORMAT), plan_path.display());
+
+    let content = read_log(state_dir)?;
+    let lines = content.lines().count();
+    if lines >= MAX_ENTRIES {
+        let mut kept: String = content
+            .lines()
+            .skip(lines - MAX_ENTRIES / 2)
+            .map(|l| format!("{}\n", l))
+            .collect();
+        kept.push_str(&entry);
+        kept.push('\n');
+        return crate::file::write_atomic(&log, &kept);
+    }
+    if content.is_empty() {
+        fs::write(&log, "")?;
+    }
+    crate::file::append_to_file(&log, &entry, &lock)
+}
+
+/// Opened files, most recently opened first. The log is append-only, so a
+/// later line is a later open even when timestamps tie. Malformed lines are
+/// skipped.
+pub fn recent(state_dir: &Path) -> crate::Result<Vec<Visit>> {
+    let content = read_log(state_dir)?;
+    let mut visits: HashMap<PathBuf, (usize, Visit)> = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let Some((stamp, path)) = line.split_once('\t') else {
+            continue;
+        };
+        let Ok(at) = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT) else {
+            continue;
+        };
+        let (seq, visit) = visits.entry(PathBuf::from(path)).or_insert((
+            i,
+            Visit {
+                path: PathBuf::from(path),
+                count: 0,
+                last: at,
+            },
+        ));
+        *seq = i;
+        visit.count += 1;
+        visit.last = at;
+    }
+    let mut list: Vec<(usize, Visit)> = visits.into_values().collect();
+    list.sort_by_key(|(seq, _)| std::cmp::Reverse(*seq));
+    Ok(list.into_iter().map(|(_, v)| v).collect())
+}
@@ -0,0 +1,75 @@
+use crate::config::LockConfig;
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const OPENED_MARKER: &str = "opened";
+
+/// Whether `plan_path` has been opened in the editor before, according to the
+/// marker list kept in the state directory.
+pub fn was_opened(state_dir: &Path, plan_path: &Path) -> crate::Result<bool> {
+    match fs::read_to_string(state_dir.join(OPENED_MARKER)) {
+        Ok(content) => {
+            let key = plan_path.to_string_lossy();
+            Ok(content.lines().any(|l| l == key))
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Record that `plan_path` has been opened so its first-open hook won't run again.
+pub fn mark_opened(state_dir: &Path, plan_path: &Path) -> crate::Result<()> {
+    crate::migrate::ensure_current(state_dir)?;
+    let marker = state_dir.join(OPENED_MARKER);
+    let lock = crate::file::acquire_lock(&marker, &LockConfig::default())?;
+    if was_opened(state_dir, plan_path)? {
+        return Ok(());
+    }
+    if !marker.exists() {
+        fs::write(&marker, "")?;
+    }
+    crate::file::append_to_file(&marker, &plan_path.to_string_lossy(), &lock)
+}
+
+/// Build the command for a hook: `command` is split with shell quoting rules
+/// and receives the target file in `PLAN_FILE` and its date in `PLAN_DATE`,
+/// with stdin closed. `what` names the hook in the error for an empty command.
+pub(crate) fn hook_command(
+    what: &str,
+    command: &str,
+    plan_path: &Path,
+    date: NaiveDate,
+) -> crate::Result<(String, Command)> {
+    let args = shlex::split(command).unwrap_or_default();
+    let Some((program, rest)) = args.split_first() else {
+        return Err(crate::Error::InvalidConfig(format!(
+            "Invalid {}: '{}'",
+            what, command
+        )));
+    };
+    let mut cmd = Command::new(program);
+    cmd.args(rest)
+        .env("PLAN_FILE", plan_path)
+        .env("PLAN_DATE", date.format("%Y-%m-%d").to_string())
+        .stdin(Stdio::null());
+    Ok((program.clone(), cmd))
+}
+
+/// Run a first-open hook command and return its stdout.
+///
+/// See `hook_command` for how the command is run. A non-zero exit is an
+/// error.
+pub fn run_first_open(command: &str, plan_path: &Path, date: NaiveDate) -> crate::Result<String> {
+    let (program, mut cmd) = hook_command("first_open hook", command, plan_path, date)?;
+    let output = cmd.stderr(Stdio::inherit()).output()?;
+    if !output.status.success() {
+        return Err(crate::Error::CommandFailed(format!(
+            "first_open hook '{}' exited with {}",
+            program, output.status
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,139 @@
+use crate::export::PlanFile;
+use crate::task;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+/// Escape text for an iCalendar TEXT value (RFC 5545 section 3.3.11).
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Append a content line, folded at 75 octets as RFC 5545 requires.
+fn push_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn ics_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Task text without its `due:` token, for the summary.
+fn summary(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|word| !word.starts_with("due:"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Hands out UIDs for one day from each entry's kind and text, so they
+/// survive lines moving around the file. Repeats of the same text are
+/// numbered in order.
+struct Uids {
+    date: NaiveDate,
+    seen: HashMap<(&'static str, String), usize>,
+}
+
+impl Uids {
+    fn next(&mut self, kind: &'static str, text: &str) -> String {
+        let n = self.seen.entry((kind, text.to_string())).or_insert(0);
+        *n += 1;
+        let hash = crate::index::fnv1a(format!("{}:{}", kind, text).as_bytes());
+        match *n {
+            1 => format!("UID:{}-{}-{:016x}@plan", ics_date(self.date), kind, hash),
+            n => format!(
+                "UID:{}-{}-{:016x}-{}@plan",
+                ics_date(self.date),
+                kind,
+                hash,
+                n
+            ),
+        }
+    }
+}
+
+/// An iCalendar file with a VTODO for every pending task carrying a
+/// `due:YYYY-MM-DD` token and a VEVENT for every `@` event. UIDs come from
+/// the day and the entry's text (without its due date or time), so
+/// exporting again updates entries instead of duplicating them, and DTSTAMP
+/// is the file's date so unchanged days export byte for byte the same.
+pub fn to_ics(files: &[PlanFile]) -> String {
+    let mut out = String::new();
+    push_line(&mut out, "BEGIN:VCALENDAR");
+    push_line(&mut out, "VERSION:2.0");
+    push_line(&mut out, "PRODID:-//plan//plan export//EN");
+    for file in files {
+        let stamp = format!("DTSTAMP:{}T000000Z", ics_date(file.date));
+        let mut uids = Uids {
+            date: file.date,
+            seen: HashMap::new(),
+        };
+        for t in task::parse_tasks(&file.content) {
+            let Some(due) = task::due_date(&t.text).filter(|_| t.state.is_pending()) else {
+                continue;
+            };
+            let text = summary(&t.text);
+            push_line(&mut out, "BEGIN:VTODO");
+            push_line(&mut out, &uids.next("todo", &text));
+            push_line(&mut out, &stamp);
+            // DUE must come after DTSTART, so a task due on or before the
+            // day it was written gets no start
+            if due > file.date {
+                push_line(
+                    &mut out,
+                    &format!("DTSTART;VALUE=DATE:{}", ics_date(file.date)),
+                );
+            }
+            push_line(&mut out, &format!("DUE;VALUE=DATE:{}", ics_date(due)));
+            push_line(&mut out, &format!("SUMMARY:{}", escape(&text)));
+            if let Some(p) = t.priority {
+                // A-C map onto 1-3, the high end of iCalendar's 1-9 scale
+                let level = (p.letter() as u32 - 'A' as u32 + 1).min(9);
+                push_line(&mut out, &format!("PRIORITY:{}", level));
+            }
+            push_line(&mut out, "STATUS:NEEDS-ACTION");
+            push_line(&mut out, "END:VTODO");
+        }
+        for (i, line) in crate::section::prose_lines(&file.content) {
+            let Some(event) = task::parse_event(line, i + 1) else {
+                continue;
+            };
+            push_line(&mut out, "BEGIN:VEVENT");
+            push_line(&mut out, &uids.next("event", &event.text));
+            push_line(&mut out, &stamp);
+            match event.time {
+                Some(time) => push_line(
+                    &mut out,
+                    &format!("DTSTART:{}T{}", ics_date(file.date), time.format("%H%M%S")),
+                ),
+                None => push_line(
+                    &mut out,
+                    &format!("DTSTART;VALUE=DATE:{}", ics_date(file.date)),
+                ),
+            }
+            push_line(&mut out, &format!("SUMMARY:{}", escape(&event.text)));
+            push_line(&mut out, "END:VEVENT");
+        }
+    }
+    push_line(&mut out, "END:VCALENDAR");
+    out
+}
@@ -0,0 +1,282 @@
+use crate::config::LockConfig;
+use crate::layout::Layout;
+use crate::search::{self, SearchMatch};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bumped whenever the on-disk layout changes; older indexes are rebuilt.
+const INDEX_VERSION: u64 = 1;
+
+/// What the index remembers about one plan file.
+struct FileEntry {
+    /// Modification time as (seconds, nanoseconds) since the epoch
+    modified: (u64, u32),
+    len: u64,
+    /// Sorted, lowercased three-character windows of every line
+    trigrams: Vec<String>,
+}
+
+/// A persistent trigram index over a plan directory.
+///
+/// Search uses it to skip files that cannot contain the query, so only the
+/// candidates are read. Files are re-indexed when their size or mtime
+/// changes, so the index never has to be rebuilt by hand.
+pub struct Index {
+    path: PathBuf,
+    dir: PathBuf,
+    files: BTreeMap<String, FileEntry>,
+    dirty: bool,
+}
+
+/// How many files an [`Index::update`] touched.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub indexed: usize,
+    pub removed: usize,
+}
+
+/// Where the index for `plan_dir` lives. Each plan directory gets its own
+/// file, keyed by a hash of its path.
+pub fn index_path(cache_dir: &Path, plan_dir: &Path) -> PathBuf {
+    cache_dir
+        .join("index")
+        .join(format!("{}.json", crate::paths::dir_key(plan_dir)))
+}
+
+/// FNV-1a: stable across builds, unlike the std hasher.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Every three-character window of each lowercased line. Windows never span
+/// lines, matching search, which is line based.
+pub fn trigrams(content: &str) -> BTreeSet<String> {
+    let mut out = BTreeSet::new();
+    for line in content.lines() {
+        let chars: Vec<char> = line.to_lowercase().chars().collect();
+        for window in chars.windows(3) {
+            out.insert(window.iter().collect());
+        }
+    }
+    out
+}
+
+fn modified(meta: &fs::Metadata) -> (u64, u32) {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| (d.as_secs(), d.subsec_nanos()))
+        .unwrap_or_default()
+}
+
+fn parse_entry(value: &Value) -> Option<FileEntry> {
+    let modified = value.get("modified")?.as_array()?;
+    Some(FileEntry {
+        modified: (
+            modified.first()?.as_u64()?,
+            modified.get(1)?.as_u64()? as u32,
+        ),
+        len: value.get("len")?.as_u64()?,
+        trigrams: value
+            .get("trigrams")?
+            .as_array()?
+            .iter()
+            .map(|t| t.as_str().map(str::to_string))
+            .collect::<Option<_>>()?,
+    })
+}
+
+impl Index {
+    /// An index with nothing in it, saved to the usual location.
+    pub fn empty(cache_dir: &Path, plan_dir: &Path) -> Index {
+        Index {
+            path: index_path(cache_dir, plan_dir),
+            dir: plan_dir.to_path_buf(),
+            files: BTreeMap::new(),
+            dirty: true,
+        }
+    }
+
+    /// Load the index for `plan_dir`. A missing, corrupt, or outdated index
+    /// loads as empty and is rebuilt by the next [`Index::update`].
+    pub fn load(cache_dir: &Path, plan_dir: &Path) -> crate::Result<Index> {
+        let mut index = Index::empty(cache_dir, plan_dir);
+        let content = match fs::read_to_string(&index.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e.into()),
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&content) else {
+            return Ok(index);
+        };
+        if value.get("version").and_then(Value::as_u64) != Some(INDEX_VERSION)
+            || value.get("dir").and_then(Value::as_str) != Some(&*plan_dir.to_string_lossy())
+        {
+            return Ok(index);
+        }
+        if let Some(files) = value.get("files").and_then(Value::as_object) {
+            for (name, entry) in files {
+                if let Some(entry) = parse_entry(entry) {
+                    index.files.insert(name.clone(), entry);
+                }
+            }
+        }
+        index.dirty = false;
+        Ok(index)
+    }
+
+    /// Bring the index in line with `entries`: re-index files whose size or
+    /// mtime changed and forget files that are gone.
+    pub fn update(&mut self, entries: &[fs::DirEntry]) -> crate::Result<UpdateStats> {
+        let mut stats = UpdateStats::default();
+        let mut seen = BTreeSet::new();
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let modified = modified(&meta);
+            seen.insert(name.clone());
+            if self
+                .files
+                .get(&name)
+                .is_some_and(|f| f.modified == modified && f.len == meta.len())
+            {
+                continue;
+            }
+            // Unreadable files are left out, as the built-in search skips them too
+            let Ok(content) = fs::read_to_string(entry.path()) else {
+                self.files.remove(&name);
+                continue;
+            };
+            self.files.insert(
+                name,
+                FileEntry {
+                    modified,
+                    len: meta.len(),
+                    trigrams: trigrams(&content).into_iter().collect(),
+                },
+            );
+            stats.indexed += 1;
+        }
+        let before = self.files.len();
+        self.files.retain(|name, _| seen.contains(name));
+        stats.removed = before - self.files.len();
+        if stats.indexed > 0 || stats.removed > 0 {
+            self.dirty = true;
+        }
+        Ok(stats)
+    }
+
+    /// Files that may contain `query`. Queries shorter than three characters
+    /// have no trigrams and match every file.
+    pub fn candidates(&self, query: &str) -> BTreeSet<&str> {
+        let wanted = trigrams(query);
+        self.files
+            .iter()
+            .filter(|(_, f)| wanted.iter().all(|t| f.trigrams.binary_search(t).is_ok()))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Write the index if anything changed since it was loaded.
+    pub fn save(&mut self) -> crate::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let files: serde_json::Map<String, Value> = self
+            .files
+            .iter()
+            .map(|(name, f)| {
+                (
+                    name.clone(),
+                    json!({
+                        "modified": [f.modified.0, f.modified.1],
+                        "len": f.len,
+                        "trigrams": f.trigrams,
+                    }),
+                )
+            })
+            .collect();
+        let value = json!({
+            "version": INDEX_VERSION,
+            "dir": self.dir.to_string_lossy(),
+            "files": files,
+        });
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        crate::file::write_atomic(&self.path, &value.to_string())?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// Hold the index for `plan_dir` exclusively while it is loaded, refreshed,
+/// and saved. Another process that was refreshing at the same time is
+/// waited for, and its work is then loaded instead of repeated.
+pub fn lock(cache_dir: &Path, plan_dir: &Path) -> crate::Result<crate::file::LockGuard> {
+    let path = index_path(cache_dir, plan_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    crate::file::acquire_lock(&path, &LockConfig::default())
+}
+
+/// Search through the index: refresh it, then scan only candidate files.
+/// Results match [`search::search_entries`] exactly.
+pub fn search_indexed(
+    layout: &Layout,
+    cache_dir: &Path,
+    plan_dir: &Path,
+    entries: &[fs::DirEntry],
+    query: &str,
+    ignore_case: bool,
+) -> crate::Result<Vec<SearchMatch>> {
+    let index = {
+        let _lock = lock(cache_dir, plan_dir)?;
+        let mut index = Index::load(cache_dir, plan_dir)?;
+        index.update(entries)?;
+        index.save()?;
+        index
+    };
+    let candidates = index.candidates(query);
+    Ok(search::search_entries(
+        layout,
+        entries
+            .iter()
+            .filter(|e| candidates.contains(&*e.file_name().to_string_lossy())),
+        query,
+        ignore_case,
+    ))
+}
+
+/// Throw away the index for `plan_dir` and index every file from scratch.
+pub fn rebuild(
+    cache_dir: &Path,
+    plan_dir: &Path,
+    entries: &[fs::DirEntry],
+) -> crate::Result<Index> {
+    let _lock = lock(cache_dir, plan_dir)?;
+    let mut index = Index::empty(cache_dir, plan_dir);
+    index.update(entries)?;
+    index.save()?;
+    Ok(index)
+}
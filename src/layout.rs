@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+
+/// How plan files are named and laid out on disk, from the `format` config
+/// key. Everything that names, creates, or edits files takes one, usually
+/// [`crate::config::Config::layout`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// `YYYY-MM-DD.plan` files with `~~~name~~~` sections
+    #[default]
+    Plan,
+    /// Obsidian daily notes: `YYYY-MM-DD.md` files whose sections are
+    /// Markdown headings, with the inbox under `inbox_heading`
+    Obsidian { inbox_heading: String },
+}
+
+/// The inbox heading used when `obsidian.inbox_heading` isn't set.
+pub const DEFAULT_INBOX_HEADING: &str = "## Inbox";
+
+impl Layout {
+    /// Extension of plan files in this layout, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Layout::Plan => "plan",
+            Layout::Obsidian { .. } => "md",
+        }
+    }
+}
+
+/// `## Title` as its level and title, for any level from 1 to 6.
+pub fn parse_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?.trim();
+    (!title.is_empty()).then_some((level, title))
+}
+
+/// The heading line a section called `name` lives under: the configured
+/// inbox heading for the inbox, otherwise `## Name`.
+pub fn heading_for(inbox_heading: &str, name: &str) -> String {
+    if name.eq_ignore_ascii_case("inbox") {
+        inbox_heading.to_string()
+    } else {
+        format!("## {}", name)
+    }
+}
+
+/// A new Obsidian daily note: the date as a title, links to the days either
+/// side, and an empty inbox.
+pub fn obsidian_template(date: NaiveDate, inbox_heading: &str) -> String {
+    let link = |d: Option<NaiveDate>| match d {
+        Some(d) => format!("[[{}]]", d.format("%Y-%m-%d")),
+        None => String::new(),
+    };
+    format!(
+        "# {}\n\n<< {} | {} >>\n\n{}\n",
+        date.format("%A, %B %-d, %Y"),
+        link(date.pred_opt()),
+        link(date.succ_opt()),
+        inbox_heading
+    )
+}
+
+/// An Obsidian note for a continuation file or scratch file: just a title
+/// and an empty inbox.
+pub fn obsidian_template_with_title(title: &str, inbox_heading: &str) -> String {
+    format!("# {}\n\n{}\n", title, inbox_heading)
+}
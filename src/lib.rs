@@ -1,3 +1,57 @@
+pub mod annotations;
+pub mod attach;
+pub mod auto;
+pub mod backup;
+pub mod clipboard;
+pub mod color;
 pub mod config;
+pub mod conflict;
+#[cfg(unix)]
+pub mod daemon;
 pub mod date;
+pub mod diag;
+mod error;
+pub mod export;
 pub mod file;
+#[cfg(feature = "highlight")]
+pub mod highlight;
+pub mod history;
+pub mod hooks;
+pub mod ics;
+pub mod index;
+pub mod layout;
+pub mod lint;
+pub mod mcp;
+pub mod migrate;
+pub mod model;
+pub mod nag;
+pub mod names;
+pub mod opener;
+pub mod paths;
+pub mod plugin;
+pub mod porcelain;
+pub mod recur;
+pub mod rollup;
+pub mod schema;
+pub mod scratch;
+pub mod search;
+pub mod section;
+pub mod selftest;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod standup;
+pub mod stats;
+pub mod sync;
+pub mod tags;
+pub mod task;
+pub mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod todotxt;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod undo;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+pub use error::{Error, Result};
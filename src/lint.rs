@@ -0,0 +1,156 @@
+use crate::layout::{self, Layout};
+use crate::section;
+use chrono::NaiveDate;
+
+/// A structural problem in a plan file. `line` is 1-based; 0 means the file
+/// as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+fn problem(line: usize, message: String) -> Problem {
+    Problem { line, message }
+}
+
+/// Check a `2026, Feb 19 - Thursday` header, with an optional `(part N)`,
+/// against the date in the file name. Headers that don't start with a
+/// digit are left alone, since a template may title files differently.
+fn check_header(header: &str, date: NaiveDate, problems: &mut Vec<Problem>) {
+    if !header.starts_with(|c: char| c.is_ascii_digit()) {
+        return;
+    }
+    let Some((day, rest)) = header.split_once(" - ") else {
+        problems.push(problem(1, format!("header '{}' is not a date", header)));
+        return;
+    };
+    let Ok(parsed) = NaiveDate::parse_from_str(day, "%Y, %b %d") else {
+        problems.push(problem(1, format!("header '{}' is not a date", header)));
+        return;
+    };
+    if parsed != date {
+        problems.push(problem(
+            1,
+            format!(
+                "header date {} doesn't match the file name ({})",
+                parsed.format("%Y-%m-%d"),
+                date.format("%Y-%m-%d")
+            ),
+        ));
+    }
+    let (weekday, extra) = rest.split_once(' ').unwrap_or((rest, ""));
+    let expected = parsed.format("%A").to_string();
+    if weekday != expected {
+        problems.push(problem(
+            1,
+            format!(
+                "header says {} but {} is a {}",
+                weekday,
+                parsed.format("%Y-%m-%d"),
+                expected
+            ),
+        ));
+    }
+    let extra = extra.trim();
+    let is_part = extra
+        .strip_prefix("(part ")
+        .and_then(|s| s.strip_suffix(')'))
+        .is_some_and(|n| n.parse::<u32>().is_ok());
+    if !extra.is_empty() && !is_part {
+        problems.push(problem(
+            1,
+            format!("unexpected text after the header date: '{}'", extra),
+        ));
+    }
+}
+
+/// Tilde lines that aren't valid markers, markers in the wrong place, and
+/// sections left open, outside code zones.
+fn check_markers(content: &str, problems: &mut Vec<Problem>) {
+    let mut open: Option<(usize, &str)> = None;
+    for (i, line) in section::prose_lines(content) {
+        let t = line.trim();
+        if let Some(name) = section::open_marker_name(line) {
+            match open {
+                Some((at, current)) => problems.push(problem(
+                    i + 1,
+                    format!(
+                        "section '{}' starts inside '{}' (line {}), which is still open",
+                        name,
+                        current,
+                        at + 1
+                    ),
+                )),
+                None => open = Some((i, name)),
+            }
+        } else if section::is_close_marker(line) {
+            if open.take().is_none() {
+                problems.push(problem(
+                    i + 1,
+                    "tilde line doesn't close any section".to_string(),
+                ));
+            }
+        } else if t.starts_with("~~~") || t.ends_with("~~~") {
+            problems.push(problem(i + 1, format!("malformed section marker '{}'", t)));
+        }
+    }
+    if let Some((at, name)) = open {
+        problems.push(problem(
+            at + 1,
+            format!("section '{}' is never closed", name),
+        ));
+    }
+}
+
+/// Every problem in one plan file, in line order. `date` is the day from
+/// the file name, when it has one, and is checked against the header.
+pub fn check(layout: &Layout, date: Option<NaiveDate>, content: &str) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let inboxes: Vec<usize> = match layout {
+        Layout::Plan => {
+            if let (Some(date), Some(header)) = (date, content.lines().next()) {
+                check_header(header.trim_end(), date, &mut problems);
+            }
+            check_markers(content, &mut problems);
+            section::parse_sections(content)
+                .iter()
+                .filter(|s| s.name.eq_ignore_ascii_case("inbox"))
+                .map(|s| s.open)
+                .collect()
+        }
+        Layout::Obsidian { inbox_heading } => {
+            let wanted = layout::parse_heading(inbox_heading.trim()).map(|(_, t)| t.to_string());
+            section::prose_lines(content)
+                .filter(|(_, line)| {
+                    layout::parse_heading(line)
+                        .zip(wanted.as_deref())
+                        .is_some_and(|((_, t), w)| t.eq_ignore_ascii_case(w))
+                })
+                .map(|(i, _)| i)
+                .collect()
+        }
+    };
+    match inboxes.split_first() {
+        None => problems.push(problem(0, "no inbox section".to_string())),
+        Some((first, rest)) => {
+            for at in rest {
+                problems.push(problem(
+                    at + 1,
+                    format!(
+                        "another inbox section; new entries only go into the one at line {}",
+                        first + 1
+                    ),
+                ));
+            }
+        }
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        problems.push(problem(
+            content.lines().count(),
+            "no newline at end of file".to_string(),
+        ));
+    }
+    problems.sort_by_key(|p| p.line);
+    problems
+}
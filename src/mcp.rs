@@ -0,0 +1,242 @@
+use crate::config::Config;
+use crate::{backup, date, file, search, template, undo};
+use chrono::NaiveDate;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::{BufRead, Write};
+
+/// Model Context Protocol revision spoken by `plan mcp`.
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Where the tools read and write: the plan directory of `cfg`, skipping
+/// its `scan.ignored_patterns` while searching.
+pub struct Server<'a> {
+    pub cfg: &'a Config,
+}
+
+fn date_property() -> Value {
+    json!({
+        "type": "string",
+        "description": "YYYY-MM-DD or 'today' (the default)",
+    })
+}
+
+/// The `tools/list` result.
+fn tools() -> Value {
+    json!({ "tools": [
+        {
+            "name": "get_plan",
+            "description": "Read a day's plan files, continuation files included.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "date": date_property() },
+            },
+        },
+        {
+            "name": "append_inbox",
+            "description": "Add each non-blank line of text to a day's inbox, as `plan jot` does. \
+                            Today and later days are created on demand.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Lines to add, e.g. '* task' or '@ 14:00 event'" },
+                    "date": date_property(),
+                },
+                "required": ["text"],
+            },
+        },
+        {
+            "name": "search",
+            "description": "Case-insensitive search over every plan file, newest first. \
+                            Each match is FILE:LINE: TEXT.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "query": { "type": "string" } },
+                "required": ["query"],
+            },
+        },
+    ] })
+}
+
+/// A tool's answer: text for the assistant, flagged when the call failed.
+fn tool_result(text: impl Into<String>, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text.into() }],
+        "isError": is_error,
+    })
+}
+
+/// The `date` argument: `today` when missing.
+fn date_argument(args: &Value) -> Result<NaiveDate, String> {
+    match args.get("date").and_then(Value::as_str) {
+        None | Some("today") => Ok(date::today()),
+        Some(s) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map_err(|_| format!("Dates are YYYY-MM-DD or 'today', not '{}'", s)),
+    }
+}
+
+fn string_argument<'a>(args: &'a Value, name: &str) -> Result<&'a str, String> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing string argument '{}'", name))
+}
+
+fn get_plan(cfg: &Config, day: NaiveDate) -> crate::Result<Value> {
+    let paths = date::day_paths(&cfg.layout, &cfg.dir, day);
+    if paths.is_empty() {
+        return Ok(tool_result(
+            format!("No plan file for {}", day.format("%Y-%m-%d")),
+            true,
+        ));
+    }
+    let mut text = String::new();
+    for path in paths {
+        let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+        text.push_str(&fs::read_to_string(&path)?);
+    }
+    Ok(tool_result(text, false))
+}
+
+fn append_inbox(cfg: &Config, day: NaiveDate, text: &str) -> crate::Result<Value> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Ok(tool_result("Nothing to add", true));
+    }
+    let path = date::current_plan_path(&cfg.layout, &cfg.dir, day);
+    let lock = file::acquire_lock(&path, &cfg.lock)?;
+    let before = fs::read_to_string(&path).ok();
+    let template = template::load(&cfg.template)?;
+    match date::ensure_file_exists(
+        &cfg.layout,
+        template.as_deref(),
+        &path,
+        day,
+        day < date::today(),
+    ) {
+        Ok(()) => {}
+        Err(crate::Error::FileMissing(_)) => {
+            return Ok(tool_result(
+                format!("No plan file for {}", day.format("%Y-%m-%d")),
+                true,
+            ));
+        }
+        Err(e) => return Err(e),
+    }
+    backup::snapshot(&path, &cfg.backups)?;
+    for line in &lines {
+        file::insert_into_inbox(&cfg.layout, &path, line, &lock)?;
+    }
+    undo::record_write("mcp", &path, before.as_deref());
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(tool_result(
+        format!("Added {} line(s) to {}", lines.len(), name),
+        false,
+    ))
+}
+
+fn search(cfg: &Config, query: &str) -> crate::Result<Value> {
+    let dir = &cfg.dir;
+    if !dir.exists() {
+        return Ok(tool_result("No matches", false));
+    }
+    let scan = file::scan_plan_dir(&cfg.layout, dir, &cfg.scan.ignored_patterns)?;
+    let matches = search::search_entries(&cfg.layout, &scan.plan_entries, query, true);
+    if matches.is_empty() {
+        return Ok(tool_result("No matches", false));
+    }
+    let text: String = matches
+        .iter()
+        .map(|m| format!("{}:{}: {}\n", m.filename, m.line_number, m.line))
+        .collect();
+    Ok(tool_result(text, false))
+}
+
+impl Server<'_> {
+    /// Run one tool. Bad arguments and I/O failures come back as tool
+    /// errors so the assistant can see them; an unknown tool is a protocol
+    /// error.
+    fn call_tool(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+        let args = params.get("arguments").cloned().unwrap_or(json!({}));
+        let result = match name {
+            "get_plan" => date_argument(&args).map(|day| get_plan(self.cfg, day)),
+            "append_inbox" => date_argument(&args).and_then(|day| {
+                string_argument(&args, "text").map(|text| append_inbox(self.cfg, day, text))
+            }),
+            "search" => string_argument(&args, "query").map(|q| search(self.cfg, q)),
+            _ => return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name))),
+        };
+        Ok(match result {
+            Ok(Ok(value)) => value,
+            Ok(Err(e)) => tool_result(e.to_string(), true),
+            Err(message) => tool_result(message, true),
+        })
+    }
+
+    /// Answer one JSON-RPC message. Notifications get no reply.
+    pub fn handle(&self, message: &Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str);
+        let Some(method) = method else {
+            return Some(error(
+                id.unwrap_or(Value::Null),
+                INVALID_REQUEST,
+                "Missing method",
+            ));
+        };
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(json!({}));
+        let result = match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "plan", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(tools()),
+            "tools/call" => self.call_tool(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error(id, code, &message),
+        })
+    }
+
+    /// Read newline-delimited JSON-RPC messages from `input` and write each
+    /// reply as one line to `output` until `input` closes.
+    pub fn run(&self, input: impl BufRead, mut output: impl Write) -> crate::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+            };
+            if let Some(reply) = reply {
+                writeln!(output, "{}", reply)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
@@ -0,0 +1,149 @@
+use crate::config::LockConfig;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Version of the on-disk state layout written by this build.
+pub const STATE_VERSION: u32 = 1;
+
+const MARKER: &str = "state_version";
+
+/// A single ordered upgrade step from `from` to `from + 1`.
+struct Migration {
+    from: u32,
+    description: &'static str,
+    apply: fn(&Path) -> io::Result<()>,
+}
+
+/// Steps are applied in order; each one must bump the version by exactly one.
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "stamp unversioned state directory",
+    apply: stamp_only,
+}];
+
+fn stamp_only(_state_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Outcome of a migration run that changed the state directory.
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub steps: Vec<&'static str>,
+    pub backup: PathBuf,
+}
+
+/// Read the version marker. A directory without a marker predates versioning (0).
+pub fn read_version(state_dir: &Path) -> io::Result<u32> {
+    match fs::read_to_string(state_dir.join(MARKER)) {
+        Ok(content) => content.trim().parse::<u32>().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Corrupt state marker {}: expected an integer, found '{}'",
+                    state_dir.join(MARKER).display(),
+                    content.trim()
+                ),
+            )
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_version(state_dir: &Path, version: u32) -> io::Result<()> {
+    Ok(crate::file::write_atomic(
+        &state_dir.join(MARKER),
+        &format!("{}\n", version),
+    )?)
+}
+
+/// Bring an existing state directory up to [`STATE_VERSION`].
+///
+/// A missing directory is left alone; features create it on demand through
+/// [`ensure_current`]. The directory is copied aside before any step runs, and
+/// state written by a newer build is refused rather than guessed at.
+pub fn run(state_dir: &Path) -> io::Result<Option<MigrationReport>> {
+    if !state_dir.is_dir() {
+        return Ok(None);
+    }
+    if read_version(state_dir)? == STATE_VERSION {
+        return Ok(None);
+    }
+
+    let _lock = crate::file::acquire_lock(&state_dir.join(MARKER), &LockConfig::default())?;
+    // Re-check under the lock: a concurrent invocation may have migrated already
+    let from = read_version(state_dir)?;
+    if from == STATE_VERSION {
+        return Ok(None);
+    }
+    if from > STATE_VERSION {
+        return Err(io::Error::other(format!(
+            "State in {} is version {}, but this build of plan only understands up to version {}. \
+             Upgrade plan, or move the directory aside to start fresh.",
+            state_dir.display(),
+            from,
+            STATE_VERSION
+        )));
+    }
+
+    let backup = backup_dir(state_dir, from)?;
+    let mut steps = Vec::new();
+    for step in MIGRATIONS.iter().filter(|m| m.from >= from) {
+        (step.apply)(state_dir)?;
+        write_version(state_dir, step.from + 1)?;
+        steps.push(step.description);
+    }
+
+    Ok(Some(MigrationReport {
+        from,
+        to: STATE_VERSION,
+        steps,
+        backup,
+    }))
+}
+
+/// Create the state directory if needed, stamping fresh directories with the
+/// current version and migrating existing ones.
+pub fn ensure_current(state_dir: &Path) -> io::Result<Option<MigrationReport>> {
+    if !state_dir.exists() {
+        fs::create_dir_all(state_dir)?;
+        write_version(state_dir, STATE_VERSION)?;
+        return Ok(None);
+    }
+    run(state_dir)
+}
+
+/// Copy the state directory to a sibling `<name>.bak-v<version>` directory.
+fn backup_dir(state_dir: &Path, version: u32) -> io::Result<PathBuf> {
+    let name = state_dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "state".to_string());
+    let mut backup = state_dir.with_file_name(format!("{}.bak-v{}", name, version));
+    let mut n = 1;
+    while backup.exists() {
+        backup = state_dir.with_file_name(format!("{}.bak-v{}-{}", name, version, n));
+        n += 1;
+    }
+    copy_dir(state_dir, &backup)?;
+    Ok(backup)
+}
+
+fn copy_dir(src: &Path, dst: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        if name.to_string_lossy().ends_with(".lock") {
+            continue;
+        }
+        let target = dst.join(&name);
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+
+pub use crate::task::{Task, TaskState};
+
+/// How a line ends in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+    /// The last line of a file without a trailing newline
+    None,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::None => "",
+        }
+    }
+}
+
+/// One line of a plan file. `text` never contains the line ending.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub text: String,
+    pub ending: LineEnding,
+}
+
+impl Line {
+    pub fn new(text: impl Into<String>, ending: LineEnding) -> Self {
+        Self {
+            text: text.into(),
+            ending,
+        }
+    }
+
+    /// The line as a task, if it starts with a task sigil.
+    pub fn task(&self) -> Option<Task> {
+        crate::task::parse_task(&self.text, 0)
+    }
+}
+
+/// A named `~~~name~~~` block. `close` is `None` when the file ends before
+/// the closing tilde line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub name: String,
+    pub open: Line,
+    pub lines: Vec<Line>,
+    pub close: Option<Line>,
+}
+
+impl Section {
+    /// Tasks in the section, numbered from 1 within the section.
+    pub fn tasks(&self) -> Vec<Task> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, l)| crate::task::parse_task(&l.text, i + 1))
+            .collect()
+    }
+
+    /// Append `text` as the last line of the section, ending it the same way
+    /// as the opener.
+    pub fn push(&mut self, text: impl Into<String>) {
+        let eol = match self.open.ending {
+            LineEnding::None => LineEnding::Lf,
+            ending => ending,
+        };
+        let previous = self.lines.last_mut().unwrap_or(&mut self.open);
+        // Only the last line of the file goes without an ending, and the new
+        // line takes its place
+        let ending = if previous.ending == LineEnding::None {
+            previous.ending = eol;
+            LineEnding::None
+        } else {
+            eol
+        };
+        self.lines.push(Line::new(text, ending));
+    }
+}
+
+/// A stretch of a plan file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// Lines outside any section: the header, the `---` separator, notes
+    Lines(Vec<Line>),
+    Section(Section),
+}
+
+/// A parsed plan file: runs of free lines and named sections, in file
+/// order. Every line keeps its exact text and ending, so formatting a parsed
+/// plan with `to_string` gives back the original bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Plan {
+    pub blocks: Vec<Block>,
+}
+
+/// Split `content` into lines, keeping each line's ending.
+fn split_lines(content: &str) -> Vec<Line> {
+    content
+        .split_inclusive('\n')
+        .map(|raw| {
+            if let Some(text) = raw.strip_suffix("\r\n") {
+                Line::new(text, LineEnding::CrLf)
+            } else if let Some(text) = raw.strip_suffix('\n') {
+                Line::new(text, LineEnding::Lf)
+            } else {
+                Line::new(raw, LineEnding::None)
+            }
+        })
+        .collect()
+}
+
+impl Plan {
+    /// Parse a plan file. Sections are found the same way as
+    /// [`crate::section::parse_sections`]: they don't nest, and the first
+    /// bare tilde line after an opener closes it. Parsing never fails; any
+    /// text is a valid plan.
+    pub fn parse(content: &str) -> Plan {
+        let mut blocks = Vec::new();
+        let mut loose: Vec<Line> = Vec::new();
+        let mut open: Option<Section> = None;
+
+        for line in split_lines(content) {
+            if let Some(mut section) = open.take() {
+                if crate::section::is_close_marker(&line.text) {
+                    section.close = Some(line);
+                    blocks.push(Block::Section(section));
+                } else {
+                    section.lines.push(line);
+                    open = Some(section);
+                }
+            } else if let Some(name) = crate::section::open_marker_name(&line.text) {
+                if !loose.is_empty() {
+                    blocks.push(Block::Lines(std::mem::take(&mut loose)));
+                }
+                open = Some(Section {
+                    name: name.to_string(),
+                    open: line,
+                    lines: Vec::new(),
+                    close: None,
+                });
+            } else {
+                loose.push(line);
+            }
+        }
+        if let Some(section) = open {
+            blocks.push(Block::Section(section));
+        }
+        if !loose.is_empty() {
+            blocks.push(Block::Lines(loose));
+        }
+        Plan { blocks }
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = &Section> {
+        self.blocks.iter().filter_map(|b| match b {
+            Block::Section(s) => Some(s),
+            Block::Lines(_) => None,
+        })
+    }
+
+    /// The first section called `name` (case-insensitive).
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut Section> {
+        self.blocks.iter_mut().find_map(|b| match b {
+            Block::Section(s) if s.name.eq_ignore_ascii_case(name) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Every line in file order.
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.blocks
+            .iter()
+            .flat_map(|b| -> Box<dyn Iterator<Item = &Line>> {
+                match b {
+                    Block::Lines(lines) => Box::new(lines.iter()),
+                    Block::Section(s) => Box::new(
+                        std::iter::once(&s.open)
+                            .chain(s.lines.iter())
+                            .chain(s.close.iter()),
+                    ),
+                }
+            })
+    }
+
+    /// Every task in the file, numbered by file line and skipping code
+    /// zones, like [`crate::task::parse_tasks`].
+    pub fn tasks(&self) -> Vec<Task> {
+        crate::task::parse_tasks(&self.to_string())
+    }
+}
+
+impl fmt::Display for Plan {
+    /// The file's exact bytes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            f.write_str(&line.text)?;
+            f.write_str(line.ending.as_str())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::task::{Task, TaskState};
+use chrono::NaiveDate;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// An open task found in a plan file, with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub date: NaiveDate,
+    pub filename: String,
+    pub line_number: usize,
+    /// The task line as written
+    pub line: String,
+}
+
+/// Whether `task`, from a file dated `date`, belongs in a nag. In-progress
+/// and prioritized tasks always do; plain inbox items only when
+/// `include_inbox` is set and they are from before today. Tasks in files
+/// scheduled after today are not due yet.
+pub fn wanted(task: &Task, date: NaiveDate, today: NaiveDate, include_inbox: bool) -> bool {
+    if date > today || !task.state.is_pending() {
+        return false;
+    }
+    task.state == TaskState::Open || task.priority.is_some() || (include_inbox && date < today)
+}
+
+/// Age groups, youngest first, with the most days old each one holds.
+const GROUPS: [(&str, i64); 4] = [
+    ("Today", 0),
+    ("This week", 6),
+    ("This month", 29),
+    ("Older", i64::MAX),
+];
+
+/// `items` grouped by how many days before `today` they were written down.
+/// Empty groups are left out; within a group, the newest day comes first.
+pub fn group(mut items: Vec<Item>, today: NaiveDate) -> Vec<(&'static str, Vec<Item>)> {
+    items.sort_by(|a, b| {
+        b.filename
+            .cmp(&a.filename)
+            .then(a.line_number.cmp(&b.line_number))
+    });
+    let mut groups: Vec<(&'static str, Vec<Item>)> = Vec::new();
+    for item in items {
+        let age = (today - item.date).num_days();
+        let (label, _) = GROUPS
+            .iter()
+            .find(|(_, max)| age <= *max)
+            .expect("the last group holds every age");
+        match groups.last_mut() {
+            Some((current, list)) if current == label => list.push(item),
+            _ => groups.push((label, vec![item])),
+        }
+    }
+    groups
+}
+
+/// The nag as plain text: a heading per group, then `file:line: task`.
+pub fn render(groups: &[(&str, Vec<Item>)]) -> String {
+    let mut out = String::new();
+    for (label, items) in groups {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{} ({})\n", label, items.len()));
+        for item in items {
+            out.push_str(&format!(
+                "  {}:{}: {}\n",
+                item.filename, item.line_number, item.line
+            ));
+        }
+    }
+    out
+}
+
+/// A plain-text email to `to`, ready for `sendmail -t`.
+pub fn message(to: &str, subject: &str, body: &str) -> String {
+    format!(
+        "To: {}\nSubject: {}\nMIME-Version: 1.0\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        to, subject, body
+    )
+}
+
+/// Pipe `message` to the mail command, split with shell quoting rules. A
+/// non-zero exit is an error.
+pub fn send(command: &str, message: &str) -> io::Result<()> {
+    let args = shlex::split(command).unwrap_or_default();
+    let Some((program, rest)) = args.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid mail command: '{}'", command),
+        ));
+    };
+
+    let mut child = Command::new(program)
+        .args(rest)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that exits without reading is reported by its status below
+        match stdin.write_all(message.as_bytes()) {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "mail command '{}' exited with {}",
+            program, status
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Manifest of named dates, stored in the plan directory so it syncs with the plans.
+pub const MANIFEST_NAME: &str = ".plan-names";
+
+/// Mnemonic names for dates (`launch-day = 2026-03-14`).
+#[derive(Default)]
+pub struct Names {
+    entries: BTreeMap<String, NaiveDate>,
+}
+
+pub fn manifest_path(dir: &Path) -> PathBuf {
+    dir.join(MANIFEST_NAME)
+}
+
+/// Whether `name` is usable as a date name: letters, digits, `-` and `_`,
+/// starting with a letter (so it can't be confused with a date or an
+/// option), and not one of the built-in date words.
+pub fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && crate::date::parse_date_opt(Some(name)).is_err()
+}
+
+impl Names {
+    /// Load the manifest from `dir`. A missing manifest means no names.
+    /// Malformed lines are skipped.
+    pub fn load(dir: &Path) -> crate::Result<Self> {
+        let content = match fs::read_to_string(manifest_path(dir)) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        let entries = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    return None;
+                }
+                let (name, date) = line.split_once('=')?;
+                let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+                Some((name.trim().to_lowercase(), date))
+            })
+            .collect();
+        Ok(Self { entries })
+    }
+
+    /// Write the manifest atomically. Callers should hold the manifest lock.
+    pub fn save(&self, dir: &Path) -> crate::Result<()> {
+        let content: String = self
+            .entries
+            .iter()
+            .map(|(name, date)| format!("{} = {}\n", name, date.format("%Y-%m-%d")))
+            .collect();
+        crate::file::write_atomic(&manifest_path(dir), &content)
+    }
+
+    /// Look up a name (case-insensitive).
+    pub fn get(&self, name: &str) -> Option<NaiveDate> {
+        self.entries.get(&name.trim().to_lowercase()).copied()
+    }
+
+    /// Add or move a name. Returns the date it previously pointed at.
+    pub fn set(&mut self, name: &str, date: NaiveDate) -> Option<NaiveDate> {
+        self.entries.insert(name.to_lowercase(), date)
+    }
+
+    /// Names ordered by date, then name.
+    pub fn by_date(&self) -> Vec<(&str, NaiveDate)> {
+        let mut list: Vec<(&str, NaiveDate)> =
+            self.entries.iter().map(|(n, d)| (n.as_str(), *d)).collect();
+        list.sort_by_key(|(n, d)| (*d, *n));
+        list
+    }
+}
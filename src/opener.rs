@@ -0,0 +1,70 @@
+use std::env;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// The command that hands `path` to the desktop's default application:
+/// `$PLAN_OPENER` when it is set, otherwise `open` on macOS, the shell's URL
+/// handler on Windows, and `xdg-open` elsewhere.
+pub fn opener_command(path: &Path) -> Command {
+    let custom = env::var("PLAN_OPENER")
+        .ok()
+        .and_then(|spec| shlex::split(&spec))
+        .filter(|words| !words.is_empty());
+    if let Some(words) = custom {
+        let mut command = Command::new(&words[0]);
+        command.args(&words[1..]).arg(path);
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg(path);
+        command
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd would split a URL at `&` and expand `%`
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler").arg(path);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(path);
+        command
+    }
+}
+
+/// Open `path` with the platform opener without waiting for the application
+/// it launches. Fails if the opener itself is missing or reports an error.
+pub fn open_path(path: &Path) -> io::Result<()> {
+    let status = opener_command(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "could not open {} ({})",
+            path.display(),
+            status
+        )))
+    }
+}
+
+/// A byte count as a short human-readable size: `512 B`, `1.5 KiB`, `12 MiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Move up a unit before rounding would print 1024
+    while size >= 1023.5 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size < 10.0 {
+        format!("{:.1} {}", size, UNITS[unit])
+    } else {
+        format!("{:.0} {}", size, UNITS[unit])
+    }
+}
@@ -0,0 +1,62 @@
+use crate::config::{expand_tilde, platform_dir};
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// `$VAR/plan` when the XDG variable is set, otherwise `unix/plan`, or
+/// `%LOCALAPPDATA%\plan\<windows>` on Windows.
+fn xdg_dir(var: &str, unix: &str, windows: &str) -> PathBuf {
+    if let Ok(xdg) = env::var(var)
+        && !xdg.is_empty()
+    {
+        return PathBuf::from(xdg).join("plan");
+    }
+    if cfg!(windows) {
+        return platform_dir(dirs::data_local_dir, "~")
+            .join("plan")
+            .join(windows);
+    }
+    expand_tilde(unix).join("plan")
+}
+
+/// Directory for plan's own bookkeeping (the undo journal, open history,
+/// format markers).
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", "~/.local/state", "state")
+}
+
+/// Directory for state worth keeping but not syncing: backups and the
+/// sync backend's last-known copies.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", "~/.local/share", "data")
+}
+
+/// Directory for anything plan can rebuild from the plan files, like the
+/// search index.
+pub fn cache_dir() -> PathBuf {
+    xdg_dir("XDG_CACHE_HOME", "~/.cache", "cache")
+}
+
+/// Name that keeps one plan directory's derived state apart from another's:
+/// a hash of its path, ignoring a trailing separator or `.` components.
+pub fn dir_key(plan_dir: &Path) -> String {
+    let normalized: PathBuf = plan_dir.components().collect();
+    format!(
+        "{:016x}",
+        crate::index::fnv1a(normalized.to_string_lossy().as_bytes())
+    )
+}
+
+/// Where snapshots of `plan_dir`'s files are kept.
+pub fn backups_dir(plan_dir: &Path) -> PathBuf {
+    data_dir().join("backups").join(dir_key(plan_dir))
+}
+
+/// Where `plan daemon` listens for `plan_dir`: under `$XDG_RUNTIME_DIR` when
+/// it is set, otherwise the state directory.
+pub fn socket_path(plan_dir: &Path) -> PathBuf {
+    let dir = match env::var("XDG_RUNTIME_DIR") {
+        Ok(runtime) if !runtime.is_empty() => PathBuf::from(runtime).join("plan"),
+        _ => state_dir(),
+    };
+    dir.join(format!("{}.sock", dir_key(plan_dir)))
+}
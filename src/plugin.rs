@@ -0,0 +1,52 @@
+use chrono::NaiveDate;
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Plugins are executables named `plan-NAME`, run as `plan NAME`.
+pub const PREFIX: &str = "plan-";
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The first `plan-NAME` executable on `PATH`. Names that look like options
+/// or paths never match.
+pub fn find(name: &str) -> Option<PathBuf> {
+    if name.is_empty() || name.starts_with('-') || name.contains(['/', '\\']) {
+        return None;
+    }
+    let file = format!("{}{}{}", PREFIX, name, env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+/// Run a plugin with `args`, telling it where plan's files are through
+/// `PLAN_DIR`, `PLAN_CONFIG`, and `PLAN_DATE`, and return its exit code.
+pub fn run(
+    exe: &Path,
+    args: &[OsString],
+    plan_dir: &Path,
+    config_path: &Path,
+    date: NaiveDate,
+) -> io::Result<i32> {
+    let status = Command::new(exe)
+        .args(args)
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_CONFIG", config_path)
+        .env("PLAN_DATE", date.format("%Y-%m-%d").to_string())
+        .status()?;
+    // Killed by a signal: report failure like a shell would
+    Ok(status.code().unwrap_or(1))
+}
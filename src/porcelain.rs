@@ -0,0 +1,76 @@
+use crate::search::SearchMatch;
+use crate::stats::DayCounts;
+use crate::task::Task;
+use chrono::NaiveDate;
+
+/// Versions of the `--porcelain` output. A released version never changes:
+/// adding, removing, or reordering fields means a new version, and the old
+/// one stays available for the scripts that ask for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    V1,
+    /// Adds the directory to `plan ls` records.
+    V2,
+}
+
+/// A field with backslashes, tabs, and line breaks escaped as `\\`, `\t`,
+/// `\n`, and `\r`, so every record stays on one line.
+pub fn field(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn record(fields: &[String]) -> String {
+    fields.join("\t")
+}
+
+/// `plan ls`: date, weekday (Mon..Sun), lines, open, done, files, and from
+/// v2 on the directory: the extra directory's label, or empty for the plan
+/// directory.
+pub fn ls(version: Version, date: NaiveDate, counts: &DayCounts, dir: Option<&str>) -> String {
+    let mut fields = vec![
+        date.format("%Y-%m-%d").to_string(),
+        date.format("%a").to_string(),
+        counts.lines.to_string(),
+        counts.open.to_string(),
+        counts.done.to_string(),
+        counts.files.to_string(),
+    ];
+    if version >= Version::V2 {
+        fields.push(field(dir.unwrap_or_default()));
+    }
+    record(&fields)
+}
+
+/// `plan search`: file, line, text.
+pub fn search(_version: Version, m: &SearchMatch) -> String {
+    record(&[
+        field(&m.filename),
+        m.line_number.to_string(),
+        field(&m.line),
+    ])
+}
+
+/// `plan tasks`: file, line, number within the day, state, priority letter
+/// (empty when unset), text without the sigil or priority.
+pub fn task(_version: Version, filename: &str, number: usize, task: &Task) -> String {
+    record(&[
+        field(filename),
+        task.line_number.to_string(),
+        number.to_string(),
+        task.state.name().to_string(),
+        task.priority
+            .map(|p| p.letter().to_string())
+            .unwrap_or_default(),
+        field(&task.text),
+    ])
+}
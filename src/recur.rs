@@ -0,0 +1,113 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Recurring entries, stored in the plan directory so they sync with the plans.
+pub const RULES_NAME: &str = ".plan-recurring";
+
+/// When a rule's entry is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schedule {
+    Daily,
+    /// Monday through Friday
+    Weekdays,
+    /// Saturday and Sunday
+    Weekends,
+    Weekly(Weekday),
+    /// That day of every month; months without it are skipped
+    Monthly(u32),
+    /// Month and day of every year
+    Yearly(u32, u32),
+}
+
+impl Schedule {
+    /// Parse `daily`, `weekdays`, `weekends`, a weekday name (`mon`,
+    /// `monday`), `monthly N`, or `yearly MM-DD`, ignoring case.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim().to_lowercase();
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["daily"] => Some(Schedule::Daily),
+            ["weekdays"] => Some(Schedule::Weekdays),
+            ["weekends"] => Some(Schedule::Weekends),
+            ["monthly", day] => day
+                .parse()
+                .ok()
+                .filter(|d| (1..=31).contains(d))
+                .map(Schedule::Monthly),
+            ["yearly", md] => {
+                // A leap year, so Feb 29 is accepted
+                let date = NaiveDate::parse_from_str(&format!("2024-{}", md), "%Y-%m-%d").ok()?;
+                Some(Schedule::Yearly(date.month(), date.day()))
+            }
+            [day] => day.parse::<Weekday>().ok().map(Schedule::Weekly),
+            _ => None,
+        }
+    }
+
+    pub fn matches(self, date: NaiveDate) -> bool {
+        let weekday = date.weekday();
+        match self {
+            Schedule::Daily => true,
+            Schedule::Weekdays => !matches!(weekday, Weekday::Sat | Weekday::Sun),
+            Schedule::Weekends => matches!(weekday, Weekday::Sat | Weekday::Sun),
+            Schedule::Weekly(day) => weekday == day,
+            Schedule::Monthly(day) => date.day() == day,
+            Schedule::Yearly(month, day) => date.month() == month && date.day() == day,
+        }
+    }
+}
+
+/// One line of the rules file: `SCHEDULE = ENTRY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub schedule: Schedule,
+    /// Added to the inbox as written, e.g. `* water the plants`
+    pub entry: String,
+}
+
+pub fn rules_path(dir: &Path) -> PathBuf {
+    dir.join(RULES_NAME)
+}
+
+/// Parse rules, one `SCHEDULE = ENTRY` per line. Blank lines, `#` comments,
+/// and malformed lines are skipped.
+pub fn parse(content: &str) -> Vec<Rule> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (schedule, entry) = line.split_once('=')?;
+            let entry = entry.trim();
+            if entry.is_empty() {
+                return None;
+            }
+            Some(Rule {
+                schedule: Schedule::parse(schedule)?,
+                entry: entry.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Load the rules from `dir`. A missing file means no rules.
+pub fn load(dir: &Path) -> io::Result<Vec<Rule>> {
+    match fs::read_to_string(rules_path(dir)) {
+        Ok(content) => Ok(parse(&content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The entries due on `date`, in the order the rules are written.
+pub fn due_on(rules: &[Rule], date: NaiveDate) -> Vec<&str> {
+    rules
+        .iter()
+        .filter(|r| r.schedule.matches(date))
+        .map(|r| r.entry.as_str())
+        .collect()
+}
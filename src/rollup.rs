@@ -0,0 +1,196 @@
+use crate::stats::DayCounts;
+use crate::task::{Priority, TaskState};
+use chrono::{Datelike, Months, NaiveDate};
+use std::io::{self, BufRead, Write};
+
+/// A line worth carrying into a rollup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Highlight {
+    pub date: NaiveDate,
+    /// The line as it should appear in the rollup, without any `! ` mark
+    pub text: String,
+    /// Higher is more notable: 2 for lines marked `! `, 1 for completed
+    /// top-priority tasks
+    pub score: u8,
+}
+
+/// Lines in one day's `content` that could be rollup highlights: notes marked
+/// with a leading `! `, and `(A)` tasks that were done. Code zones are skipped.
+pub fn highlight_candidates(date: NaiveDate, content: &str) -> Vec<Highlight> {
+    crate::section::prose_lines(content)
+        .filter_map(|(i, line)| {
+            if let Some(text) = line.strip_prefix("! ") {
+                let text = text.trim();
+                return (!text.is_empty()).then(|| Highlight {
+                    date,
+                    text: text.to_string(),
+                    score: 2,
+                });
+            }
+            let task = crate::task::parse_task(line, i + 1)?;
+            (task.state == TaskState::Done && task.priority == Priority::parse("A")).then(|| {
+                Highlight {
+                    date,
+                    text: line.to_string(),
+                    score: 1,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Order candidates for review: most notable first, then oldest first.
+pub fn rank(candidates: &mut [Highlight]) {
+    candidates.sort_by(|a, b| b.score.cmp(&a.score).then(a.date.cmp(&b.date)));
+}
+
+/// Walk through `candidates`, asking on `output` whether to include each one,
+/// and return the ones kept. Empty answers keep the default, which is to
+/// include marked lines and leave the rest out. Answering `q`, or running out
+/// of input, keeps the defaults for everything not yet asked about.
+pub fn curate(
+    candidates: Vec<Highlight>,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<Vec<Highlight>> {
+    let total = candidates.len();
+    let mut kept = Vec::new();
+    let mut remaining = candidates.into_iter().enumerate();
+    while let Some((i, candidate)) = remaining.next() {
+        let default = candidate.score >= 2;
+        writeln!(
+            output,
+            "[{}/{}] {}  {}",
+            i + 1,
+            total,
+            candidate.date.format("%Y-%m-%d"),
+            candidate.text
+        )?;
+        let include = loop {
+            write!(
+                output,
+                "  include? {} ",
+                if default { "[Y/n/q]" } else { "[y/N/q]" }
+            )?;
+            output.flush()?;
+            let mut answer = String::new();
+            if input.read_line(&mut answer)? == 0 {
+                writeln!(output)?;
+                break None;
+            }
+            match answer.trim().to_lowercase().as_str() {
+                "" => break Some(default),
+                "y" | "yes" => break Some(true),
+                "n" | "no" => break Some(false),
+                "q" | "quit" => break None,
+                _ => writeln!(output, "  Please answer y, n, or q.")?,
+            }
+        };
+        match include {
+            Some(true) => kept.push(candidate),
+            Some(false) => {}
+            None => {
+                if default {
+                    kept.push(candidate);
+                }
+                kept.extend(remaining.map(|(_, c)| c).filter(|c| c.score >= 2));
+                break;
+            }
+        }
+    }
+    Ok(kept)
+}
+
+/// The span of days a rollup covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    /// Monday through Sunday
+    Week,
+    Month,
+}
+
+impl Period {
+    /// First and last day of the period that contains `date`.
+    pub fn bounds(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let start = match self {
+            Period::Week => {
+                date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+            }
+            Period::Month => date.with_day(1).unwrap_or(date),
+        };
+        let end = match self {
+            Period::Week => start + chrono::Duration::days(6),
+            Period::Month => start
+                .checked_add_months(Months::new(1))
+                .and_then(|d| d.pred_opt())
+                .unwrap_or(start),
+        };
+        (start, end)
+    }
+
+    /// Name of the rollup file for the period that contains `date`, without
+    /// the extension: `2026-W08` or `2026-02`.
+    pub fn stem(self, date: NaiveDate) -> String {
+        match self {
+            Period::Week => date.format("%G-W%V").to_string(),
+            Period::Month => date.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// Title line of the rollup, e.g. `Week 8, 2026 (Feb 16 - Feb 22)` or
+    /// `February 2026`.
+    pub fn title(self, date: NaiveDate) -> String {
+        let (start, end) = self.bounds(date);
+        match self {
+            Period::Week => format!(
+                "Week {}, {} ({} - {})",
+                date.iso_week().week(),
+                date.iso_week().year(),
+                start.format("%b %-d"),
+                end.format("%b %-d")
+            ),
+            Period::Month => date.format("%B %Y").to_string(),
+        }
+    }
+}
+
+/// Everything that goes into a rollup file.
+#[derive(Debug, Clone, Default)]
+pub struct Rollup {
+    pub title: String,
+    pub highlights: Vec<Highlight>,
+    /// Done task lines as written, oldest day first
+    pub completed: Vec<String>,
+    /// Days in the period with a plan file
+    pub days: usize,
+    pub counts: DayCounts,
+}
+
+/// The rollup as a plan file: the title, then Highlights (left out when
+/// there are none), Completed, and Stats. Marked notes keep their `! ` so a
+/// rollup reads like the days it came from.
+pub fn render(rollup: &Rollup) -> String {
+    let mut out = format!("{}\n", rollup.title);
+    if !rollup.highlights.is_empty() {
+        out.push_str("\nHighlights\n");
+        for h in &rollup.highlights {
+            if h.score >= 2 {
+                out.push_str(&format!("! {}\n", h.text));
+            } else {
+                out.push_str(&format!("{}\n", h.text));
+            }
+        }
+    }
+    out.push_str("\nCompleted\n");
+    if rollup.completed.is_empty() {
+        out.push_str("nothing\n");
+    }
+    for line in &rollup.completed {
+        out.push_str(&format!("{}\n", line));
+    }
+    out.push_str(&format!(
+        "\nStats\ndays:        {}\ntasks done:  {}\ntasks open:  {}\nlines:       {}\n",
+        rollup.days, rollup.counts.done, rollup.counts.open, rollup.counts.lines
+    ));
+    out
+}
@@ -0,0 +1,145 @@
+use serde_json::{Value, json};
+
+/// Version of the `--json` output formats, reported as `schema_version` in
+/// every document. It goes up whenever a field is removed, renamed, or
+/// changes type; adding a field doesn't change it.
+pub const VERSION: u64 = 1;
+
+/// Commands whose JSON output has a schema, in the order `plan schema`
+/// lists them.
+pub const NAMES: &[&str] = &["ls", "parse", "search", "tasks", "stats"];
+
+fn string() -> Value {
+    json!({ "type": "string" })
+}
+
+fn date() -> Value {
+    json!({ "type": "string", "format": "date" })
+}
+
+fn count() -> Value {
+    json!({ "type": "integer", "minimum": 0 })
+}
+
+fn line_number() -> Value {
+    json!({ "type": "integer", "minimum": 1 })
+}
+
+fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+/// An object schema where every property is required.
+fn object(properties: Value) -> Value {
+    let required: Vec<&String> = properties
+        .as_object()
+        .map(|p| p.keys().collect())
+        .unwrap_or_default();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+/// A top-level document: `schema_version` plus `properties`.
+fn document(name: &str, description: &str, mut properties: Value) -> Value {
+    properties["schema_version"] = json!({ "const": VERSION });
+    let mut doc = object(properties);
+    doc["$schema"] = json!("https://json-schema.org/draft/2020-12/schema");
+    doc["title"] = json!(format!("plan {} JSON output", name));
+    doc["description"] = json!(description);
+    doc
+}
+
+fn task_state() -> Value {
+    json!({ "enum": ["inbox", "open", "done", "cancelled"] })
+}
+
+fn priority() -> Value {
+    nullable(json!({ "type": "string", "pattern": "^[A-Z]$" }))
+}
+
+/// The JSON Schema for `plan NAME --json`, or `None` for a command without
+/// JSON output.
+pub fn schema(name: &str) -> Option<Value> {
+    let doc = match name {
+        "ls" => document(
+            name,
+            "One entry per day and directory, newest first; continuation files are counted with their day. `dir` names the extra directory a day is from, or is null for the plan directory.",
+            json!({ "days": array(object(json!({
+                "date": date(),
+                "weekday": string(),
+                "lines": count(),
+                "open": count(),
+                "done": count(),
+                "files": count(),
+                "dir": nullable(string()),
+            }))) }),
+        ),
+        "parse" => document(
+            name,
+            "A day's plan files, in part order, split into sections, tasks, and events.",
+            json!({ "files": array(object(json!({
+                "file": string(),
+                "date": date(),
+                "weekday": string(),
+                "sections": array(object(json!({
+                    "name": string(),
+                    "lines": array(string()),
+                }))),
+                "tasks": array(object(json!({
+                    "line": line_number(),
+                    "section": nullable(string()),
+                    "state": task_state(),
+                    "priority": priority(),
+                    "text": string(),
+                }))),
+                "events": array(object(json!({
+                    "line": line_number(),
+                    "time": nullable(json!({ "type": "string", "pattern": "^[0-9]{2}:[0-9]{2}$" })),
+                    "text": string(),
+                }))),
+                "content": string(),
+            }))) }),
+        ),
+        "search" => document(
+            name,
+            "Matching lines in file order.",
+            json!({ "matches": array(object(json!({
+                "file": string(),
+                "line": line_number(),
+                "text": string(),
+            }))) }),
+        ),
+        "tasks" => document(
+            name,
+            "Open tasks, most urgent first.",
+            json!({ "tasks": array(object(json!({
+                "file": string(),
+                "line": line_number(),
+                "number": line_number(),
+                "state": task_state(),
+                "priority": priority(),
+                "text": string(),
+            }))) }),
+        ),
+        "stats" => document(
+            name,
+            "Totals over every dated plan file.",
+            json!({
+                "days": count(),
+                "lines": count(),
+                "first": date(),
+                "last": date(),
+                "longest_streak": count(),
+            }),
+        ),
+        _ => return None,
+    };
+    Some(doc)
+}
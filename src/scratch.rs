@@ -0,0 +1,96 @@
+use crate::layout::Layout;
+use crate::model::{Block, Plan};
+use std::path::{Path, PathBuf};
+
+/// Scratch files are named `scratch-<name>.plan`.
+pub const PREFIX: &str = "scratch-";
+
+/// Whether `name` can name a scratch file: letters, digits, `-` and `_`.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn filename(layout: &Layout, name: &str) -> String {
+    format!("{}{}.{}", PREFIX, name, layout.extension())
+}
+
+/// Where the scratch file called `name` lives, or `None` for an invalid name.
+pub fn path(layout: &Layout, dir: &Path, name: &str) -> Option<PathBuf> {
+    valid_name(name).then(|| dir.join(filename(layout, name)))
+}
+
+/// The scratch name in a `scratch-<name>.plan` filename.
+pub fn name_of<'a>(layout: &Layout, filename: &'a str) -> Option<&'a str> {
+    let name = filename
+        .strip_prefix(PREFIX)?
+        .strip_suffix(layout.extension())?
+        .strip_suffix('.')?;
+    valid_name(name).then_some(name)
+}
+
+/// Initial content for a new scratch file: a dated file's layout with the
+/// scratch name as its header.
+pub fn template(layout: &Layout, name: &str) -> String {
+    crate::date::template_with_header(layout, format!("Scratch: {}", name))
+}
+
+/// What a scratch file contributes when it is merged into a dated file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Promotion {
+    /// Inbox lines, for the dated file's inbox
+    pub inbox: Vec<String>,
+    /// Other sections, by name, with their lines
+    pub sections: Vec<(String, Vec<String>)>,
+    /// Everything below the `---` separator that isn't in a section, for a
+    /// section named after the scratch file
+    pub notes: Vec<String>,
+}
+
+/// Split scratch `content` into the pieces [`Promotion`] describes. The
+/// header line and the first `---` separator are dropped, as are blank lines
+/// in the inbox and blank lines around the notes.
+pub fn promote(content: &str) -> Promotion {
+    let plan = Plan::parse(content);
+    let mut promotion = Promotion::default();
+    let mut separator_seen = false;
+    for (i, block) in plan.blocks.iter().enumerate() {
+        match block {
+            Block::Section(section) if section.name.eq_ignore_ascii_case("inbox") => {
+                promotion.inbox.extend(
+                    section
+                        .lines
+                        .iter()
+                        .filter(|l| !l.text.trim().is_empty())
+                        .map(|l| l.text.clone()),
+                );
+            }
+            Block::Section(section) => promotion.sections.push((
+                section.name.clone(),
+                section.lines.iter().map(|l| l.text.clone()).collect(),
+            )),
+            Block::Lines(lines) => {
+                // The file's first line is the `Scratch: name` header
+                for line in lines.iter().skip(usize::from(i == 0)) {
+                    if !separator_seen && line.text.trim_end() == "---" {
+                        separator_seen = true;
+                        continue;
+                    }
+                    promotion.notes.push(line.text.clone());
+                }
+            }
+        }
+    }
+    while promotion.notes.last().is_some_and(|l| l.trim().is_empty()) {
+        promotion.notes.pop();
+    }
+    let leading = promotion
+        .notes
+        .iter()
+        .take_while(|l| l.trim().is_empty())
+        .count();
+    promotion.notes.drain(..leading);
+    promotion
+}
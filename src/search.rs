@@ -0,0 +1,209 @@
+use crate::layout::Layout;
+use rayon::prelude::*;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// A single matching line from a plan file.
+pub struct SearchMatch {
+    pub filename: String,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// How a query treats case, following ripgrep: smart case (the default)
+/// ignores case unless the query has an uppercase letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    #[default]
+    Smart,
+    Sensitive,
+    Insensitive,
+}
+
+impl Case {
+    /// Whether searching for `query` should ignore case.
+    pub fn ignores_case(self, query: &str) -> bool {
+        match self {
+            Case::Smart => !query.chars().any(char::is_uppercase),
+            Case::Sensitive => false,
+            Case::Insensitive => true,
+        }
+    }
+}
+
+/// Substring search over pre-scanned plan entries, ignoring case when
+/// `ignore_case` is set. Files are read in parallel; results are ordered
+/// newest day first (a day's continuation files after it), then by line
+/// number.
+pub fn search_entries<'a>(
+    layout: &Layout,
+    entries: impl IntoIterator<Item = &'a fs::DirEntry>,
+    query: &str,
+    ignore_case: bool,
+) -> Vec<SearchMatch> {
+    let q_lower = query.to_lowercase();
+    let is_match = |line: &str| {
+        if ignore_case {
+            line.to_lowercase().contains(&q_lower)
+        } else {
+            line.contains(query)
+        }
+    };
+    let mut sorted: Vec<&fs::DirEntry> = entries.into_iter().collect();
+    sorted.sort_by_key(|e| crate::file::newest_first_key(layout, &e.file_name().to_string_lossy()));
+
+    sorted
+        .par_iter()
+        .map(|entry| {
+            let filename = entry.file_name().to_string_lossy().into_owned();
+            let mut matches = Vec::new();
+            if let Ok(content) = fs::read_to_string(entry.path()) {
+                for (i, line) in content.lines().enumerate() {
+                    if is_match(line) {
+                        matches.push(SearchMatch {
+                            filename: filename.clone(),
+                            line_number: i + 1,
+                            line: line.to_string(),
+                        });
+                    }
+                }
+            }
+            matches
+        })
+        .flatten_iter()
+        .collect()
+}
+
+/// Search the plan directory by shelling out to `rg --json`.
+///
+/// Returns `Ok(None)` when `rg` is not installed so callers can fall back to
+/// [`search_entries`]. Results use the same ordering as the built-in engine.
+pub fn search_ripgrep(
+    layout: &Layout,
+    dir: &Path,
+    query: &str,
+    ignore_case: bool,
+) -> io::Result<Option<Vec<SearchMatch>>> {
+    let case = if ignore_case {
+        "--ignore-case"
+    } else {
+        "--case-sensitive"
+    };
+    let output = match Command::new("rg")
+        .args([
+            "--json",
+            case,
+            "--fixed-strings",
+            "--no-ignore",
+            "--max-depth",
+            "1",
+            "--glob",
+            &format!("*.{}", layout.extension()),
+            "--glob",
+            "!.sync-conflict*",
+            "--",
+            query,
+        ])
+        .arg(dir)
+        .output()
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    // rg exits 1 when nothing matched; anything above that is a real failure.
+    if output.status.code().is_none_or(|c| c > 1) {
+        return Err(io::Error::other(format!(
+            "rg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut matches: Vec<SearchMatch> = stdout.lines().filter_map(parse_rg_match).collect();
+    matches.sort_by(|a, b| {
+        crate::file::newest_first_key(layout, &a.filename)
+            .cmp(&crate::file::newest_first_key(layout, &b.filename))
+            .then(a.line_number.cmp(&b.line_number))
+    });
+    Ok(Some(matches))
+}
+
+/// Convert one line of `rg --json` output into a match, skipping the
+/// `begin`/`end`/`summary` messages and non-UTF-8 payloads.
+fn parse_rg_match(json_line: &str) -> Option<SearchMatch> {
+    let msg: serde_json::Value = serde_json::from_str(json_line).ok()?;
+    if msg.get("type")?.as_str()? != "match" {
+        return None;
+    }
+    let data = msg.get("data")?;
+    let path = data.get("path")?.get("text")?.as_str()?;
+    let filename = Path::new(path).file_name()?.to_string_lossy().into_owned();
+    let line_number = data.get("line_number")?.as_u64()? as usize;
+    let text = data.get("lines")?.get("text")?.as_str()?;
+    Some(SearchMatch {
+        filename,
+        line_number,
+        line: text.trim_end_matches(['\n', '\r']).to_string(),
+    })
+}
+
+/// A line that would change under a literal replacement.
+pub struct LineChange {
+    pub line_number: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// Every line of `content` containing `old`, with `old` replaced by `new`
+/// (case-sensitive, all occurrences).
+pub fn line_replacements(content: &str, old: &str, new: &str) -> Vec<LineChange> {
+    if old.is_empty() {
+        return Vec::new();
+    }
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.contains(old))
+        .map(|(i, line)| LineChange {
+            line_number: i + 1,
+            before: line.to_string(),
+            after: line.replace(old, new),
+        })
+        .collect()
+}
+
+/// Score `candidate` against a fuzzy `query`: every query character must
+/// appear in order (case-insensitive). Consecutive runs and matches at the
+/// start of a word score higher. `None` means no match; an empty query
+/// matches everything with score 0.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut prev_match: Option<usize> = None;
+    let mut chars = candidate.chars().enumerate();
+    let mut prev_char = ' ';
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next()?;
+        loop {
+            let (i, c) = chars.next()?;
+            let at_word_start = !prev_char.is_alphanumeric();
+            prev_char = c;
+            if c.to_lowercase().next() == Some(q) {
+                score += 1;
+                if prev_match.is_some_and(|p| p + 1 == i) {
+                    score += 5;
+                }
+                if at_word_start {
+                    score += 3;
+                }
+                prev_match = Some(i);
+                break;
+            }
+        }
+    }
+    Some(score)
+}
@@ -0,0 +1,156 @@
+use crate::layout::{self, Layout};
+
+/// A named tilde-delimited block, e.g. the inbox:
+///
+/// ```text
+/// ~~~~~~~~~inbox~~~~~~~~~
+/// * task
+/// ~~~~~~~~~~~~~~~~~~~~~~~
+/// ```
+///
+/// Line indices are 0-based. `close` is `None` when the block is never closed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionSpan {
+    pub name: String,
+    pub open: usize,
+    pub close: Option<usize>,
+}
+
+impl SectionSpan {
+    /// 0-based indices of the lines between the markers.
+    pub fn body(&self) -> std::ops::Range<usize> {
+        self.open + 1..self.close.unwrap_or(self.open + 1)
+    }
+}
+
+/// Name of the section opened by `line`, if it is a `~~~name~~~` marker.
+pub fn open_marker_name(line: &str) -> Option<&str> {
+    let t = line.trim();
+    if !t.starts_with('~') || !t.ends_with('~') {
+        return None;
+    }
+    let name = t.trim_matches('~');
+    if name.is_empty() || name.contains('~') || name.trim() != name {
+        return None;
+    }
+    Some(name)
+}
+
+/// Whether `line` is a bare tilde line, which closes the open section.
+pub fn is_close_marker(line: &str) -> bool {
+    let t = line.trim();
+    !t.is_empty() && t.chars().all(|c| c == '~')
+}
+
+/// Find every named section in document order. Sections don't nest: the first
+/// bare tilde line after an opener closes it.
+pub fn parse_sections(content: &str) -> Vec<SectionSpan> {
+    let mut sections: Vec<SectionSpan> = Vec::new();
+    let mut open: Option<SectionSpan> = None;
+
+    for (i, line) in content.lines().enumerate() {
+        if let Some(mut current) = open.take() {
+            if is_close_marker(line) {
+                current.close = Some(i);
+                sections.push(current);
+            } else {
+                open = Some(current);
+            }
+        } else if let Some(name) = open_marker_name(line) {
+            open = Some(SectionSpan {
+                name: name.to_string(),
+                open: i,
+                close: None,
+            });
+        }
+    }
+    sections.extend(open);
+    sections
+}
+
+/// The first section called `name` (case-insensitive). With the Obsidian
+/// layout this is a Markdown heading section instead; see
+/// [`find_heading_section`].
+pub fn find_section(layout: &Layout, content: &str, name: &str) -> Option<SectionSpan> {
+    if let Layout::Obsidian { inbox_heading } = layout {
+        return find_heading_section(content, &layout::heading_for(inbox_heading, name));
+    }
+    parse_sections(content)
+        .into_iter()
+        .find(|s| s.name.eq_ignore_ascii_case(name))
+}
+
+/// The section under the first line matching `heading` (case-insensitive,
+/// at any level). It runs to the next heading of the same or a higher level;
+/// `close` is the index just past its last non-blank line, so inserting
+/// there keeps the blank line before the next heading.
+pub fn find_heading_section(content: &str, heading: &str) -> Option<SectionSpan> {
+    let (_, title) = layout::parse_heading(heading.trim())?;
+    let lines: Vec<&str> = content.lines().collect();
+    let (open, level) = lines.iter().enumerate().find_map(|(i, line)| {
+        let (level, t) = layout::parse_heading(line)?;
+        t.eq_ignore_ascii_case(title).then_some((i, level))
+    })?;
+    let end = lines[open + 1..]
+        .iter()
+        .position(|line| layout::parse_heading(line).is_some_and(|(l, _)| l <= level))
+        .map_or(lines.len(), |n| open + 1 + n);
+    let close = (open + 1..end)
+        .rev()
+        .find(|i| !lines[*i].trim().is_empty())
+        .map_or(open + 1, |i| i + 1);
+    Some(SectionSpan {
+        name: title.to_string(),
+        open,
+        close: Some(close),
+    })
+}
+
+/// Lines outside `--- :lang` code zones, with their 0-based indices. Zone
+/// openers and `---` separators themselves are skipped.
+pub fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut in_zone = false;
+    content.lines().enumerate().filter(move |(_, line)| {
+        if let Some(rest) = line.strip_prefix("---") {
+            in_zone = rest.trim_start().starts_with(':');
+            return false;
+        }
+        !in_zone
+    })
+}
+
+/// A `--- :lang` code zone. `body` holds the 0-based indices of its lines,
+/// up to the next `---` line or the end of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeZone {
+    pub lang: String,
+    pub body: std::ops::Range<usize>,
+}
+
+/// Every code zone in document order.
+pub fn code_zones(content: &str) -> Vec<CodeZone> {
+    let mut zones: Vec<CodeZone> = Vec::new();
+    let mut open: Option<CodeZone> = None;
+    let mut count = 0;
+    for (i, line) in content.lines().enumerate() {
+        count = i + 1;
+        let Some(rest) = line.strip_prefix("---") else {
+            continue;
+        };
+        if let Some(mut zone) = open.take() {
+            zone.body.end = i;
+            zones.push(zone);
+        }
+        if let Some(lang) = rest.trim_start().strip_prefix(':') {
+            open = Some(CodeZone {
+                lang: lang.trim().to_string(),
+                body: i + 1..i + 1,
+            });
+        }
+    }
+    if let Some(mut zone) = open {
+        zone.body.end = count;
+        zones.push(zone);
+    }
+    zones
+}
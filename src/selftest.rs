@@ -0,0 +1,258 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+/// What a check expects on stdout or stderr.
+#[derive(Debug, Clone, Copy)]
+pub enum Expect {
+    Empty,
+    Contains(&'static str),
+}
+
+impl Expect {
+    fn check(self, stream: &str, text: &str) -> Result<(), String> {
+        match self {
+            Expect::Empty if text.is_empty() => Ok(()),
+            Expect::Empty => Err(format!("expected no {}, got {:?}", stream, text)),
+            Expect::Contains(needle) if text.contains(needle) => Ok(()),
+            Expect::Contains(needle) => Err(format!(
+                "expected {} to contain {:?}, got {:?}",
+                stream, needle, text
+            )),
+        }
+    }
+}
+
+/// One invocation of the CLI and what it must do.
+pub struct Case {
+    pub name: &'static str,
+    pub args: &'static [&'static str],
+    pub exit: i32,
+    pub stdout: Expect,
+    pub stderr: Expect,
+}
+
+const fn case(
+    name: &'static str,
+    args: &'static [&'static str],
+    exit: i32,
+    stdout: Expect,
+    stderr: Expect,
+) -> Case {
+    Case {
+        name,
+        args,
+        exit,
+        stdout,
+        stderr,
+    }
+}
+
+/// The contract, in order. Later cases see the files earlier ones wrote.
+pub const CASES: &[Case] = &[
+    case(
+        "log adds a task",
+        &["log", "self-test task"],
+        0,
+        Expect::Empty,
+        Expect::Empty,
+    ),
+    case(
+        "jot adds a note",
+        &["jot", "self-test note"],
+        0,
+        Expect::Empty,
+        Expect::Empty,
+    ),
+    case(
+        "show prints today's file",
+        &["show"],
+        0,
+        Expect::Contains("* self-test task\nself-test note\n"),
+        Expect::Empty,
+    ),
+    case(
+        "search finds the task",
+        &["search", "--ignore-case", "SELF-TEST TASK"],
+        0,
+        Expect::Contains(".plan:3: * self-test task"),
+        Expect::Empty,
+    ),
+    case(
+        "ls lists today",
+        &["ls"],
+        0,
+        Expect::Contains("1 open"),
+        Expect::Empty,
+    ),
+    case(
+        "tasks --json is versioned",
+        &["tasks", "--json"],
+        0,
+        Expect::Contains("\"schema_version\": 1"),
+        Expect::Empty,
+    ),
+    case(
+        "empty messages are refused",
+        &["log", ""],
+        2,
+        Expect::Empty,
+        Expect::Contains("plan: Message cannot be empty."),
+    ),
+    case(
+        "--last conflicts with a date",
+        &["log", "x", "yesterday", "--last"],
+        2,
+        Expect::Empty,
+        Expect::Contains("plan: Cannot use --last with a specific date."),
+    ),
+    case(
+        "--last is refused by ls",
+        &["ls", "--last"],
+        2,
+        Expect::Empty,
+        Expect::Contains("plan: --last is not supported with the 'ls' command."),
+    ),
+    case(
+        "--path is refused by ls",
+        &["--path", "ls"],
+        2,
+        Expect::Empty,
+        Expect::Contains(
+            "plan: --path can only be used with the default command, show, log, jot, or event.",
+        ),
+    ),
+    case(
+        "bad dates are usage errors",
+        &["show", "not-a-date"],
+        2,
+        Expect::Empty,
+        Expect::Contains("plan: Invalid date format."),
+    ),
+    case(
+        "past files aren't created",
+        &["log", "too late", "2000-01-01"],
+        2,
+        Expect::Empty,
+        Expect::Contains("plan: No plan file for that date: 2000-01-01.plan"),
+    ),
+    case(
+        "unknown flags are usage errors",
+        &["ls", "--no-such-flag"],
+        2,
+        Expect::Empty,
+        Expect::Contains("unexpected argument '--no-such-flag'"),
+    ),
+];
+
+/// How many processes the locking check runs at once.
+const WRITERS: usize = 16;
+
+/// The result of one check.
+pub struct Outcome {
+    pub name: String,
+    /// Why the check failed; `None` when it passed
+    pub failure: Option<String>,
+}
+
+/// A scratch directory under the system temp dir, removed on drop.
+struct Scratch(PathBuf);
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// `exe` with the environment pointed at `root`, so the user's own config,
+/// history, and plan files are never touched.
+fn command(exe: &Path, root: &Path) -> Command {
+    let mut command = Command::new(exe);
+    command
+        .env("PLAN_DIR", root.join("plan"))
+        .env("XDG_CONFIG_HOME", root.join("config"))
+        .env("XDG_DATA_HOME", root.join("data"))
+        .env("XDG_CACHE_HOME", root.join("cache"))
+        .env("XDG_STATE_HOME", root.join("state"))
+        .env("VISUAL", "true")
+        .env("EDITOR", "true")
+        .env("NO_COLOR", "1")
+        .env_remove("PLAN_MOCK_TIME")
+        .stdin(Stdio::null());
+    command
+}
+
+fn check(case: &Case, output: &Output) -> Result<(), String> {
+    let code = output.status.code().unwrap_or(-1);
+    if code != case.exit {
+        return Err(format!(
+            "expected exit {}, got {} (stderr: {:?})",
+            case.exit,
+            code,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    case.stdout
+        .check("stdout", &String::from_utf8_lossy(&output.stdout))?;
+    case.stderr
+        .check("stderr", &String::from_utf8_lossy(&output.stderr))
+}
+
+/// Many processes log at once; every line must land exactly once.
+fn check_concurrent_writes(exe: &Path, root: &Path) -> Result<(), String> {
+    let handles: Vec<_> = (0..WRITERS)
+        .map(|i| {
+            let mut command = command(exe, root);
+            command.args(["log", &format!("concurrent writer {}", i)]);
+            thread::spawn(move || command.output())
+        })
+        .collect();
+    for handle in handles {
+        let output = handle
+            .join()
+            .map_err(|_| "a writer thread panicked".to_string())?
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "a writer failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    let show = command(exe, root)
+        .arg("show")
+        .output()
+        .map_err(|e| e.to_string())?;
+    let content = String::from_utf8_lossy(&show.stdout);
+    for i in 0..WRITERS {
+        let line = format!("* concurrent writer {}\n", i);
+        let count = content.matches(&line).count();
+        if count != 1 {
+            return Err(format!("{:?} appears {} times", line.trim_end(), count));
+        }
+    }
+    Ok(())
+}
+
+/// Run every check against `exe` in a fresh temporary directory.
+pub fn run(exe: &Path) -> io::Result<Vec<Outcome>> {
+    let root = std::env::temp_dir().join(format!("plan-self-test-{}", std::process::id()));
+    let scratch = Scratch(root);
+    fs::create_dir_all(scratch.0.join("plan"))?;
+
+    let mut outcomes = Vec::new();
+    for case in CASES {
+        let output = command(exe, &scratch.0).args(case.args).output()?;
+        outcomes.push(Outcome {
+            name: case.name.to_string(),
+            failure: check(case, &output).err(),
+        });
+    }
+    outcomes.push(Outcome {
+        name: format!("{} concurrent writers keep every line", WRITERS),
+        failure: check_concurrent_writes(exe, &scratch.0).err(),
+    });
+    Ok(outcomes)
+}
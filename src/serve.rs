@@ -0,0 +1,290 @@
+use crate::config::Config;
+use crate::{backup, date, file, schema, stats, template, undo};
+use chrono::NaiveDate;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::net::IpAddr;
+
+/// Request bodies past this size are refused.
+const MAX_BODY: u64 = 64 * 1024;
+
+/// An HTTP response before it is written to the connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn text(status: u16, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.into(),
+        }
+    }
+
+    fn json(status: u16, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: value.to_string(),
+        }
+    }
+}
+
+/// A date in a URL: `today` or `YYYY-MM-DD`.
+fn parse_date(segment: &str) -> Option<NaiveDate> {
+    if segment == "today" {
+        return Some(date::today());
+    }
+    NaiveDate::parse_from_str(segment, "%Y-%m-%d").ok()
+}
+
+/// `GET /plans`: every day, newest first, shaped like `plan ls --json`.
+fn list_plans(cfg: &Config) -> io::Result<Response> {
+    let scan = file::scan_plan_dir(&cfg.layout, &cfg.dir, &[])?;
+    let mut days: BTreeMap<NaiveDate, stats::DayCounts> = BTreeMap::new();
+    for entry in &scan.plan_entries {
+        if let Some((day, _)) = file::plan_part(&cfg.layout, &entry.file_name().to_string_lossy()) {
+            days.entry(day)
+                .or_default()
+                .add(&fs::read_to_string(entry.path())?);
+        }
+    }
+    let days: Vec<serde_json::Value> = days
+        .iter()
+        .rev()
+        .map(|(day, counts)| {
+            json!({
+                "date": day.format("%Y-%m-%d").to_string(),
+                "weekday": day.format("%a").to_string(),
+                "lines": counts.lines,
+                "open": counts.open,
+                "done": counts.done,
+                "files": counts.files,
+            })
+        })
+        .collect();
+    Ok(Response::json(
+        200,
+        json!({ "schema_version": schema::VERSION, "days": days }),
+    ))
+}
+
+/// `GET /plans/{date}`: the day's files, continuation files included.
+fn show_plan(cfg: &Config, day: NaiveDate) -> io::Result<Response> {
+    let paths = date::day_paths(&cfg.layout, &cfg.dir, day);
+    if paths.is_empty() {
+        return Ok(Response::text(404, "No plan file for that date\n"));
+    }
+    let mut body = String::new();
+    for path in paths {
+        let _lock = file::acquire_shared_lock(&path, &cfg.lock)?;
+        body.push_str(&fs::read_to_string(&path)?);
+    }
+    Ok(Response::text(200, body))
+}
+
+/// `POST /plans/{date}/inbox`: add each non-blank line of the body to the
+/// day's inbox as written, the way `plan jot` does. Today and later days are
+/// created on demand; earlier days must exist already.
+fn add_to_inbox(cfg: &Config, day: NaiveDate, body: &str) -> io::Result<Response> {
+    let lines: Vec<&str> = body
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+        .collect();
+    if lines.is_empty() {
+        return Ok(Response::text(400, "Nothing to add\n"));
+    }
+    let path = date::current_plan_path(&cfg.layout, &cfg.dir, day);
+    let lock = file::acquire_lock(&path, &cfg.lock)?;
+    let before = fs::read_to_string(&path).ok();
+    let template = template::load(&cfg.template)?;
+    match date::ensure_file_exists(
+        &cfg.layout,
+        template.as_deref(),
+        &path,
+        day,
+        day < date::today(),
+    ) {
+        Ok(()) => {}
+        Err(crate::Error::FileMissing(_)) => {
+            return Ok(Response::text(404, "No plan file for that date\n"));
+        }
+        Err(e) => return Err(e.into()),
+    }
+    backup::snapshot(&path, &cfg.backups)?;
+    for line in &lines {
+        file::insert_into_inbox(&cfg.layout, &path, line, &lock)?;
+    }
+    undo::record_write("serve", &path, before.as_deref());
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    Ok(Response::json(
+        201,
+        json!({ "file": name, "added": lines.len() }),
+    ))
+}
+
+/// Route one request. `url` may carry a query string, which is ignored.
+pub fn handle(cfg: &Config, method: &str, url: &str, body: &str) -> Response {
+    let path = url.split('?').next().unwrap_or_default();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let result = match (method, segments.as_slice()) {
+        ("GET", []) => Ok(Response {
+            status: 200,
+            content_type: "text/html; charset=utf-8",
+            body: CAPTURE_PAGE.to_string(),
+        }),
+        ("GET", ["plans"]) => list_plans(cfg),
+        (_, ["plans"]) => Ok(Response::text(405, "Method not allowed\n")),
+        (method, ["plans", day, rest @ ..]) => {
+            let Some(day) = parse_date(day) else {
+                return Response::text(400, "Dates are YYYY-MM-DD or 'today'\n");
+            };
+            match (method, rest) {
+                ("GET", []) => show_plan(cfg, day),
+                ("POST", ["inbox"]) => add_to_inbox(cfg, day, body),
+                (_, [] | ["inbox"]) => Ok(Response::text(405, "Method not allowed\n")),
+                _ => Ok(Response::text(404, "Not found\n")),
+            }
+        }
+        _ => Ok(Response::text(404, "Not found\n")),
+    };
+    result.unwrap_or_else(|e| Response::text(500, format!("{}\n", e)))
+}
+
+/// Whether the request carries `Authorization: Bearer <token>`.
+fn authorized(request: &tiny_http::Request, token: &str) -> bool {
+    let expected = format!("Bearer {}", token);
+    header(request, "Authorization")
+        .is_some_and(|value| constant_time_eq(value.as_bytes(), expected.as_bytes()))
+}
+
+/// Compare without stopping at the first differing byte, so response times
+/// don't reveal how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn header<'a>(request: &'a tiny_http::Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Whether a `Host` header names the loopback interface, with or without a
+/// port.
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    name.eq_ignore_ascii_case("localhost")
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// The refusal for a request to a server without a token, if it gets one.
+/// Such a server trusts whoever can reach it, so it only answers requests
+/// addressed to a loopback name, which rules out DNS rebinding, and refuses
+/// writes carrying another site's `Origin`, since any web page can POST a
+/// form cross-origin.
+pub fn check_local(method: &str, host: Option<&str>, origin: Option<&str>) -> Option<Response> {
+    let Some(host) = host.filter(|h| is_loopback_host(h)) else {
+        return Some(Response::text(
+            403,
+            "Only localhost is served without serve.token\n",
+        ));
+    };
+    if method != "GET" && origin.is_some_and(|o| o != format!("http://{}", host)) {
+        return Some(Response::text(403, "Cross-origin writes are refused\n"));
+    }
+    None
+}
+
+/// Serve the plan directory on `addr` until the process is stopped. When `token` is set,
+/// every request except the capture page needs it as a bearer token;
+/// otherwise requests must pass [`check_local`].
+pub fn run(cfg: &Config, addr: &str, token: Option<&str>) -> io::Result<()> {
+    let server = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+    if let Some(local) = server.server_addr().to_ip() {
+        eprintln!("plan: serving {} on http://{}", cfg.dir.display(), local);
+    }
+    for mut request in server.incoming_requests() {
+        let is_page = request.url() == "/";
+        let refusal = match token {
+            Some(t) if !is_page && !authorized(&request, t) => {
+                Some(Response::text(401, "Missing or wrong bearer token\n"))
+            }
+            Some(_) => None,
+            None => check_local(
+                request.method().as_str(),
+                header(&request, "Host"),
+                header(&request, "Origin"),
+            ),
+        };
+        let response = if let Some(refusal) = refusal {
+            refusal
+        } else {
+            let mut body = String::new();
+            match request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+                Ok(_) => handle(cfg, request.method().as_str(), request.url(), &body),
+                Err(_) => Response::text(400, "Request body must be UTF-8 text\n"),
+            }
+        };
+        let header = tiny_http::Header::from_bytes("Content-Type", response.content_type)
+            .expect("static header is valid");
+        let reply = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(header);
+        // A client that hung up doesn't stop the server
+        let _ = request.respond(reply);
+    }
+    Ok(())
+}
+
+/// Quick-capture page served at `/`: posts the text box to today's inbox.
+const CAPTURE_PAGE: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>plan</title>
+<style>
+body { font-family: sans-serif; max-width: 32rem; margin: 2rem auto; padding: 0 1rem; }
+textarea, input, button { width: 100%; box-sizing: border-box; font-size: 1rem; margin-bottom: 0.5rem; }
+</style>
+</head>
+<body>
+<form id="capture">
+<textarea id="text" rows="4" placeholder="* task, @ 14:00 event, or a note" autofocus></textarea>
+<input id="token" type="password" placeholder="Token (if the server needs one)">
+<button>Add to today's inbox</button>
+</form>
+<p id="status"></p>
+<script>
+const token = document.getElementById("token");
+token.value = localStorage.getItem("plan-token") || "";
+document.getElementById("capture").addEventListener("submit", async (e) => {
+  e.preventDefault();
+  localStorage.setItem("plan-token", token.value);
+  const text = document.getElementById("text");
+  const headers = token.value ? { Authorization: "Bearer " + token.value } : {};
+  const res = await fetch("/plans/today/inbox", { method: "POST", headers, body: text.value });
+  document.getElementById("status").textContent = res.ok ? "Added." : await res.text();
+  if (res.ok) text.value = "";
+});
+</script>
+</body>
+</html>
+"#;
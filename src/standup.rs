@@ -0,0 +1,51 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+
+/// The tag that puts a task in the Blocked list.
+pub const BLOCKED_TAG: &str = "blocked";
+
+/// What goes into a standup update. Each list holds task text without the
+/// sigil, priority, or closing date.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Standup {
+    /// The working day the done tasks come from, if there is a plan file
+    /// for one
+    pub previous: Option<NaiveDate>,
+    pub done: Vec<String>,
+    pub today: Vec<String>,
+    pub blocked: Vec<String>,
+}
+
+/// The most recent weekday before `today` among `days`, so a Monday
+/// standup reports on Friday, or on whichever day was last worked.
+pub fn previous_working_day(days: &[NaiveDate], today: NaiveDate) -> Option<NaiveDate> {
+    days.iter()
+        .copied()
+        .filter(|d| *d < today && !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .max()
+}
+
+fn push_list(out: &mut String, items: &[String]) {
+    if items.is_empty() {
+        out.push_str("- nothing\n");
+    }
+    for item in items {
+        out.push_str(&format!("- {}\n", item));
+    }
+}
+
+/// The update as Markdown, ready to paste into chat. The Blocked list is
+/// left out when nothing is blocked.
+pub fn render(standup: &Standup) -> String {
+    let mut out = match standup.previous {
+        Some(day) => format!("**Done** ({})\n", day.format("%A, %b %-d")),
+        None => "**Done**\n".to_string(),
+    };
+    push_list(&mut out, &standup.done);
+    out.push_str("\n**Today**\n");
+    push_list(&mut out, &standup.today);
+    if !standup.blocked.is_empty() {
+        out.push_str("\n**Blocked**\n");
+        push_list(&mut out, &standup.blocked);
+    }
+    out
+}
@@ -0,0 +1,308 @@
+use crate::color::{Palette, Role};
+use crate::layout::Layout;
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+
+/// Line and task counts of a single day's plan file.
+pub struct DayActivity {
+    pub date: NaiveDate,
+    pub lines: usize,
+    pub tasks: usize,
+}
+
+/// Line and task counts for one day, summed over its continuation files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DayCounts {
+    pub files: usize,
+    pub lines: usize,
+    /// Inbox and open tasks
+    pub open: usize,
+    pub done: usize,
+}
+
+impl DayCounts {
+    /// Count one more file belonging to the day.
+    pub fn add(&mut self, content: &str) {
+        self.files += 1;
+        self.lines += content.lines().count();
+        for task in crate::task::parse_tasks(content) {
+            if task.state.is_pending() {
+                self.open += 1;
+            } else if task.state == crate::task::TaskState::Done {
+                self.done += 1;
+            }
+        }
+    }
+
+    /// Fold another day's counts into these, for subtotals.
+    pub fn merge(&mut self, other: &DayCounts) {
+        self.files += other.files;
+        self.lines += other.lines;
+        self.open += other.open;
+        self.done += other.done;
+    }
+}
+
+/// Reading speed used to estimate reading time.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Size and task figures for one day, shown by `plan show --stats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DocStats {
+    /// Words outside code zones, section markers, and separators
+    pub words: usize,
+    /// Inbox and open tasks
+    pub open: usize,
+    pub done: usize,
+    /// Non-blank lines in the inbox
+    pub captures: usize,
+}
+
+impl DocStats {
+    /// Count one more file belonging to the day.
+    pub fn add(&mut self, layout: &Layout, content: &str) {
+        self.words += crate::section::prose_lines(content)
+            .filter(|(_, line)| {
+                crate::section::open_marker_name(line).is_none()
+                    && !crate::section::is_close_marker(line)
+            })
+            .map(|(_, line)| line.split_whitespace().count())
+            .sum::<usize>();
+        for task in crate::task::parse_tasks(content) {
+            if task.state.is_pending() {
+                self.open += 1;
+            } else if task.state == crate::task::TaskState::Done {
+                self.done += 1;
+            }
+        }
+        if let Some(inbox) = crate::section::find_section(layout, content, "inbox") {
+            self.captures += content
+                .lines()
+                .skip(inbox.body().start)
+                .take(inbox.body().len())
+                .filter(|l| !l.trim().is_empty())
+                .count();
+        }
+    }
+
+    /// Estimated reading time in whole minutes, rounded up; 0 for an empty day.
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE)
+    }
+}
+
+/// Aggregate figures over a set of plan files.
+pub struct Summary {
+    pub days: usize,
+    pub lines: usize,
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    pub longest_streak: usize,
+}
+
+/// Read every dated plan file and record its line and task counts, oldest
+/// first.
+/// Files without a `YYYY-MM-DD` name or that can't be read are skipped.
+pub fn collect_activity(layout: &Layout, entries: &[fs::DirEntry]) -> Vec<DayActivity> {
+    let mut activity: Vec<DayActivity> = entries
+        .iter()
+        .filter_map(|e| {
+            let date = crate::file::plan_date(layout, &e.file_name().to_string_lossy())?;
+            let content = fs::read_to_string(e.path()).ok()?;
+            Some(DayActivity {
+                date,
+                lines: content.lines().count(),
+                tasks: crate::task::parse_tasks(&content).len(),
+            })
+        })
+        .collect();
+    activity.sort_by_key(|a| a.date);
+    activity
+}
+
+/// Summarize activity sorted oldest first. Returns `None` when there is none.
+pub fn summarize(activity: &[DayActivity]) -> Option<Summary> {
+    let first = activity.first()?.date;
+    let last = activity.last()?.date;
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut prev: Option<NaiveDate> = None;
+    for day in activity {
+        run = match prev {
+            Some(p) if day.date - p == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        prev = Some(day.date);
+    }
+
+    Some(Summary {
+        days: activity.len(),
+        lines: activity.iter().map(|a| a.lines).sum(),
+        first,
+        last,
+        longest_streak: longest,
+    })
+}
+
+/// First day of the month `months - 1` months before `end`, so `months = 1`
+/// covers just the current month.
+pub fn heatmap_start(end: NaiveDate, months: u32) -> NaiveDate {
+    let first_of_month = end.with_day(1).unwrap_or(end);
+    first_of_month
+        .checked_sub_months(Months::new(months.saturating_sub(1)))
+        .unwrap_or(first_of_month)
+}
+
+const CELL: i64 = 11;
+const STEP: i64 = 13;
+const LEFT: i64 = 30;
+const TOP: i64 = 20;
+const PALETTE: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+/// Intensity bucket 0-4 for a day with `lines` lines, relative to the busiest day.
+pub fn intensity(lines: usize, max: usize) -> usize {
+    if lines == 0 || max == 0 {
+        0
+    } else {
+        (lines * 4).div_ceil(max).clamp(1, 4)
+    }
+}
+
+/// Render a standalone calendar heatmap SVG covering `start..=end`.
+///
+/// Columns are Monday-first weeks, rows are weekdays, and each cell carries a
+/// `<title>` tooltip with the date and line count.
+pub fn render_heatmap_svg(activity: &[DayActivity], start: NaiveDate, end: NaiveDate) -> String {
+    let by_date: HashMap<NaiveDate, usize> = activity
+        .iter()
+        .filter(|a| a.date >= start && a.date <= end)
+        .map(|a| (a.date, a.lines))
+        .collect();
+    let max = by_date.values().copied().max().unwrap_or(0);
+
+    let grid_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let weeks = (end - grid_start).num_days() / 7 + 1;
+    let width = LEFT + weeks * STEP;
+    let height = TOP + 7 * STEP;
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="9">"#
+    );
+    let _ = writeln!(
+        svg,
+        r##"<rect width="{width}" height="{height}" fill="#ffffff"/>"##
+    );
+
+    for (row, label) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+        let _ = writeln!(
+            svg,
+            r##"<text x="0" y="{}" fill="#767676">{}</text>"##,
+            TOP + row * STEP + CELL - 2,
+            label
+        );
+    }
+
+    let mut day = grid_start;
+    let mut last_month = None;
+    while day <= end {
+        let col = (day - grid_start).num_days() / 7;
+        let row = day.weekday().num_days_from_monday() as i64;
+        let x = LEFT + col * STEP;
+        let y = TOP + row * STEP;
+
+        if day >= start && last_month != Some(day.month()) {
+            let _ = writeln!(
+                svg,
+                r##"<text x="{}" y="{}" fill="#767676">{}</text>"##,
+                x,
+                TOP - 6,
+                day.format("%b")
+            );
+            last_month = Some(day.month());
+        }
+
+        if day >= start {
+            let lines = by_date.get(&day).copied().unwrap_or(0);
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" rx="2" fill="{}"><title>{}: {} lines</title></rect>"#,
+                PALETTE[intensity(lines, max)],
+                day.format("%Y-%m-%d"),
+                lines
+            );
+        }
+        day += Duration::days(1);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Shades of a `plan cal` cell, from an empty day to the busiest.
+const BLOCKS: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+
+/// Render a calendar heatmap covering `start..=end` as Unicode blocks, laid
+/// out like the SVG one. Cells measure tasks when `by_tasks` is set, lines
+/// otherwise.
+pub fn render_heatmap_text(
+    activity: &[DayActivity],
+    start: NaiveDate,
+    end: NaiveDate,
+    by_tasks: bool,
+    palette: &Palette,
+) -> String {
+    let mut by_date: HashMap<NaiveDate, usize> = HashMap::new();
+    for a in activity.iter().filter(|a| a.date >= start && a.date <= end) {
+        *by_date.entry(a.date).or_default() += if by_tasks { a.tasks } else { a.lines };
+    }
+    let max = by_date.values().copied().max().unwrap_or(0);
+    let shade = |level: usize| match level {
+        0 => BLOCKS[0].to_string(),
+        _ => palette.paint(Role::Done, BLOCKS[level]),
+    };
+
+    let grid_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let weeks = (end - grid_start).num_days() / 7 + 1;
+
+    // Each month is named above the week holding its first shown day
+    let mut months = String::new();
+    for col in 0..weeks {
+        let week = grid_start + Duration::days(col * 7);
+        let first = (0..7)
+            .map(|d| week + Duration::days(d))
+            .find(|d| *d >= start && *d <= end && (d.day() == 1 || *d == start));
+        let at = col as usize * 2;
+        if let Some(day) = first
+            && months.chars().count() <= at
+        {
+            let pad = at - months.chars().count();
+            let _ = write!(months, "{}{}", " ".repeat(pad), day.format("%b"));
+        }
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "    {}", palette.paint(Role::Header, &months));
+    for (row, label) in ["Mon", "", "Wed", "", "Fri", "", ""].iter().enumerate() {
+        let cells: Vec<String> = (0..weeks)
+            .map(|col| {
+                let day = grid_start + Duration::days(col * 7 + row as i64);
+                if day < start || day > end {
+                    " ".to_string()
+                } else {
+                    shade(intensity(by_date.get(&day).copied().unwrap_or(0), max))
+                }
+            })
+            .collect();
+        let line = format!("{:<4}{}", label, cells.join(" "));
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    let legend: Vec<String> = (0..BLOCKS.len()).map(shade).collect();
+    let _ = writeln!(out, "\n    less {} more", legend.join(" "));
+    out
+}
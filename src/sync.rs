@@ -0,0 +1,557 @@
+use crate::config::{Config, SyncBackend};
+use crate::layout::Layout;
+use crate::{date, file, index};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// What a sync did, or would do with `--dry-run`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Files taken from the remote
+    pub pulled: Vec<String>,
+    /// Files sent to the remote
+    pub pushed: Vec<String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Report {
+    /// Conflicted files are listed once, as conflicts.
+    fn drop_conflicted(&mut self) {
+        let conflicted = |f: &String| self.conflicts.iter().any(|c| &c.file == f);
+        self.pulled.retain(|f| !conflicted(f));
+        self.pushed.retain(|f| !conflicted(f));
+    }
+}
+
+/// A file changed on both sides since the last sync. The local copy wins
+/// and is pushed; the remote one is saved next to it as `copy`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub file: String,
+    pub copy: String,
+}
+
+/// What to do with one file, decided by [`reconcile`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    Pull(String),
+    Push(String),
+    Conflict(String),
+}
+
+/// Compare local and remote contents against the content hashes recorded at
+/// the last sync. A file changed on one side only goes to the other; a file
+/// changed on both is a conflict. Deletions aren't synced: a file missing on
+/// one side is copied from the other.
+pub fn reconcile(
+    local: &BTreeMap<String, String>,
+    remote: &BTreeMap<String, String>,
+    base: &BTreeMap<String, u64>,
+) -> Vec<Action> {
+    let names: BTreeSet<&String> = local.keys().chain(remote.keys()).collect();
+    let mut actions = Vec::new();
+    for name in names {
+        let action = match (local.get(name), remote.get(name)) {
+            (Some(_), None) => Action::Push(name.clone()),
+            (None, Some(_)) => Action::Pull(name.clone()),
+            (Some(l), Some(r)) if l == r => continue,
+            (Some(l), Some(r)) => match base.get(name) {
+                Some(&b) if b == content_hash(l) => Action::Pull(name.clone()),
+                Some(&b) if b == content_hash(r) => Action::Push(name.clone()),
+                _ => Action::Conflict(name.clone()),
+            },
+            (None, None) => continue,
+        };
+        actions.push(action);
+    }
+    actions
+}
+
+/// The hash [`reconcile`] compares against the last sync.
+pub fn content_hash(content: &str) -> u64 {
+    index::fnv1a(content.as_bytes())
+}
+
+/// Name for the remote side of a conflict on `name`, in the Syncthing style
+/// the rest of plan already skips: `2026-02-19.sync-conflict-20260219-101500-remote.plan`.
+pub fn conflict_name(name: &str) -> String {
+    let stamp = date::now().format("%Y%m%d-%H%M%S");
+    match name.rsplit_once('.') {
+        Some((stem, ext)) => format!("{}.sync-conflict-{}-remote.{}", stem, stamp, ext),
+        None => format!("{}.sync-conflict-{}-remote", name, stamp),
+    }
+}
+
+/// Sync the plan directory with the configured remote: pull first, then
+/// push.
+pub fn sync(cfg: &Config, data_dir: &Path, dry_run: bool) -> crate::Result<Report> {
+    let Some(backend) = cfg.sync.backend else {
+        return Err(crate::Error::InvalidConfig(
+            "No sync backend configured; set sync.backend to git, rsync, or webdav".to_string(),
+        ));
+    };
+    let remote = cfg.sync.remote.as_deref();
+    // It is passed to git, rsync, and curl as an argument, where a leading
+    // dash would be read as an option
+    if remote.is_some_and(|r| r.starts_with('-')) {
+        return Err(crate::Error::InvalidConfig(
+            "sync.remote must not start with '-'".to_string(),
+        ));
+    }
+    match (backend, remote) {
+        (SyncBackend::Git, remote) => git_sync(cfg, remote.unwrap_or("origin"), dry_run),
+        (SyncBackend::Rsync, Some(remote)) => file_sync(cfg, data_dir, &Rsync(remote), dry_run),
+        (SyncBackend::WebDav, Some(url)) => {
+            file_sync(cfg, data_dir, &WebDav(url.trim_end_matches('/')), dry_run)
+        }
+        (_, None) => Err(crate::Error::InvalidConfig(
+            "sync.remote must be set for the rsync and webdav backends".to_string(),
+        )),
+    }
+}
+
+/// Run `program` and fail with its stderr unless it exits zero.
+fn run(command: &mut Command) -> crate::Result<Output> {
+    let output = command.output().map_err(|e| {
+        let program = command.get_program().to_string_lossy().into_owned();
+        crate::Error::CommandFailed(format!("Could not run {}: {}", program, e))
+    })?;
+    if !output.status.success() {
+        return Err(crate::Error::CommandFailed(format!(
+            "{} failed: {}",
+            command.get_program().to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output)
+}
+
+fn stdout_lines(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// ---- git ----
+
+fn git(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    command
+}
+
+/// Names of changed files between two revisions, as `git diff --name-only`.
+fn git_changed(dir: &Path, range: &str) -> crate::Result<Vec<String>> {
+    Ok(stdout_lines(&run(git(dir).args([
+        "diff",
+        "--name-only",
+        range,
+        "--",
+    ]))?))
+}
+
+/// Pathspecs for what a sync commits: everything but lock files, temp
+/// files, and conflict copies.
+const COMMITTED: &[&str] = &[
+    "--",
+    ".",
+    ":(exclude)*.lock",
+    ":(exclude)*.tmp-*",
+    ":(exclude)*.sync-conflict-*",
+];
+
+fn git_has(dir: &Path, rev: &str) -> crate::Result<bool> {
+    Ok(git(dir)
+        .args(["rev-parse", "--verify", "--quiet", rev])
+        .output()?
+        .status
+        .success())
+}
+
+/// Commit local changes, merge the remote branch, and push. Files both sides
+/// changed keep the local version; the remote one is saved as a conflict
+/// copy and the merge is committed.
+fn git_sync(cfg: &Config, remote: &str, dry_run: bool) -> crate::Result<Report> {
+    let dir = &cfg.dir;
+    // Held until the push, so no plan command writes a file while git
+    // commits, merges, or checks it out
+    let _locks = lock_plan_files(cfg)?;
+    // symbolic-ref works before the first commit, unlike rev-parse
+    let branch = stdout_lines(&run(git(dir).args(["symbolic-ref", "--short", "HEAD"]))?)
+        .pop()
+        .unwrap_or_default();
+    run(git(dir).args(["fetch", "--quiet", remote]))?;
+    let tracking = format!("{}/{}", remote, branch);
+
+    let local_changes: Vec<String> = stdout_lines(&run(git(dir)
+        .args(["status", "--porcelain"])
+        .args(COMMITTED))?)
+    .iter()
+    .filter_map(|l| l.get(3..))
+    .map(|path| path.rsplit(" -> ").next().unwrap_or(path))
+    .map(|path| path.trim_matches('"').to_string())
+    .collect();
+    if !dry_run && !local_changes.is_empty() {
+        run(git(dir).args(["add", "-A"]).args(COMMITTED))?;
+        run(git(dir).args(["commit", "--quiet", "-m", "plan sync"]))?;
+    }
+
+    let mut report = Report::default();
+    let has_head = git_has(dir, "HEAD")?;
+    let has_remote_branch = git_has(dir, &tracking)?;
+    match (has_head, has_remote_branch) {
+        (true, true) => {
+            report.pulled = git_changed(dir, &format!("HEAD...{}", tracking))?;
+            report.pushed = git_changed(dir, &format!("{}...HEAD", tracking))?;
+        }
+        (true, false) => report.pushed = stdout_lines(&run(git(dir).args(["ls-files"]))?),
+        (false, true) => {
+            report.pulled = stdout_lines(&run(git(dir).args([
+                "ls-tree",
+                "-r",
+                "--name-only",
+                &tracking,
+            ]))?);
+        }
+        (false, false) => {}
+    }
+    if dry_run {
+        report.pushed.extend(local_changes);
+        report.pushed.sort();
+        report.pushed.dedup();
+    }
+
+    if dry_run {
+        // Both sides touched these; only the merge can tell whether the
+        // changes overlap, so report them as likely conflicts
+        let both: Vec<String> = report
+            .pulled
+            .iter()
+            .filter(|f| report.pushed.contains(f))
+            .cloned()
+            .collect();
+        for file in both {
+            report.conflicts.push(Conflict {
+                copy: conflict_name(&file),
+                file,
+            });
+        }
+        report.drop_conflicted();
+        return Ok(report);
+    }
+
+    if has_remote_branch {
+        let merge = git(dir)
+            .args(["merge", "--quiet", "--no-edit", &tracking])
+            .output()?;
+        if !merge.status.success() {
+            let conflicted = stdout_lines(&run(git(dir).args([
+                "diff",
+                "--name-only",
+                "--diff-filter=U",
+            ]))?);
+            if conflicted.is_empty() {
+                return Err(crate::Error::CommandFailed(format!(
+                    "git merge failed: {}",
+                    String::from_utf8_lossy(&merge.stderr).trim()
+                )));
+            }
+            for name in conflicted {
+                let theirs = run(git(dir).args(["show", &format!(":3:{}", name)]))?;
+                let copy = conflict_name(&name);
+                fs::write(dir.join(&copy), &theirs.stdout)?;
+                run(git(dir).args(["checkout", "--ours", "--", &name]))?;
+                run(git(dir).args(["add", "--", &name]))?;
+                report.conflicts.push(Conflict { file: name, copy });
+            }
+            run(git(dir).args(["commit", "--quiet", "--no-edit"]))?;
+        }
+    }
+    if !report.pushed.is_empty() || !report.conflicts.is_empty() {
+        run(git(dir).args(["push", "--quiet", remote, &format!("HEAD:{}", branch)]))?;
+    }
+    report.drop_conflicted();
+    Ok(report)
+}
+
+/// Exclusive locks on every plan file directly in the plan directory.
+fn lock_plan_files(cfg: &Config) -> crate::Result<Vec<file::LockGuard>> {
+    let mut names: Vec<String> = fs::read_dir(&cfg.dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|name| file::is_plan_file(&cfg.layout, name))
+        .collect();
+    // One order for every caller, so two syncs can't deadlock
+    names.sort();
+    let mut locks = Vec::new();
+    for name in names {
+        locks.push(file::acquire_lock(&cfg.dir.join(name), &cfg.lock)?);
+    }
+    Ok(locks)
+}
+
+// ---- file-based backends ----
+
+/// A remote holding plain copies of the plan files.
+trait Remote {
+    /// Replace the plan files in `mirror` with the remote's current ones.
+    fn fetch(&self, layout: &Layout, mirror: &Path) -> crate::Result<()>;
+    /// Upload `names` from `dir`, overwriting the remote copies.
+    fn upload(&self, dir: &Path, names: &[String]) -> crate::Result<()>;
+}
+
+/// `rsync` to a local path or `host:path`.
+struct Rsync<'a>(&'a str);
+
+impl Rsync<'_> {
+    fn target(&self) -> String {
+        format!("{}/", self.0.trim_end_matches('/'))
+    }
+}
+
+impl Remote for Rsync<'_> {
+    fn fetch(&self, layout: &Layout, mirror: &Path) -> crate::Result<()> {
+        let pattern = format!("*.{}", layout.extension());
+        run(Command::new("rsync")
+            .args(["-a", "--delete", "--exclude=*.sync-conflict-*"])
+            .arg(format!("--include={}", pattern))
+            .args(["--exclude=*", &self.target()])
+            .arg(mirror))
+        .map(drop)
+    }
+
+    fn upload(&self, dir: &Path, names: &[String]) -> crate::Result<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        run(Command::new("rsync")
+            .arg("-a")
+            .args(names.iter().map(|n| dir.join(n)))
+            .arg(self.target()))
+        .map(drop)
+    }
+}
+
+/// A WebDAV collection, reached with `curl`. Credentials come from
+/// `~/.netrc`.
+struct WebDav<'a>(&'a str);
+
+fn curl() -> Command {
+    let mut command = Command::new("curl");
+    command.args(["--silent", "--show-error", "--fail", "--netrc-optional"]);
+    command
+}
+
+/// File names from the `href`s of a PROPFIND response.
+fn propfind_names(xml: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find("href>") {
+        rest = &rest[start + "href>".len()..];
+        let Some(end) = rest.find('<') else { break };
+        let href = rest[..end].trim().trim_end_matches('/');
+        if let Some(name) = href.rsplit('/').next().map(percent_decode) {
+            names.push(name);
+        }
+        rest = &rest[end..];
+    }
+    names
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+impl Remote for WebDav<'_> {
+    fn fetch(&self, layout: &Layout, mirror: &Path) -> crate::Result<()> {
+        let listing = run(curl()
+            .args(["--request", "PROPFIND", "--header", "Depth: 1"])
+            .arg(format!("{}/", self.0)))?;
+        let names: Vec<String> = propfind_names(&String::from_utf8_lossy(&listing.stdout))
+            .into_iter()
+            .filter(|n| file::is_plan_file(layout, n))
+            .collect();
+        clear_plan_files(layout, mirror)?;
+        if names.is_empty() {
+            return Ok(());
+        }
+        let mut command = curl();
+        for name in &names {
+            command
+                .arg("--output")
+                .arg(mirror.join(name))
+                .arg(format!("{}/{}", self.0, name));
+        }
+        run(&mut command).map(drop)
+    }
+
+    fn upload(&self, dir: &Path, names: &[String]) -> crate::Result<()> {
+        for name in names {
+            run(curl()
+                .arg("--upload-file")
+                .arg(dir.join(name))
+                .arg(format!("{}/{}", self.0, name)))?;
+        }
+        Ok(())
+    }
+}
+
+fn clear_plan_files(layout: &Layout, dir: &Path) -> crate::Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        if file::is_plan_file(layout, &entry.file_name().to_string_lossy()) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Plan files directly in `dir`, by name.
+fn read_plan_files(cfg: &Config, dir: &Path) -> crate::Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if file::is_plan_file(&cfg.layout, &name) && entry.path().is_file() {
+            let _lock = file::acquire_shared_lock(&entry.path(), &cfg.lock)?;
+            files.insert(name, fs::read_to_string(entry.path())?);
+        }
+    }
+    Ok(files)
+}
+
+/// Where the remote copy and the last-sync hashes for `dir` are kept.
+fn state_paths(data_dir: &Path, dir: &Path) -> (PathBuf, PathBuf) {
+    let key = format!("{:016x}", index::fnv1a(dir.to_string_lossy().as_bytes()));
+    let root = data_dir.join("sync");
+    (root.join(&key), root.join(format!("{}.json", key)))
+}
+
+fn load_base(path: &Path) -> crate::Result<BTreeMap<String, u64>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let value: Value = serde_json::from_str(&content).unwrap_or_default();
+    Ok(value
+        .get("files")
+        .and_then(Value::as_object)
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|(name, h)| {
+                    let h = u64::from_str_radix(h.as_str()?, 16).ok()?;
+                    Some((name.clone(), h))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn save_base(path: &Path, base: &BTreeMap<String, u64>) -> crate::Result<()> {
+    let files: serde_json::Map<String, Value> = base
+        .iter()
+        .map(|(name, h)| (name.clone(), json!(format!("{:016x}", h))))
+        .collect();
+    file::write_atomic(path, &json!({ "files": files }).to_string())
+}
+
+/// Fetch the remote into a mirror, reconcile it with `dir`, apply the
+/// result locally, then upload what changed here.
+fn file_sync(
+    cfg: &Config,
+    data_dir: &Path,
+    remote: &dyn Remote,
+    dry_run: bool,
+) -> crate::Result<Report> {
+    let dir = &cfg.dir;
+    let (mirror, base_path) = state_paths(data_dir, dir);
+    fs::create_dir_all(&mirror)?;
+    let _lock = file::acquire_lock(&base_path, &cfg.lock)?;
+    remote.fetch(&cfg.layout, &mirror)?;
+
+    let local = read_plan_files(cfg, dir)?;
+    let theirs = read_plan_files(cfg, &mirror)?;
+    let mut base = load_base(&base_path)?;
+    let mut report = Report::default();
+    let mut upload = Vec::new();
+
+    for action in reconcile(&local, &theirs, &base) {
+        match action {
+            Action::Pull(name) => {
+                let content = &theirs[&name];
+                if !dry_run {
+                    let path = dir.join(&name);
+                    let _lock = file::acquire_lock(&path, &cfg.lock)?;
+                    // Compared and written under one lock, so an edit made
+                    // since the files were read becomes a conflict instead
+                    // of being overwritten
+                    if let Ok(current) = fs::read_to_string(&path)
+                        && Some(&current) != local.get(&name)
+                    {
+                        let copy = conflict_name(&name);
+                        fs::write(dir.join(&copy), content)?;
+                        base.insert(name.clone(), content_hash(&current));
+                        upload.push(name.clone());
+                        report.conflicts.push(Conflict { file: name, copy });
+                        continue;
+                    }
+                    crate::backup::snapshot(&path, &cfg.backups)?;
+                    file::write_atomic(&path, content)?;
+                }
+                base.insert(name.clone(), content_hash(content));
+                report.pulled.push(name);
+            }
+            Action::Push(name) => {
+                base.insert(name.clone(), content_hash(&local[&name]));
+                upload.push(name.clone());
+                report.pushed.push(name);
+            }
+            Action::Conflict(name) => {
+                let copy = conflict_name(&name);
+                if !dry_run {
+                    fs::write(dir.join(&copy), &theirs[&name])?;
+                }
+                // The remote side is saved here, so the local one wins
+                base.insert(name.clone(), content_hash(&local[&name]));
+                upload.push(name.clone());
+                report.conflicts.push(Conflict { file: name, copy });
+            }
+        }
+    }
+    for (name, content) in &local {
+        if theirs.get(name) == Some(content) {
+            base.insert(name.clone(), content_hash(content));
+        }
+    }
+    if dry_run {
+        return Ok(report);
+    }
+    remote.upload(dir, &upload)?;
+    save_base(&base_path, &base)?;
+    Ok(report)
+}
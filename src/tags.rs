@@ -0,0 +1,40 @@
+use std::collections::BTreeMap;
+
+/// `#tag` tokens in `line`, lowercased, in order of appearance.
+///
+/// A tag starts at the beginning of the line or after whitespace, begins with
+/// a letter, and continues through letters, digits, `-` and `_`. This leaves
+/// markdown headings (`# Title`) and issue references (`#123`) alone.
+pub fn extract_tags(line: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut prev_is_space = true;
+    for (i, c) in line.char_indices() {
+        if c == '#' && prev_is_space {
+            let rest = &line[i + 1..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                .unwrap_or(rest.len());
+            let tag = rest[..end].trim_end_matches(['-', '_']);
+            if tag.starts_with(char::is_alphabetic) {
+                tags.push(tag.to_lowercase());
+            }
+        }
+        prev_is_space = c.is_whitespace();
+    }
+    tags
+}
+
+/// Whether `line` carries `tag` (case-insensitive, with or without the `#`).
+pub fn has_tag(line: &str, tag: &str) -> bool {
+    let tag = tag.trim_start_matches('#').to_lowercase();
+    extract_tags(line).contains(&tag)
+}
+
+/// Count tag occurrences across `content`, skipping code zones.
+pub fn count_tags(content: &str, counts: &mut BTreeMap<String, usize>) {
+    for (_, line) in crate::section::prose_lines(content) {
+        for tag in extract_tags(line) {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+}
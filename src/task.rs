@@ -0,0 +1,262 @@
+use crate::layout::Layout;
+use chrono::{NaiveDate, NaiveTime};
+use std::fmt;
+
+/// What a line in a plan file records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// Free text, including the header and section markers
+    Note,
+    /// A line starting with a task sigil (`* `, `\ `, `+ `, `- `)
+    Task,
+    /// `@ [HH:MM] text`, something happening at a point in the day
+    Event,
+}
+
+/// Classify a single line.
+pub fn entry_kind(line: &str) -> EntryKind {
+    if parse_task(line, 0).is_some() {
+        EntryKind::Task
+    } else if parse_event(line, 0).is_some() {
+        EntryKind::Event
+    } else {
+        EntryKind::Note
+    }
+}
+
+/// State of a task line, from its leading sigil.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    /// `* ` an inbox item not yet triaged
+    Inbox,
+    /// `\ ` in progress
+    Open,
+    /// `+ ` done
+    Done,
+    /// `- ` cancelled
+    Cancelled,
+}
+
+impl TaskState {
+    fn from_sigil(c: char) -> Option<Self> {
+        match c {
+            '*' => Some(Self::Inbox),
+            '\\' => Some(Self::Open),
+            '+' => Some(Self::Done),
+            '-' => Some(Self::Cancelled),
+            _ => None,
+        }
+    }
+
+    /// Whether the task still needs doing.
+    pub fn is_pending(self) -> bool {
+        matches!(self, Self::Inbox | Self::Open)
+    }
+
+    /// Lowercase name used in exported data.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Inbox => "inbox",
+            Self::Open => "open",
+            Self::Done => "done",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Task priority. `A` is the most urgent; `!high`, `!medium` and `!low` are
+/// aliases for `A`, `B` and `C`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Priority(char);
+
+impl Priority {
+    /// Parse a priority as given on the command line: a letter or a level name.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_ascii_alphabetic()
+        {
+            return Some(Self(c.to_ascii_uppercase()));
+        }
+        match s.to_ascii_lowercase().as_str() {
+            "high" => Some(Self('A')),
+            "medium" | "med" => Some(Self('B')),
+            "low" => Some(Self('C')),
+            _ => None,
+        }
+    }
+
+    /// The priority letter, `A` being the most urgent.
+    pub fn letter(self) -> char {
+        self.0
+    }
+
+    /// Parse a leading `(A)` or `!high` token, returning the rest of the text.
+    fn strip_prefix(text: &str) -> Option<(Self, &str)> {
+        let (token, rest) = text.split_once(' ').unwrap_or((text, ""));
+        let priority = if let Some(level) = token.strip_prefix('!') {
+            if level.len() == 1 {
+                return None;
+            }
+            Self::parse(level)?
+        } else {
+            let letter = token.strip_prefix('(')?.strip_suffix(')')?;
+            let c = letter.chars().next().filter(|c| c.is_ascii_uppercase())?;
+            if letter.len() != 1 {
+                return None;
+            }
+            Self(c)
+        };
+        Some((priority, rest.trim_start()))
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({})", self.0)
+    }
+}
+
+/// A task line in a plan file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    pub state: TaskState,
+    pub priority: Option<Priority>,
+    /// Task text without the sigil or priority token
+    pub text: String,
+    /// 1-based line number
+    pub line_number: usize,
+}
+
+/// Parse a single line as a task. Sigils must start the line.
+pub fn parse_task(line: &str, line_number: usize) -> Option<Task> {
+    let mut chars = line.chars();
+    let state = TaskState::from_sigil(chars.next()?)?;
+    let body = chars.as_str().strip_prefix(' ')?;
+    let (priority, text) = match Priority::strip_prefix(body) {
+        Some((p, rest)) => (Some(p), rest),
+        None => (None, body),
+    };
+    Some(Task {
+        state,
+        priority,
+        text: text.to_string(),
+        line_number,
+    })
+}
+
+/// Rewrite a pending task line as done or cancelled on `date`, the same way the
+/// editor plugin does: `+ text (YYYY-MM-DD)`.
+pub fn close_task(line: &str, state: TaskState, date: NaiveDate) -> Option<String> {
+    let sigil = match state {
+        TaskState::Done => '+',
+        TaskState::Cancelled => '-',
+        TaskState::Inbox | TaskState::Open => return None,
+    };
+    if !parse_task(line, 0)?.state.is_pending() {
+        return None;
+    }
+    Some(format!(
+        "{}{} ({})",
+        sigil,
+        &line[1..],
+        date.format("%Y-%m-%d")
+    ))
+}
+
+/// Undo [`close_task`]: turn a done or cancelled task back into an inbox
+/// item, dropping the `(YYYY-MM-DD)` it was closed with.
+pub fn reopen_task(line: &str) -> Option<String> {
+    if parse_task(line, 0)?.state.is_pending() {
+        return None;
+    }
+    Some(format!("*{}", without_close_date(&line[1..])))
+}
+
+/// `text` without the trailing `(YYYY-MM-DD)` that [`close_task`] adds.
+pub fn without_close_date(text: &str) -> &str {
+    match text.trim_end().rsplit_once(' ') {
+        Some((rest, stamp))
+            if stamp
+                .strip_prefix('(')
+                .and_then(|s| s.strip_suffix(')'))
+                .is_some_and(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").is_ok()) =>
+        {
+            rest
+        }
+        _ => text,
+    }
+}
+
+/// The date of a `due:YYYY-MM-DD` token in task text, if there is one. Like
+/// tags, the token starts the text or follows whitespace.
+pub fn due_date(text: &str) -> Option<NaiveDate> {
+    text.split_whitespace()
+        .filter_map(|word| word.strip_prefix("due:"))
+        .find_map(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+}
+
+/// Every task in `content`, skipping `--- :lang` code zones.
+pub fn parse_tasks(content: &str) -> Vec<Task> {
+    crate::section::prose_lines(content)
+        .filter_map(|(i, line)| parse_task(line, i + 1))
+        .collect()
+}
+
+/// An event line in a plan file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub time: Option<NaiveTime>,
+    /// Event text without the sigil or time
+    pub text: String,
+    /// 1-based line number
+    pub line_number: usize,
+}
+
+/// Parse a time given as `HH:MM`.
+pub fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// Render an event line as written to a plan file.
+pub fn format_event(time: Option<NaiveTime>, text: &str) -> String {
+    match time {
+        Some(t) => format!("@ {} {}", t.format("%H:%M"), text),
+        None => format!("@ {}", text),
+    }
+}
+
+/// Parse a single line as an event. The sigil must start the line.
+pub fn parse_event(line: &str, line_number: usize) -> Option<Event> {
+    let body = line.strip_prefix("@ ")?;
+    let (token, rest) = body.split_once(' ').unwrap_or((body, ""));
+    let (time, text) = match parse_time(token) {
+        Some(t) => (Some(t), rest.trim_start()),
+        None => (None, body),
+    };
+    Some(Event {
+        time,
+        text: text.to_string(),
+        line_number,
+    })
+}
+
+/// Lines scheduled in a plan file: everything in the inbox, plus events and
+/// pending tasks elsewhere. Returned with 1-based line numbers.
+pub fn scheduled_items<'a>(layout: &Layout, content: &'a str) -> Vec<(usize, &'a str)> {
+    let inbox = crate::section::find_section(layout, content, "inbox").map(|s| s.body());
+    crate::section::prose_lines(content)
+        .filter(|(i, line)| {
+            let in_inbox = inbox.as_ref().is_some_and(|r| r.contains(i));
+            if in_inbox {
+                !line.trim().is_empty()
+            } else if let Some(t) = parse_task(line, i + 1) {
+                t.state.is_pending()
+            } else {
+                parse_event(line, i + 1).is_some()
+            }
+        })
+        .map(|(i, line)| (i + 1, line))
+        .collect()
+}
@@ -0,0 +1,138 @@
+use crate::layout::Layout;
+use chrono::NaiveDate;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Placeholders a template may use, and what each becomes.
+pub const PLACEHOLDERS: &[(&str, &str)] = &[
+    (
+        "header",
+        "the day's title line, e.g. 2026, Feb 19 - Thursday",
+    ),
+    ("inbox", "an empty inbox section for the current layout"),
+    ("date", "the date as YYYY-MM-DD"),
+    ("weekday", "the weekday name, e.g. Thursday"),
+    ("yesterday", "the day before, as YYYY-MM-DD"),
+    ("tomorrow", "the day after, as YYYY-MM-DD"),
+];
+
+/// The user's template, if they have one. The CLI loads it with [`set`]
+/// at startup; new plan files use the built-in layout otherwise.
+/// Read the template at `path`; a missing file means no template.
+pub fn load(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The template equivalent to the built-in layout, written out by
+/// `plan template edit` as a starting point.
+pub fn default_template(layout: &Layout) -> String {
+    match layout {
+        Layout::Plan => "{header}\n{inbox}\n\n---\n".to_string(),
+        Layout::Obsidian { .. } => {
+            "{header}\n\n<< [[{yesterday}]] | [[{tomorrow}]] >>\n\n{inbox}\n".to_string()
+        }
+    }
+}
+
+fn value(layout: &Layout, name: &str, date: NaiveDate) -> Option<String> {
+    let iso = |d: Option<NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+    match (name, layout) {
+        ("header", Layout::Plan) => Some(date.format("%Y, %b %d - %A").to_string()),
+        ("header", Layout::Obsidian { .. }) => Some(date.format("# %A, %B %-d, %Y").to_string()),
+        ("inbox", Layout::Plan) => {
+            let width = date.format("%Y, %b %d - %A").to_string().len();
+            Some(format!(
+                "{}\n{}",
+                crate::file::make_inbox_line(width),
+                "~".repeat(width)
+            ))
+        }
+        ("inbox", Layout::Obsidian { inbox_heading }) => Some(inbox_heading.clone()),
+        ("date", _) => iso(Some(date)),
+        ("weekday", _) => Some(date.format("%A").to_string()),
+        ("yesterday", _) => iso(date.pred_opt()),
+        ("tomorrow", _) => iso(date.succ_opt()),
+        _ => None,
+    }
+}
+
+/// `{name}` spans in `line`, as (byte offset, name). Only lowercase names
+/// count, so braces in ordinary text are left alone.
+fn placeholders(line: &str) -> Vec<(usize, &str)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    while let Some(open) = line[start..].find('{').map(|i| start + i) {
+        let rest = &line[open + 1..];
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c == '_'))
+            .unwrap_or(rest.len());
+        if name_len > 0 && rest[name_len..].starts_with('}') {
+            found.push((open, &rest[..name_len]));
+            start = open + name_len + 2;
+        } else {
+            start = open + 1;
+        }
+    }
+    found
+}
+
+/// Fill in the placeholders for `date`. Unknown ones are left as written.
+pub fn render(layout: &Layout, template: &str, date: NaiveDate) -> String {
+    let mut out = String::with_capacity(template.len());
+    for line in template.split_inclusive('\n') {
+        let mut last = 0;
+        for (at, name) in placeholders(line) {
+            if let Some(v) = value(layout, name, date) {
+                out.push_str(&line[last..at]);
+                out.push_str(&v);
+                last = at + name.len() + 2;
+            }
+        }
+        out.push_str(&line[last..]);
+    }
+    out
+}
+
+/// Something wrong with a template. `line` is 1-based; 0 means the
+/// template as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Unknown placeholders, and whether a file made from the template for
+/// `date` would have an inbox for new entries to go into.
+pub fn check(layout: &Layout, template: &str, date: NaiveDate) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    for (i, line) in template.lines().enumerate() {
+        for (_, name) in placeholders(line) {
+            if value(layout, name, date).is_none() {
+                let known: Vec<String> = PLACEHOLDERS
+                    .iter()
+                    .map(|(n, _)| format!("{{{}}}", n))
+                    .collect();
+                problems.push(Problem {
+                    line: i + 1,
+                    message: format!(
+                        "unknown placeholder {{{}}}; expected one of {}",
+                        name,
+                        known.join(", ")
+                    ),
+                });
+            }
+        }
+    }
+    if crate::section::find_section(layout, &render(layout, template, date), "inbox").is_none() {
+        problems.push(Problem {
+            line: 0,
+            message: "new files would have no inbox section; add {inbox}".to_string(),
+        });
+    }
+    problems
+}
@@ -0,0 +1,156 @@
+use crate::date;
+use crate::layout::Layout;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Pins [`date::now`] to a fixed moment on the current thread until dropped.
+/// Guards nest: dropping one restores the clock it replaced.
+pub struct FixedClock {
+    previous: Option<NaiveDateTime>,
+}
+
+impl FixedClock {
+    pub fn at(moment: NaiveDateTime) -> Self {
+        let previous = date::CLOCK_OVERRIDE.replace(Some(moment));
+        Self { previous }
+    }
+
+    /// Midnight at the start of `day`.
+    pub fn on(day: NaiveDate) -> Self {
+        Self::at(day.and_time(NaiveTime::MIN))
+    }
+}
+
+impl Drop for FixedClock {
+    fn drop(&mut self) {
+        date::CLOCK_OVERRIDE.set(self.previous);
+    }
+}
+
+/// A plan directory in a temporary location, removed when dropped. It carries
+/// the [`crate::file::ROOT_MARKER`] like a directory set up by `plan --init`.
+pub struct PlanDir {
+    temp: TempDir,
+}
+
+impl PlanDir {
+    pub fn new() -> io::Result<Self> {
+        let temp = TempDir::new()?;
+        crate::file::write_root_marker(temp.path())?;
+        Ok(Self { temp })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.temp.path()
+    }
+
+    /// Where the plan file for `day` lives, whether or not it exists.
+    pub fn plan_path(&self, day: NaiveDate) -> PathBuf {
+        date::get_plan_path(&Layout::default(), self.path(), day)
+    }
+
+    /// Write the plan file for `day`, replacing any existing one.
+    pub fn write(&self, day: NaiveDate, content: &str) -> io::Result<PathBuf> {
+        let path = self.plan_path(day);
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// Write an arbitrary file, e.g. a continuation file or a stray file.
+    pub fn write_file(&self, name: &str, content: &str) -> io::Result<PathBuf> {
+        let path = self.path().join(name);
+        fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    pub fn read(&self, day: NaiveDate) -> io::Result<String> {
+        fs::read_to_string(self.plan_path(day))
+    }
+
+    /// Scan the directory the way the CLI does, with no extra ignore patterns.
+    pub fn scan(&self) -> crate::Result<crate::file::ScanResult> {
+        crate::file::scan_plan_dir(&Layout::default(), self.path(), &[])
+    }
+}
+
+/// Builds plan file content in the same layout `plan` itself writes: the
+/// dated header and inbox, the `---` separator, free-form lines, then named
+/// sections and code zones in the order they were added.
+pub struct PlanDoc {
+    day: NaiveDate,
+    inbox: Vec<String>,
+    body: Vec<String>,
+}
+
+impl PlanDoc {
+    pub fn new(day: NaiveDate) -> Self {
+        Self {
+            day,
+            inbox: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Add an inbox task, as `plan log` would.
+    pub fn log(mut self, text: &str) -> Self {
+        self.inbox.push(format!("* {}", text));
+        self
+    }
+
+    /// Add a raw inbox line, as `plan jot` would.
+    pub fn jot(mut self, line: &str) -> Self {
+        self.inbox.push(line.to_string());
+        self
+    }
+
+    /// Add a free-form line below the separator.
+    pub fn line(mut self, line: &str) -> Self {
+        self.body.push(line.to_string());
+        self
+    }
+
+    /// Add a named `~~~name~~~` section holding `lines`.
+    pub fn section(mut self, name: &str, lines: &[&str]) -> Self {
+        let width = self.header().len();
+        self.body.push(String::new());
+        self.body.push(crate::file::make_section_line(name, width));
+        self.body.extend(lines.iter().map(|l| l.to_string()));
+        self.body.push("~".repeat(width));
+        self
+    }
+
+    /// Add a `--- :lang` code zone holding `lines`.
+    pub fn code(mut self, lang: &str, lines: &[&str]) -> Self {
+        self.body.push(format!("--- :{}", lang));
+        self.body.extend(lines.iter().map(|l| l.to_string()));
+        self.body.push("---".to_string());
+        self
+    }
+
+    fn header(&self) -> String {
+        self.day.format("%Y, %b %d - %A").to_string()
+    }
+
+    pub fn build(&self) -> String {
+        let header = self.header();
+        let mut out = String::new();
+        out.push_str(&header);
+        out.push('\n');
+        out.push_str(&crate::file::make_inbox_line(header.len()));
+        out.push('\n');
+        for line in &self.inbox {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str(&"~".repeat(header.len()));
+        out.push_str("\n\n---\n");
+        for line in &self.body {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,144 @@
+use crate::export::PlanFile;
+use crate::task::{self, Priority};
+use chrono::NaiveDate;
+
+/// One line of a todo.txt file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub done: bool,
+    pub priority: Option<Priority>,
+    pub completed: Option<NaiveDate>,
+    pub created: Option<NaiveDate>,
+    /// Description, with `+project` and `@context` tokens left in place
+    pub text: String,
+}
+
+/// Split a leading `YYYY-MM-DD` token off `s`.
+fn strip_date(s: &str) -> Option<(NaiveDate, &str)> {
+    let (token, rest) = s.split_once(' ').unwrap_or((s, ""));
+    let date = NaiveDate::parse_from_str(token, "%Y-%m-%d").ok()?;
+    Some((date, rest.trim_start()))
+}
+
+/// Split a leading `(A)` token off `s`.
+fn strip_priority(s: &str) -> Option<(Priority, &str)> {
+    let (token, rest) = s.split_once(' ').unwrap_or((s, ""));
+    let letter = token.strip_prefix('(')?.strip_suffix(')')?;
+    if letter.len() != 1 || !letter.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some((Priority::parse(letter)?, rest.trim_start()))
+}
+
+/// Parse a todo.txt line: `x` and a completion date for done tasks, then an
+/// optional `(A)` priority and creation date, then the description. Blank
+/// lines give `None`.
+pub fn parse_line(line: &str) -> Option<Entry> {
+    let mut rest = line.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut entry = Entry {
+        done: false,
+        priority: None,
+        completed: None,
+        created: None,
+        text: String::new(),
+    };
+    if let Some(after) = rest.strip_prefix("x ") {
+        entry.done = true;
+        rest = after.trim_start();
+        if let Some((date, after)) = strip_date(rest) {
+            entry.completed = Some(date);
+            rest = after;
+        }
+    }
+    if let Some((priority, after)) = strip_priority(rest) {
+        entry.priority = Some(priority);
+        rest = after;
+    }
+    if let Some((date, after)) = strip_date(rest) {
+        entry.created = Some(date);
+        rest = after;
+    }
+    // Done tasks often keep their priority as a `pri:A` tag
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+        match word.strip_prefix("pri:").and_then(Priority::parse) {
+            Some(p) if entry.done && entry.priority.is_none() => entry.priority = Some(p),
+            _ => words.push(word),
+        }
+    }
+    entry.text = words.join(" ");
+    Some(entry)
+}
+
+/// `+project` and `@context` become `#project` and `#context` tags.
+fn tags_from_todotxt(text: &str) -> String {
+    text.split(' ')
+        .map(|word| match word.strip_prefix(['+', '@']) {
+            Some(name) if name.starts_with(char::is_alphabetic) => format!("#{}", name),
+            _ => word.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Entry {
+    /// The day the entry is filed under: its creation date, else its
+    /// completion date, else `today`.
+    pub fn day(&self, today: NaiveDate) -> NaiveDate {
+        self.created.or(self.completed).unwrap_or(today)
+    }
+
+    /// The entry as a plan task line. Done tasks are closed the way
+    /// `plan review` closes them, with the completion date at the end.
+    pub fn to_plan_line(&self) -> String {
+        let text = tags_from_todotxt(&self.text);
+        let body = match self.priority {
+            Some(p) => format!("{} {}", p, text),
+            None => text,
+        };
+        match (self.done, self.completed) {
+            (false, _) => format!("* {}", body),
+            (true, Some(date)) => format!("+ {} ({})", body, date.format("%Y-%m-%d")),
+            (true, None) => format!("+ {}", body),
+        }
+    }
+}
+
+/// `#tag` tokens become `@context` tokens.
+fn tags_to_todotxt(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            if crate::tags::extract_tags(word).is_empty() {
+                word.to_string()
+            } else {
+                format!("@{}", &word[1..])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Every pending task in `files` as a todo.txt line, with the file's date as
+/// the creation date and `#tags` as contexts.
+pub fn to_todotxt(files: &[PlanFile]) -> String {
+    let mut out = String::new();
+    for file in files {
+        for t in task::parse_tasks(&file.content) {
+            if !t.state.is_pending() {
+                continue;
+            }
+            if let Some(p) = t.priority {
+                out.push_str(&format!("{} ", p));
+            }
+            out.push_str(&format!(
+                "{} {}\n",
+                file.date.format("%Y-%m-%d"),
+                tags_to_todotxt(&t.text)
+            ));
+        }
+    }
+    out
+}
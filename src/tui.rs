@@ -0,0 +1,597 @@
+use crate::color::Role;
+use crate::config::Config;
+use crate::task::{self, TaskState};
+use chrono::NaiveDate;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const HELP: &str = "j/k move  / search  a log  n jot  c tasks  e edit  t today  q quit";
+
+struct PlanFile {
+    date: NaiveDate,
+    path: PathBuf,
+    content: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    Log,
+    Jot,
+}
+
+enum Mode {
+    Browse,
+    Search,
+    Input {
+        kind: InputKind,
+        text: String,
+    },
+    /// Picking a pending task in the preview; `cursor` indexes the pending list
+    Tasks {
+        cursor: usize,
+    },
+}
+
+/// What the caller of [`App::handle_key`] should do next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    Quit,
+    /// Suspend the UI and open this file in the editor
+    Edit(PathBuf),
+}
+
+/// State of `plan tui`: a date list, a preview of the selected file, and the
+/// current input mode. Drawing and key handling are kept apart from the
+/// terminal so the app can be driven by tests.
+pub struct App<'a> {
+    cfg: &'a Config,
+    /// Newest first
+    files: Vec<PlanFile>,
+    /// Indices into `files` matching the search query
+    visible: Vec<usize>,
+    selected: usize,
+    query: String,
+    mode: Mode,
+    status: String,
+}
+
+impl<'c> App<'c> {
+    pub fn load(cfg: &'c Config) -> io::Result<Self> {
+        let mut app = Self {
+            cfg,
+            files: Vec::new(),
+            visible: Vec::new(),
+            selected: 0,
+            query: String::new(),
+            mode: Mode::Browse,
+            status: String::new(),
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    /// Re-read the plan directory, keeping the selected date if it still exists.
+    pub fn reload(&mut self) -> io::Result<()> {
+        let keep = self.current().map(|f| f.date);
+        let mut files = Vec::new();
+        if self.cfg.dir.exists() {
+            for entry in crate::file::scan_plan_dir(
+                &self.cfg.layout,
+                &self.cfg.dir,
+                &self.cfg.scan.ignored_patterns,
+            )?
+            .plan_entries
+            {
+                let Some(date) =
+                    crate::file::plan_date(&self.cfg.layout, &entry.file_name().to_string_lossy())
+                else {
+                    continue;
+                };
+                files.push(PlanFile {
+                    date,
+                    path: entry.path(),
+                    content: fs::read_to_string(entry.path())?,
+                });
+            }
+        }
+        files.sort_by_key(|f| std::cmp::Reverse(f.date));
+        self.files = files;
+        self.refilter(keep);
+        Ok(())
+    }
+
+    fn refilter(&mut self, keep: Option<NaiveDate>) {
+        let q = self.query.to_lowercase();
+        self.visible = (0..self.files.len())
+            .filter(|&i| q.is_empty() || self.files[i].content.to_lowercase().contains(&q))
+            .collect();
+        self.selected = keep
+            .and_then(|d| self.visible.iter().position(|&i| self.files[i].date == d))
+            .unwrap_or(0);
+    }
+
+    fn current(&self) -> Option<&PlanFile> {
+        self.visible.get(self.selected).map(|&i| &self.files[i])
+    }
+
+    fn pending_tasks(&self) -> Vec<task::Task> {
+        self.current()
+            .map(|f| task::parse_tasks(&f.content))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|t| t.state.is_pending())
+            .collect()
+    }
+
+    fn select(&mut self, date: NaiveDate) {
+        self.query.clear();
+        self.refilter(Some(date));
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> io::Result<Action> {
+        if key.kind == KeyEventKind::Release {
+            return Ok(Action::Continue);
+        }
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => return self.handle_browse(key),
+            Mode::Search => self.handle_search(key),
+            Mode::Input { kind, text } => self.handle_input(key, kind, text)?,
+            Mode::Tasks { cursor } => self.handle_tasks(key, cursor)?,
+        }
+        Ok(Action::Continue)
+    }
+
+    fn handle_browse(&mut self, key: KeyEvent) -> io::Result<Action> {
+        self.status.clear();
+        match key.code {
+            KeyCode::Char('q') => return Ok(Action::Quit),
+            KeyCode::Esc if self.query.is_empty() => return Ok(Action::Quit),
+            KeyCode::Esc => {
+                self.query.clear();
+                let keep = self.current().map(|f| f.date);
+                self.refilter(keep);
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.selected = (self.selected + 1).min(self.visible.len().saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => self.selected = 0,
+            KeyCode::Char('G') | KeyCode::End => {
+                self.selected = self.visible.len().saturating_sub(1);
+            }
+            KeyCode::Char('/') => self.mode = Mode::Search,
+            KeyCode::Char('a') | KeyCode::Char('n') if self.current().is_some() => {
+                let kind = if key.code == KeyCode::Char('a') {
+                    InputKind::Log
+                } else {
+                    InputKind::Jot
+                };
+                self.mode = Mode::Input {
+                    kind,
+                    text: String::new(),
+                };
+            }
+            KeyCode::Char('c') if self.current().is_some() => {
+                if self.pending_tasks().is_empty() {
+                    self.status = "No open tasks in this file.".to_string();
+                } else {
+                    self.mode = Mode::Tasks { cursor: 0 };
+                }
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some(f) = self.current() {
+                    return Ok(Action::Edit(f.path.clone()));
+                }
+            }
+            KeyCode::Char('t') => {
+                let today = crate::date::today();
+                let path = crate::date::get_plan_path(&self.cfg.layout, &self.cfg.dir, today);
+                {
+                    let _lock = crate::file::acquire_lock(&path, &self.cfg.lock)?;
+                    if !path.exists() {
+                        let template = crate::template::load(&self.cfg.template)?;
+                        crate::date::ensure_file_exists(
+                            &self.cfg.layout,
+                            template.as_deref(),
+                            &path,
+                            today,
+                            false,
+                        )?;
+                        crate::undo::record_write("tui", &path, None);
+                    }
+                }
+                self.reload()?;
+                self.select(today);
+            }
+            _ => {}
+        }
+        Ok(Action::Continue)
+    }
+
+    fn handle_search(&mut self, key: KeyEvent) {
+        let keep = self.current().map(|f| f.date);
+        match key.code {
+            KeyCode::Enter => return,
+            KeyCode::Esc => self.query.clear(),
+            KeyCode::Backspace => {
+                self.query.pop();
+            }
+            KeyCode::Char(c) => self.query.push(c),
+            _ => {}
+        }
+        self.refilter(keep);
+        if key.code != KeyCode::Esc {
+            self.mode = Mode::Search;
+        }
+    }
+
+    fn handle_input(&mut self, key: KeyEvent, kind: InputKind, mut text: String) -> io::Result<()> {
+        match key.code {
+            KeyCode::Esc => return Ok(()),
+            KeyCode::Enter => {
+                let text = text.trim();
+                let Some(path) = self.current().map(|f| f.path.clone()) else {
+                    return Ok(());
+                };
+                if text.is_empty() {
+                    return Ok(());
+                }
+                let line = match kind {
+                    InputKind::Log => format!("* {}", text),
+                    InputKind::Jot => text.to_string(),
+                };
+                {
+                    let lock = crate::file::acquire_lock(&path, &self.cfg.lock)?;
+                    let before = fs::read_to_string(&path)?;
+                    crate::backup::snapshot(&path, &self.cfg.backups)?;
+                    crate::file::insert_into_inbox(&self.cfg.layout, &path, &line, &lock)?;
+                    crate::undo::record_write("tui", &path, Some(&before));
+                }
+                self.status = format!("Added to {}", file_name(&path));
+                return self.reload();
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Input { kind, text };
+        Ok(())
+    }
+
+    fn handle_tasks(&mut self, key: KeyEvent, mut cursor: usize) -> io::Result<()> {
+        let tasks = self.pending_tasks();
+        let state = match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(()),
+            KeyCode::Char('j') | KeyCode::Down => {
+                cursor = (cursor + 1).min(tasks.len().saturating_sub(1));
+                None
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                cursor = cursor.saturating_sub(1);
+                None
+            }
+            KeyCode::Enter | KeyCode::Char('c') => Some(TaskState::Done),
+            KeyCode::Char('d') => Some(TaskState::Cancelled),
+            _ => None,
+        };
+
+        if let (Some(state), Some(t)) = (state, tasks.get(cursor)) {
+            let path = self.current().map(|f| f.path.clone()).unwrap_or_default();
+            if close_task_in_file(self.cfg, &path, t.line_number, state)? {
+                self.status = match state {
+                    TaskState::Done => format!("Completed: {}", t.text),
+                    _ => format!("Dropped: {}", t.text),
+                };
+            } else {
+                self.status = "File changed on disk; task left as is.".to_string();
+            }
+            return self.reload();
+        }
+        self.mode = Mode::Tasks { cursor };
+        Ok(())
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, preview_area] =
+            Layout::horizontal([Constraint::Length(18), Constraint::Min(0)]).areas(main);
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let d = self.files[i].date;
+                ListItem::new(format!("{} {}", d, d.format("%a")))
+            })
+            .collect();
+        let title = if self.query.is_empty() {
+            "plans".to_string()
+        } else {
+            format!("{}/{}", self.visible.len(), self.files.len())
+        };
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        if !self.visible.is_empty() {
+            state.select(Some(self.selected));
+        }
+        frame.render_stateful_widget(list, list_area, &mut state);
+
+        let (preview, scroll) = self.preview_lines(preview_area.height.saturating_sub(2));
+        let title = self
+            .current()
+            .map(|f| file_name(&f.path))
+            .unwrap_or_default();
+        frame.render_widget(
+            Paragraph::new(preview)
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .scroll((scroll, 0)),
+            preview_area,
+        );
+
+        let line = match &self.mode {
+            Mode::Search => format!("/{}", self.query),
+            Mode::Input { kind, text } => match kind {
+                InputKind::Log => format!("log: {}", text),
+                InputKind::Jot => format!("jot: {}", text),
+            },
+            Mode::Tasks { .. } => "j/k choose  enter complete  d drop  esc back".to_string(),
+            Mode::Browse if !self.status.is_empty() => self.status.clone(),
+            Mode::Browse => HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+
+    /// Styled preview of the selected file, and how far to scroll it so the
+    /// task cursor stays visible.
+    fn preview_lines(&self, height: u16) -> (Vec<Line<'_>>, u16) {
+        let Some(file) = self.current() else {
+            return (vec![Line::from("No plan files.")], 0);
+        };
+        let cursor_line = match self.mode {
+            Mode::Tasks { cursor } => self.pending_tasks().get(cursor).map(|t| t.line_number),
+            _ => None,
+        };
+        let lines = file
+            .content
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut styled = self.style_line(line);
+                if cursor_line == Some(i + 1) {
+                    styled = styled.style(Style::default().add_modifier(Modifier::REVERSED));
+                }
+                styled
+            })
+            .collect();
+        let scroll = cursor_line
+            .map(|n| (n as u16).saturating_sub(height))
+            .unwrap_or(0);
+        (lines, scroll)
+    }
+
+    fn style_line<'a>(&self, line: &'a str) -> Line<'a> {
+        let role = match task::parse_task(line, 0).map(|t| t.state) {
+            Some(TaskState::Inbox) => Role::Inbox,
+            Some(TaskState::Open) => Role::Open,
+            Some(TaskState::Done) => Role::Done,
+            Some(TaskState::Cancelled) => Role::Cancelled,
+            None if task::parse_event(line, 0).is_some() => Role::Event,
+            None => return Line::from(line),
+        };
+        let (sigil, rest) = line.split_at(1);
+        Line::from(vec![
+            Span::styled(sigil, style_from_sgr(self.cfg.colors.sgr(role))),
+            Span::raw(rest),
+        ])
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Close the task at `line_number` under the file lock, re-reading first.
+/// Returns false if the line is no longer a pending task.
+fn close_task_in_file(
+    cfg: &Config,
+    path: &Path,
+    line_number: usize,
+    state: TaskState,
+) -> io::Result<bool> {
+    let _lock = crate::file::acquire_lock(path, &cfg.lock)?;
+    let content = fs::read_to_string(path)?;
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let Some(closed) = lines
+        .get(line_number - 1)
+        .and_then(|l| task::close_task(l, state, crate::date::today()))
+    else {
+        return Ok(false);
+    };
+    lines[line_number - 1] = closed;
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    let eol = crate::file::line_ending(&content);
+    crate::backup::snapshot(path, &cfg.backups)?;
+    crate::file::write_atomic(path, &crate::file::with_line_ending(&updated, eol))?;
+    crate::undo::record_write("tui", path, Some(&content));
+    Ok(true)
+}
+
+/// Translate SGR parameters from a [`crate::color::Palette`] into a ratatui style.
+fn style_from_sgr(sgr: Option<&str>) -> Style {
+    let mut style = Style::default();
+    let Some(sgr) = sgr else {
+        return style;
+    };
+    let params: Vec<u8> = sgr.split(';').filter_map(|p| p.parse().ok()).collect();
+    let mut i = 0;
+    while i < params.len() {
+        style = match params[i] {
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            n @ 30..=37 => style.fg(Color::Indexed(n - 30)),
+            n @ 90..=97 => style.fg(Color::Indexed(n - 90 + 8)),
+            38 if params.get(i + 1) == Some(&5) && i + 2 < params.len() => {
+                i += 2;
+                style.fg(Color::Indexed(params[i]))
+            }
+            _ => style,
+        };
+        i += 1;
+    }
+    style
+}
+
+/// What the caller of [`Picker::handle_key`] should do next.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PickAction {
+    Continue,
+    Cancel,
+    /// Index into the items the picker was created with
+    Select(usize),
+}
+
+/// A fuzzy-filterable single-choice list, as used by `plan pick`.
+pub struct Picker {
+    items: Vec<String>,
+    query: String,
+    /// Indices into `items`, best match first
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Picker {
+    pub fn new(items: Vec<String>) -> Self {
+        let mut picker = Self {
+            items,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        picker.refilter();
+        picker
+    }
+
+    fn refilter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| Some((crate::search::fuzzy_score(&self.query, item)?, i)))
+            .collect();
+        // Stable sort keeps the original order among equal scores
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickAction {
+        if key.kind == KeyEventKind::Release {
+            return PickAction::Continue;
+        }
+        match key.code {
+            KeyCode::Esc => return PickAction::Cancel,
+            KeyCode::Enter => {
+                return match self.matches.get(self.selected) {
+                    Some(&i) => PickAction::Select(i),
+                    None => PickAction::Continue,
+                };
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::BackTab => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        PickAction::Continue
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [list_area, prompt] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|&i| ListItem::new(self.items[i].as_str()))
+            .collect();
+        let title = format!("{}/{}", self.matches.len(), self.items.len());
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        if !self.matches.is_empty() {
+            state.select(Some(self.selected));
+        }
+        frame.render_stateful_widget(list, list_area, &mut state);
+        frame.render_widget(Paragraph::new(format!("> {}", self.query)), prompt);
+    }
+}
+
+/// Let the user pick one of `items`. Returns its index, or `None` if cancelled.
+pub fn pick(items: Vec<String>) -> io::Result<Option<usize>> {
+    let mut picker = Picker::new(items);
+    let mut terminal = ratatui::init();
+    let result = (|| loop {
+        terminal.draw(|frame| picker.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            match picker.handle_key(key) {
+                PickAction::Continue => {}
+                PickAction::Cancel => return Ok(None),
+                PickAction::Select(i) => return Ok(Some(i)),
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+/// Run the TUI until the user quits. `edit` is called with the terminal
+/// restored whenever the user asks to open a file in the editor.
+pub fn run(cfg: &Config, mut edit: impl FnMut(&Path) -> io::Result<()>) -> io::Result<()> {
+    let mut app = App::load(cfg)?;
+    let mut terminal = ratatui::init();
+    let result = (|| loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            match app.handle_key(key)? {
+                Action::Continue => {}
+                Action::Quit => return Ok(()),
+                Action::Edit(path) => {
+                    ratatui::restore();
+                    let edited = edit(&path);
+                    terminal = ratatui::init();
+                    edited?;
+                    app.reload()?;
+                }
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
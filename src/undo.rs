@@ -0,0 +1,203 @@
+use crate::config::LockConfig;
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const JOURNAL: &str = "undo";
+
+/// Only the most recent writes can be undone; older entries are dropped.
+const MAX_ENTRIES: usize = 20;
+
+/// One command's writes to plan files, undone together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The command that wrote, e.g. `log`
+    pub command: String,
+    /// Every file the command wrote, in the order it wrote them
+    pub files: Vec<FileChange>,
+}
+
+/// A plan file written by a recorded command and what it held before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: PathBuf,
+    /// The content before the write; `None` when the write created the file
+    pub before: Option<String>,
+    /// Hash of the content the write left, to notice later edits
+    after: u64,
+}
+
+impl FileChange {
+    /// `path` changed from `before` to `after`.
+    pub fn new(path: &Path, before: Option<&str>, after: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            before: before.map(str::to_string),
+            after: crate::index::fnv1a(after.as_bytes()),
+        }
+    }
+}
+
+fn parse_change(value: &Value) -> Option<FileChange> {
+    Some(FileChange {
+        path: PathBuf::from(value.get("path")?.as_str()?),
+        before: value.get("before")?.as_str().map(str::to_string),
+        after: u64::from_str_radix(value.get("after")?.as_str()?, 16).ok()?,
+    })
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    let value: Value = serde_json::from_str(line).ok()?;
+    let files = match value.get("files") {
+        Some(files) => files
+            .as_array()?
+            .iter()
+            .map(parse_change)
+            .collect::<Option<Vec<_>>>()?,
+        // Entries written before one could hold several files
+        None => vec![parse_change(&value)?],
+    };
+    Some(Entry {
+        command: value.get("command")?.as_str()?.to_string(),
+        files,
+    })
+}
+
+fn format_entry(entry: &Entry) -> String {
+    let files: Vec<Value> = entry
+        .files
+        .iter()
+        .map(|f| {
+            json!({
+                "path": f.path.to_string_lossy(),
+                "before": f.before,
+                "after": format!("{:016x}", f.after),
+            })
+        })
+        .collect();
+    json!({ "command": entry.command, "files": files }).to_string()
+}
+
+/// Journal entries, oldest first. Malformed lines are skipped.
+fn read_journal(path: &Path) -> crate::Result<Vec<Entry>> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content.lines().filter_map(parse_entry).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_journal(path: &Path, entries: &[Entry]) -> crate::Result<()> {
+    let content: String = entries
+        .iter()
+        .map(|e| format!("{}\n", format_entry(e)))
+        .collect();
+    crate::file::write_atomic(path, &content)
+}
+
+/// Record that `command` made the changes in `files`, as one entry.
+pub fn record(state_dir: &Path, command: &str, files: Vec<FileChange>) -> crate::Result<()> {
+    crate::migrate::ensure_current(state_dir)?;
+    let journal = state_dir.join(JOURNAL);
+    let _lock = crate::file::acquire_lock(&journal, &LockConfig::default())?;
+    let mut entries = read_journal(&journal)?;
+    entries.push(Entry {
+        command: command.to_string(),
+        files,
+    });
+    let skip = entries.len().saturating_sub(MAX_ENTRIES);
+    write_journal(&journal, &entries[skip..])
+}
+
+/// Record that `command` just wrote `plan_path`, reading back what it left,
+/// in the user's state directory. A failure only warns, since the write
+/// itself went through.
+///
+/// Every command that writes a plan file calls this or [`record_writes`],
+/// except `resolve` and scratch merges, which delete a file undo can't
+/// bring back, and writes made in the editor, which plan doesn't see.
+pub fn record_write(command: &str, plan_path: &Path, before: Option<&str>) {
+    record_writes(command, &[(plan_path, before)]);
+}
+
+/// Like [`record_write`] for a command that wrote several files, such as
+/// `replace`, so one `plan undo` takes back all of them.
+pub fn record_writes(command: &str, files: &[(&Path, Option<&str>)]) {
+    let recorded = files
+        .iter()
+        .map(|(path, before)| Ok(FileChange::new(path, *before, &fs::read_to_string(path)?)))
+        .collect::<crate::Result<Vec<_>>>()
+        .and_then(|files| record(&crate::paths::state_dir(), command, files));
+    if let Err(e) = recorded {
+        crate::diag::warn(
+            crate::diag::Warning::HistoryUnavailable,
+            &format!("could not record undo: {}", e),
+        );
+    }
+}
+
+/// What [`undo`] did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Undone {
+    /// Every file in the entry was put back to its content before the
+    /// write, or removed if the write had created it
+    Reverted(Entry),
+    /// The journal is empty
+    Nothing,
+}
+
+/// Reverse the most recent recorded entry, holding the locks of all its
+/// files. If any of them was edited since, nothing is touched unless
+/// `force` is set, so undo never throws away changes it didn't make.
+pub fn undo(cfg: &crate::config::Config, state_dir: &Path, force: bool) -> crate::Result<Undone> {
+    let journal = state_dir.join(JOURNAL);
+    if !journal.exists() {
+        return Ok(Undone::Nothing);
+    }
+    let _journal_lock = crate::file::acquire_lock(&journal, &LockConfig::default())?;
+    let mut entries = read_journal(&journal)?;
+    let Some(entry) = entries.pop() else {
+        return Ok(Undone::Nothing);
+    };
+
+    // One order for every caller, so two multi-file writers can't deadlock
+    let mut paths: Vec<&Path> = entry.files.iter().map(|f| f.path.as_path()).collect();
+    paths.sort();
+    paths.dedup();
+    let _locks = paths
+        .iter()
+        .map(|path| crate::file::acquire_lock(path, &cfg.lock))
+        .collect::<crate::Result<Vec<_>>>()?;
+
+    let mut current = Vec::new();
+    for change in &entry.files {
+        let content = match fs::read_to_string(&change.path) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let unchanged = content
+            .as_deref()
+            .is_some_and(|c| crate::index::fnv1a(c.as_bytes()) == change.after);
+        if !unchanged && !force {
+            return Err(crate::Error::ChangedSince {
+                path: change.path.clone(),
+                command: entry.command,
+            });
+        }
+        current.push(content);
+    }
+
+    for (change, content) in entry.files.iter().zip(current) {
+        // Keep a copy when backups are on, like any other write
+        crate::backup::snapshot(&change.path, &cfg.backups)?;
+        match &change.before {
+            Some(before) => crate::file::write_atomic(&change.path, before)?,
+            None if content.is_some() => fs::remove_file(&change.path)?,
+            None => {}
+        }
+    }
+    write_journal(&journal, &entries)?;
+    Ok(Undone::Reverted(entry))
+}
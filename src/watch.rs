@@ -0,0 +1,88 @@
+use crate::layout::Layout;
+use chrono::NaiveDate;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long to wait for more events once one arrives, so an editor's
+/// write, rename, and chmod cause one refresh rather than three.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Whether `event` changed a plan file. Reads are ignored, so the callback
+/// reading the files doesn't trigger itself.
+fn touches_plan_file(layout: &Layout, event: &Event) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+        && event.paths.iter().any(|p| {
+            p.file_name()
+                .is_some_and(|n| crate::file::is_plan_file(layout, &n.to_string_lossy()))
+        })
+}
+
+/// Call `on_change` once the watch is in place, then after each burst of
+/// changes to plan files in `dir`, until it returns `Ok(false)` or an error.
+pub fn watch<E: From<io::Error>>(
+    layout: &Layout,
+    dir: &Path,
+    mut on_change: impl FnMut() -> Result<bool, E>,
+) -> Result<(), E> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(io::Error::other)?;
+    watcher
+        .watch(dir, RecursiveMode::NonRecursive)
+        .map_err(io::Error::other)?;
+
+    if !on_change()? {
+        return Ok(());
+    }
+    while let Ok(event) = rx.recv() {
+        let mut changed = touches_plan_file(layout, &event.map_err(io::Error::other)?);
+        while let Ok(event) = rx.recv_timeout(SETTLE) {
+            changed |= touches_plan_file(layout, &event.map_err(io::Error::other)?);
+        }
+        if changed && !on_change()? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Lines of `after` that `before` doesn't have, in order. A line that
+/// appears more times than before is new once for each extra copy.
+pub fn new_lines<'a>(before: &[String], after: &'a [String]) -> Vec<&'a str> {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for line in before {
+        *seen.entry(line).or_default() += 1;
+    }
+    after
+        .iter()
+        .filter(|line| match seen.get_mut(line.as_str()) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                false
+            }
+            _ => true,
+        })
+        .map(String::as_str)
+        .collect()
+}
+
+/// Run a `plan watch --exec` command the way hooks run (see
+/// `hooks::hook_command`), with the watched file in `PLAN_FILE`.
+/// A non-zero exit is an error.
+pub fn run_command(command: &str, plan_path: &Path, date: NaiveDate) -> io::Result<()> {
+    let (program, mut cmd) = crate::hooks::hook_command("command", command, plan_path, date)?;
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "'{}' exited with {}",
+            program, status
+        )));
+    }
+    Ok(())
+}
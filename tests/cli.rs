@@ -5,8 +5,11 @@ mod txtar;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tempfile::TempDir;
 
 struct TxtarTest {
@@ -16,12 +19,37 @@ struct TxtarTest {
 
 fn parse_txtar(content: &str) -> TxtarTest {
     let archive = txtar::Archive::from(content);
-    let commands: Vec<String> = archive
-        .comment()
-        .lines()
-        .filter(|l| !l.trim().is_empty())
-        .map(|l| l.trim().to_string())
-        .collect();
+    let mut commands: Vec<String> = Vec::new();
+    let mut lines = archive.comment().lines();
+    while let Some(line) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // A heredoc keeps its lines verbatim, blank ones included, and stays
+        // one command so golden updates write it back unchanged
+        if let Some(marker) = line
+            .strip_prefix("stdin <<")
+            .or_else(|| line.strip_prefix("stdin --crlf <<"))
+        {
+            let mut heredoc = vec![line.to_string()];
+            for body in lines.by_ref() {
+                heredoc.push(body.to_string());
+                if body.trim() == marker {
+                    break;
+                }
+            }
+            assert_eq!(
+                heredoc.last().map(|l| l.trim()),
+                Some(marker),
+                "unterminated heredoc: {}",
+                line
+            );
+            commands.push(heredoc.join("\n"));
+            continue;
+        }
+        commands.push(line.to_string());
+    }
     let files: Vec<(String, String)> = archive
         .iter()
         .map(|f| (f.name.clone(), f.content.clone()))
@@ -31,10 +59,7 @@ fn parse_txtar(content: &str) -> TxtarTest {
 
 /// Collect all non-lock files from a directory.
 fn collect_dir_files(dir: &PathBuf) -> Vec<(String, String)> {
-    let mut entries: Vec<_> = fs::read_dir(dir)
-        .unwrap()
-        .filter_map(Result::ok)
-        .collect();
+    let mut entries: Vec<_> = fs::read_dir(dir).unwrap().filter_map(Result::ok).collect();
     entries.sort_by_key(|e| e.path());
 
     entries
@@ -54,12 +79,7 @@ fn collect_dir_files(dir: &PathBuf) -> Vec<(String, String)> {
         .collect()
 }
 
-fn write_txtar_file(
-    path: &PathBuf,
-    commands: &[String],
-    plan_dir: &PathBuf,
-    output_dir: &PathBuf,
-) {
+fn write_txtar_file(path: &PathBuf, commands: &[String], plan_dir: &PathBuf, output_dir: &PathBuf) {
     let mut builder = txtar::Builder::new();
     let comment = commands.join("\n") + "\n";
     builder.comment(comment);
@@ -76,6 +96,141 @@ fn write_txtar_file(
     fs::write(path, builder.build().to_string()).expect("Failed to write golden file");
 }
 
+/// Check a `[...]` assertion line:
+///
+/// - `[stdout matches REGEX]` / `[stderr matches REGEX]` search the last plan
+///   command's output, with the plan directory shown as `$PLAN_DIR`. The
+///   stream is then left out of the golden files, so values that change from
+///   run to run don't need a full snapshot.
+/// - `[file absent GLOB]` / `[file exists GLOB]` check the plan directory for
+///   a file whose name matches `GLOB` (`*` and `?` wildcards).
+fn check_assertion(
+    assertion: &str,
+    plan_dir: &Path,
+    output_dir: &Path,
+    last_output: Option<&(usize, String, String)>,
+) {
+    let (subject, rest) = assertion
+        .split_once(' ')
+        .unwrap_or_else(|| panic!("malformed assertion: [{}]", assertion));
+    let (verb, arg) = rest
+        .split_once(' ')
+        .unwrap_or_else(|| panic!("malformed assertion: [{}]", assertion));
+    match (subject, verb) {
+        ("stdout" | "stderr", "matches") => {
+            let (index, stdout, stderr) =
+                last_output.expect("output assertion before any plan command");
+            let output = if subject == "stdout" { stdout } else { stderr };
+            let pattern = regex::Regex::new(arg)
+                .unwrap_or_else(|e| panic!("bad regex in [{}]: {}", assertion, e));
+            assert!(
+                pattern.is_match(output),
+                "[{}] failed for command {}; {} was:\n{}",
+                assertion,
+                index,
+                subject,
+                output
+            );
+            let _ = fs::remove_file(output_dir.join(format!("cmd_{}_{}.txt", index, subject)));
+        }
+        ("file", "absent" | "exists") => {
+            let pattern = glob_regex(arg);
+            let found = fs::read_dir(plan_dir)
+                .unwrap()
+                .filter_map(Result::ok)
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .find(|name| pattern.is_match(name));
+            match (verb, found) {
+                ("absent", Some(name)) => panic!("[{}] failed: found {}", assertion, name),
+                ("exists", None) => panic!("[{}] failed: no such file", assertion),
+                _ => {}
+            }
+        }
+        _ => panic!("unknown assertion: [{}]", assertion),
+    }
+}
+
+/// Write the script for an `editor` directive and return the VISUAL value
+/// that runs it, or `None` for `editor cat`. The script gets the plan file
+/// as its last argument:
+///
+/// - `editor append TEXT` appends a line to the file
+/// - `editor exit N` exits with status `N` without touching it
+/// - `editor signal SIG` kills itself with `SIG`
+/// - `editor args [EXTRA...]` prints each argument as `[arg]`; `EXTRA` is
+///   added to VISUAL as written, to exercise its splitting
+#[cfg(unix)]
+fn install_fake_editor(spec: &str, temp: &Path) -> Option<String> {
+    let (kind, rest) = spec.split_once(' ').unwrap_or((spec, ""));
+    let quote = |s: &str| shlex::try_quote(s).unwrap().into_owned();
+    let body = match kind {
+        "cat" => return None,
+        "append" => {
+            let text = shlex::split(rest)
+                .expect("Invalid shell quoting in editor directive")
+                .join(" ");
+            format!("printf '%s\\n' {} >> \"$file\"", quote(&text))
+        }
+        "exit" => format!(
+            "exit {}",
+            rest.trim().parse::<i32>().expect("Invalid exit code")
+        ),
+        "signal" => format!("kill -{} $$", rest.trim()),
+        "args" => "for arg in \"$@\"; do printf '[%s]\\n' \"$arg\"; done".to_string(),
+        _ => panic!("Unknown editor directive: editor {}", spec),
+    };
+    let script = temp.join("fake-editor");
+    fs::write(
+        &script,
+        format!("#!/bin/sh\neval \"file=\\${{$#}}\"\n{}\n", body),
+    )
+    .unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    let visual = quote(&script.to_string_lossy());
+    Some(match kind {
+        "args" if !rest.trim().is_empty() => format!("{} {}", visual, rest.trim()),
+        _ => visual,
+    })
+}
+
+/// The fake editors are shell scripts, so scripts using them only run on
+/// Unix; `fake_editor.txtar` is registered for Unix alone.
+#[cfg(not(unix))]
+fn install_fake_editor(spec: &str, _temp: &Path) -> Option<String> {
+    match spec {
+        "cat" => None,
+        _ => panic!("editor {} needs a POSIX shell", spec),
+    }
+}
+
+/// The modification time a `touch FILE WHEN` directive sets. The mock date
+/// doesn't move the real clock, so `WHEN` is either `+Nd`, N days past the
+/// real time, or a `YYYY-MM-DD` date at midnight UTC.
+fn touch_time(when: &str) -> SystemTime {
+    const DAY: u64 = 24 * 60 * 60;
+    if let Some(days) = when.strip_prefix('+').and_then(|d| d.strip_suffix('d')) {
+        let days: u64 = days.parse().expect("Invalid touch offset");
+        return SystemTime::now() + Duration::from_secs(days * DAY);
+    }
+    let date = chrono::NaiveDate::parse_from_str(when, "%Y-%m-%d").expect("Invalid touch date");
+    let seconds = date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp();
+    UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).expect("touch date before 1970"))
+}
+
+/// A regex matching whole file names against a `*`/`?` glob.
+fn glob_regex(glob: &str) -> regex::Regex {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern).unwrap()
+}
+
 fn run_txtar_test(path: PathBuf) {
     let content = fs::read_to_string(&path).expect("Failed to read txtar file");
     let test = parse_txtar(&content);
@@ -83,12 +238,24 @@ fn run_txtar_test(path: PathBuf) {
     let temp = TempDir::new().expect("Failed to create temp dir");
     let plan_dir = temp.path().join("plan_files");
     let output_dir = temp.path().join("cmd_output");
+    // Keeps history, the undo journal, backups, and the search index out of
+    // the real state, data, and cache directories
+    let state_dir = temp.path().join("state");
+    let data_dir = temp.path().join("data");
+    let cache_dir = temp.path().join("cache");
     fs::create_dir_all(&plan_dir).unwrap();
     fs::create_dir_all(&output_dir).unwrap();
     let mut mock_date = chrono::NaiveDate::from_ymd_opt(2026, 2, 19).unwrap();
 
     // Execute commands
     let mut executed_cmd_index = 1;
+    // Input queued by a `stdin` directive for the next plan command
+    let mut pending_stdin: Option<String> = None;
+    // Index and sanitized stdout and stderr of the last plan command, for
+    // `[stdout matches ...]` assertions
+    let mut last_output: Option<(usize, String, String)> = None;
+    // VISUAL set by the last `editor` directive, when it isn't plain `cat`
+    let mut fake_editor: Option<String> = None;
     for cmd in test.commands.iter() {
         if cmd.starts_with("#") {
             continue;
@@ -100,7 +267,34 @@ fn run_txtar_test(path: PathBuf) {
             cmd.as_str()
         };
 
-        if cmd_clean.starts_with(">> forward ") {
+        if let Some(source) = cmd_clean.strip_prefix("stdin ") {
+            let input = match source.split_once('\n') {
+                // `stdin <<EOF`, the body ending at the marker line;
+                // `stdin --crlf <<EOF` ends each line with \r\n instead
+                Some((directive, rest)) => {
+                    let body = match rest.rsplit_once('\n') {
+                        Some((body, _)) => format!("{}\n", body),
+                        None => String::new(),
+                    };
+                    if directive.starts_with("--crlf ") {
+                        body.replace('\n', "\r\n")
+                    } else {
+                        body
+                    }
+                }
+                // `stdin NAME`, a file already in the plan directory
+                None => fs::read_to_string(plan_dir.join(source.trim()))
+                    .unwrap_or_else(|e| panic!("stdin file {}: {}", source.trim(), e)),
+            };
+            pending_stdin = Some(input.replace("$PLAN_DIR", &plan_dir.to_string_lossy()));
+        } else if let Some(assertion) = cmd_clean
+            .strip_prefix('[')
+            .and_then(|a| a.strip_suffix(']'))
+        {
+            check_assertion(assertion, &plan_dir, &output_dir, last_output.as_ref());
+        } else if let Some(spec) = cmd_clean.strip_prefix("editor ") {
+            fake_editor = install_fake_editor(spec, temp.path());
+        } else if cmd_clean.starts_with(">> forward ") {
             let parts: Vec<&str> = cmd_clean.split_whitespace().collect();
             if parts.len() == 4 {
                 let amount: i64 = parts[2].parse().expect("Invalid forward amount");
@@ -148,7 +342,10 @@ fn run_txtar_test(path: PathBuf) {
             let mut command = Command::new(plan_bin);
             command
                 .env("PLAN_DIR", &plan_dir)
-                .env("PLAN_MOCK_TIME", mock_date.format("%Y-%m-%d").to_string());
+                .env("PLAN_MOCK_TIME", mock_date.format("%Y-%m-%d").to_string())
+                .env("XDG_STATE_HOME", &state_dir)
+                .env("XDG_DATA_HOME", &data_dir)
+                .env("XDG_CACHE_HOME", &cache_dir);
 
             let mut has_visual = false;
             let mut has_editor = false;
@@ -163,17 +360,36 @@ fn run_txtar_test(path: PathBuf) {
             }
 
             if !has_visual {
-                command.env("VISUAL", "cat");
+                command.env("VISUAL", fake_editor.as_deref().unwrap_or("cat"));
             }
             if !has_editor {
                 command.env("EDITOR", "cat");
             }
 
             for arg in args_iter {
-                command.arg(arg);
+                command.arg(arg.replace("$PLAN_DIR", &plan_dir.to_string_lossy()));
             }
 
-            let output = command.output().expect("Failed to execute command");
+            let output = match pending_stdin.take() {
+                Some(input) => {
+                    use std::io::Write;
+                    let mut child = command
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .spawn()
+                        .expect("Failed to execute command");
+                    child
+                        .stdin
+                        .take()
+                        .unwrap()
+                        .write_all(input.as_bytes())
+                        .unwrap();
+                    child.wait_with_output()
+                }
+                None => command.output(),
+            }
+            .expect("Failed to execute command");
 
             if expects_error && output.status.success() {
                 panic!("Command expected to fail but succeeded: {}", cmd);
@@ -194,23 +410,27 @@ fn run_txtar_test(path: PathBuf) {
                 )
                 .unwrap();
             }
+            let stdout_str = String::from_utf8_lossy(&output.stdout);
+            let sanitized_stdout =
+                stdout_str.replace(&plan_dir.to_string_lossy().to_string(), "$PLAN_DIR");
             if !output.stdout.is_empty() {
                 fs::write(
                     output_dir.join(format!("cmd_{}_stdout.txt", executed_cmd_index)),
-                    &output.stdout,
+                    sanitized_stdout.as_bytes(),
                 )
                 .unwrap();
             }
+            let stderr_str = String::from_utf8_lossy(&output.stderr);
+            let sanitized_stderr =
+                stderr_str.replace(&plan_dir.to_string_lossy().to_string(), "$PLAN_DIR");
             if !output.stderr.is_empty() {
-                let stderr_str = String::from_utf8_lossy(&output.stderr);
-                let sanitized_stderr =
-                    stderr_str.replace(&plan_dir.to_string_lossy().to_string(), "$PLAN_DIR");
                 fs::write(
                     output_dir.join(format!("cmd_{}_stderr.txt", executed_cmd_index)),
                     sanitized_stderr.as_bytes(),
                 )
                 .unwrap();
             }
+            last_output = Some((executed_cmd_index, sanitized_stdout, sanitized_stderr));
             executed_cmd_index += 1;
         } else if let Some(stripped) = cmd_clean.strip_prefix("echo ") {
             let is_append = cmd_clean.contains(">>");
@@ -227,6 +447,7 @@ fn run_txtar_test(path: PathBuf) {
                 {
                     content = &content[1..content.len() - 1];
                 }
+                let content = content.replace("$PLAN_DIR", &plan_dir.to_string_lossy());
                 let file_path = plan_dir.join(parts[1].trim());
 
                 if is_append {
@@ -268,11 +489,26 @@ fn run_txtar_test(path: PathBuf) {
         } else if let Some(stripped) = cmd_clean.strip_prefix("mkdir ") {
             let dir_path = plan_dir.join(stripped.trim());
             fs::create_dir_all(dir_path).unwrap();
+        } else if let Some(stripped) = cmd_clean.strip_prefix("touch ") {
+            let (name, when) = stripped
+                .trim()
+                .split_once(' ')
+                .unwrap_or_else(|| panic!("touch needs a file and a time: {}", cmd));
+            let file = fs::File::options()
+                .write(true)
+                .open(plan_dir.join(name))
+                .unwrap_or_else(|e| panic!("touch {}: {}", name, e));
+            file.set_modified(touch_time(when.trim())).unwrap();
         } else {
             panic!("Unsupported txtar command natively: {}", cmd);
         }
     }
 
+    assert!(
+        pending_stdin.is_none(),
+        "stdin directive without a plan command after it"
+    );
+
     if env::var("UPDATE_GOLDEN").is_ok() {
         write_txtar_file(&path, &test.commands, &plan_dir, &output_dir);
         return;
@@ -366,7 +602,89 @@ txtar_test!(
     test_warn_disabled_config,
     "tests/data/warn_disabled_config.txtar"
 );
+txtar_test!(test_ignore_config, "tests/data/ignore_config.txtar");
+txtar_test!(test_search_engine, "tests/data/search_engine.txtar");
+txtar_test!(test_state_migration, "tests/data/state_migration.txtar");
+txtar_test!(test_date_suggestions, "tests/data/date_suggestions.txtar");
+txtar_test!(test_stats, "tests/data/stats.txtar");
+txtar_test!(test_config_edit, "tests/data/config_edit.txtar");
+txtar_test!(test_cat, "tests/data/cat.txtar");
+txtar_test!(test_first_open_hook, "tests/data/first_open_hook.txtar");
+txtar_test!(test_section_editing, "tests/data/section_editing.txtar");
+txtar_test!(test_replace, "tests/data/replace.txtar");
+txtar_test!(test_named_dates, "tests/data/named_dates.txtar");
+txtar_test!(test_log_section, "tests/data/log_section.txtar");
+txtar_test!(test_from_anchor, "tests/data/from_anchor.txtar");
+txtar_test!(test_task_priorities, "tests/data/task_priorities.txtar");
+txtar_test!(test_tags, "tests/data/tags.txtar");
+txtar_test!(test_upcoming, "tests/data/upcoming.txtar");
+txtar_test!(test_recurring, "tests/data/recurring.txtar");
+txtar_test!(test_events, "tests/data/events.txtar");
+txtar_test!(test_colors, "tests/data/colors.txtar");
+txtar_test!(test_grep_today, "tests/data/grep_today.txtar");
+txtar_test!(test_log_once, "tests/data/log_once.txtar");
+txtar_test!(test_cron_tick, "tests/data/cron_tick.txtar");
+txtar_test!(test_recent, "tests/data/recent.txtar");
+txtar_test!(test_search_index, "tests/data/search_index.txtar");
+txtar_test!(test_continue, "tests/data/continue.txtar");
+txtar_test!(test_highlight, "tests/data/highlight.txtar");
+txtar_test!(test_ls_window, "tests/data/ls_window.txtar");
+txtar_test!(test_plan_root, "tests/data/plan_root.txtar");
+txtar_test!(test_warnings, "tests/data/warnings.txtar");
+#[cfg(feature = "natural-dates")]
+txtar_test!(
+    test_natural_date_parser,
+    "tests/data/natural_date_parser.txtar"
+);
+txtar_test!(test_scratch, "tests/data/scratch.txtar");
+txtar_test!(test_export, "tests/data/export.txtar");
+txtar_test!(test_annotations, "tests/data/annotations.txtar");
+txtar_test!(test_nag, "tests/data/nag.txtar");
+txtar_test!(test_todotxt, "tests/data/todotxt.txtar");
+txtar_test!(test_json_schema, "tests/data/json_schema.txtar");
+txtar_test!(test_obsidian, "tests/data/obsidian.txtar");
+txtar_test!(test_show_stats, "tests/data/show_stats.txtar");
+txtar_test!(test_resolve, "tests/data/resolve.txtar");
+txtar_test!(test_self_test, "tests/data/self_test.txtar");
+txtar_test!(test_backups, "tests/data/backups.txtar");
+txtar_test!(test_undo, "tests/data/undo.txtar");
+txtar_test!(test_template, "tests/data/template.txtar");
+txtar_test!(test_check, "tests/data/check.txtar");
+txtar_test!(test_search_counts, "tests/data/search_counts.txtar");
+txtar_test!(test_search_case, "tests/data/search_case.txtar");
+txtar_test!(test_show_section, "tests/data/show_section.txtar");
+txtar_test!(test_inbox, "tests/data/inbox.txtar");
+txtar_test!(test_sweep, "tests/data/sweep.txtar");
+txtar_test!(test_timestamps, "tests/data/timestamps.txtar");
+txtar_test!(test_standup, "tests/data/standup.txtar");
+txtar_test!(test_rollup, "tests/data/rollup.txtar");
+txtar_test!(test_onthisday, "tests/data/onthisday.txtar");
+txtar_test!(test_lint, "tests/data/lint.txtar");
+txtar_test!(test_porcelain, "tests/data/porcelain.txtar");
+txtar_test!(test_local_config, "tests/data/local_config.txtar");
+txtar_test!(test_extra_dirs, "tests/data/extra_dirs.txtar");
+txtar_test!(test_jot_clipboard, "tests/data/jot_clipboard.txtar");
+txtar_test!(test_attach, "tests/data/attach.txtar");
+txtar_test!(test_attach_open, "tests/data/attach_open.txtar");
+txtar_test!(test_backlinks, "tests/data/backlinks.txtar");
+txtar_test!(test_aliases, "tests/data/aliases.txtar");
+txtar_test!(test_complete_dates, "tests/data/complete_dates.txtar");
+txtar_test!(test_path_subcommands, "tests/data/path_subcommands.txtar");
+txtar_test!(test_show_pretty, "tests/data/show_pretty.txtar");
+txtar_test!(test_ls_group_by, "tests/data/ls_group_by.txtar");
+txtar_test!(test_cal, "tests/data/cal.txtar");
+txtar_test!(test_gaps, "tests/data/gaps.txtar");
+txtar_test!(test_backfill, "tests/data/backfill.txtar");
+txtar_test!(test_stdin_directive, "tests/data/stdin_directive.txtar");
+txtar_test!(test_clock_skew, "tests/data/clock_skew.txtar");
+txtar_test!(test_scratch_expire, "tests/data/scratch_expire.txtar");
+txtar_test!(test_jot_block, "tests/data/jot_block.txtar");
+txtar_test!(test_log_batch, "tests/data/log_batch.txtar");
+txtar_test!(test_review, "tests/data/review.txtar");
 txtar_test!(
-    test_ignore_config,
-    "tests/data/ignore_config.txtar"
+    test_review_end_of_input,
+    "tests/data/review_end_of_input.txtar"
 );
+txtar_test!(test_assertions, "tests/data/assertions.txtar");
+#[cfg(unix)]
+txtar_test!(test_fake_editor, "tests/data/fake_editor.txtar");
@@ -0,0 +1,28 @@
+//! Setup shared by the test binaries that run `plan` directly, for the
+//! scenarios a txtar script can't express.
+
+use std::path::Path;
+use std::process::Command;
+
+/// `plan` on the txtar tests' mock date, reading and writing `plan_dir`.
+/// Config, state, data, and cache live under `home`, so a test never touches
+/// the real XDG directories.
+pub fn plan_in(home: &Path, plan_dir: &Path) -> Command {
+    let mut command = Command::new(assert_cmd::cargo::cargo_bin!("plan"));
+    command
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .env("XDG_CONFIG_HOME", home.join("xdg"))
+        .env("XDG_STATE_HOME", home.join("state"))
+        .env("XDG_DATA_HOME", home.join("data"))
+        .env("XDG_CACHE_HOME", home.join("cache"))
+        .env("NO_COLOR", "1");
+    command
+}
+
+/// [`plan_in`] with everything under `plan_dir` itself; scans skip
+/// directories, so the XDG ones don't show up as stray files.
+#[allow(dead_code)]
+pub fn plan(plan_dir: &Path) -> Command {
+    plan_in(plan_dir, plan_dir)
+}
@@ -1,6 +1,7 @@
 #![cfg(feature = "test-clock")]
 
 use assert_cmd::Command;
+use plan::config::LockConfig;
 use std::fs;
 use std::thread;
 use tempfile::TempDir;
@@ -51,3 +52,150 @@ fn test_concurrent_inserts() {
         num_threads, tasks_found, content
     );
 }
+
+#[test]
+fn test_parallel_reads_keep_date_order() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let plan_dir = temp.path();
+    let start = chrono::NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+    let dates: Vec<_> = (0..200).map(|i| start + chrono::Days::new(i)).collect();
+    for (i, day) in dates.iter().enumerate() {
+        let lines = "* filler\n".repeat(i % 7);
+        fs::write(
+            plan_dir.join(format!("{}.plan", day.format("%Y-%m-%d"))),
+            format!("{}* needle {}\n", lines, day),
+        )
+        .unwrap();
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .args(["search", "needle"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let expected: Vec<String> = dates
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, day)| {
+            format!(
+                "{}.plan:{}: * needle {}",
+                day.format("%Y-%m-%d"),
+                i % 7 + 1,
+                day
+            )
+        })
+        .collect();
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), expected);
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .arg("ls")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let listed: Vec<&str> = stdout.lines().map(|l| &l[..10]).collect();
+    let expected: Vec<String> = dates
+        .iter()
+        .rev()
+        .take(30)
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .collect();
+    assert_eq!(listed, expected);
+}
+
+#[test]
+fn test_lock_contention_fails_with_lock_path() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let plan_dir = temp.path();
+    let plan_path = plan_dir.join("2026-02-19.plan");
+    fs::write(&plan_path, "").unwrap();
+    fs::create_dir_all(plan_dir.join("_xdg/plan")).unwrap();
+    fs::write(plan_dir.join("_xdg/plan/config"), "lock.timeout = 0.2s\n").unwrap();
+    let held = plan::file::acquire_lock(&plan_path, &LockConfig::default()).unwrap();
+
+    let plan = |args: &[&str]| {
+        let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+            .env("PLAN_DIR", plan_dir)
+            .env("PLAN_MOCK_TIME", "2026-02-19")
+            .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+            .env("XDG_STATE_HOME", plan_dir.join("_state"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let lock_path = plan_dir.join("2026-02-19.lock").display().to_string();
+
+    let no_wait = LockConfig {
+        timeout: std::time::Duration::ZERO,
+    };
+    assert!(matches!(
+        plan::file::acquire_lock(&plan_path, &no_wait),
+        Err(plan::Error::LockContended { waited, .. }) if waited.is_zero()
+    ));
+
+    let stderr = plan(&["--no-wait", "log", "blocked"]);
+    assert!(stderr.contains(&format!("{} is locked by another process", lock_path)));
+
+    let stderr = plan(&["log", "blocked"]);
+    assert!(stderr.contains(&format!("gave up after 0.2s waiting for {}", lock_path)));
+
+    drop(held);
+    assert!(!fs::read_to_string(&plan_path).unwrap().contains("blocked"));
+}
+
+#[test]
+fn test_concurrent_indexed_searches_share_one_index() {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    let plan_dir = temp.path().join("plan_files");
+    fs::create_dir_all(plan_dir.join("_xdg/plan")).unwrap();
+    fs::write(plan_dir.join("_xdg/plan/config"), "search.engine = index\n").unwrap();
+    for day in 1..=28 {
+        fs::write(
+            plan_dir.join(format!("2026-02-{:02}.plan", day)),
+            format!("2026, Feb {:02}\nneedle {}\nhay\n", day, day),
+        )
+        .unwrap();
+    }
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let plan_dir = plan_dir.clone();
+            thread::spawn(move || {
+                let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+                    .env("PLAN_DIR", &plan_dir)
+                    .env("PLAN_MOCK_TIME", "2026-02-19")
+                    .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+                    .env("XDG_STATE_HOME", plan_dir.join("_state"))
+                    .env("XDG_CACHE_HOME", plan_dir.join("_cache"))
+                    .args(["search", "needle"])
+                    .assert()
+                    .success()
+                    .get_output()
+                    .stdout
+                    .clone();
+                String::from_utf8(output).unwrap()
+            })
+        })
+        .collect();
+    let outputs: Vec<String> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    assert_eq!(outputs[0].lines().count(), 28);
+    assert!(outputs.iter().all(|o| *o == outputs[0]));
+
+    // Exactly one index, and it is whole
+    let index_dir = plan_dir.join("_cache/plan/index");
+    let indexes: Vec<_> = fs::read_dir(&index_dir)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+        .collect();
+    assert_eq!(indexes.len(), 1);
+    let index: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(indexes[0].path()).unwrap()).unwrap();
+    assert_eq!(index["files"].as_object().unwrap().len(), 28);
+}
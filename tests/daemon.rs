@@ -0,0 +1,254 @@
+#![cfg(all(unix, feature = "test-clock"))]
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Command, Stdio};
+use tempfile::TempDir;
+
+/// The daemon, killed on drop so a failed assertion doesn't leave it running.
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn start(plan_dir: &std::path::Path, socket: &std::path::Path) -> Daemon {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .arg("daemon")
+        .arg("--socket")
+        .arg(socket)
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+        .env("XDG_STATE_HOME", plan_dir.join("_state"))
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The banner is printed once the socket accepts connections
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    assert!(banner.starts_with("plan: listening on"), "{}", banner);
+    Daemon(child)
+}
+
+#[test]
+fn test_daemon_appends_to_inbox() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    let socket = plan_dir.join("_run/plan.sock");
+    let _daemon = start(plan_dir, &socket);
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    stream
+        .write_all(b"log buy milk\njot a note\nlog   \nedit today\n")
+        .unwrap();
+    let replies: Vec<String> = BufReader::new(stream.try_clone().unwrap())
+        .lines()
+        .take(4)
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        replies,
+        [
+            "ok 2026-02-19.plan",
+            "ok 2026-02-19.plan",
+            "error nothing to add",
+            "error unknown command 'edit'; use log or jot",
+        ]
+    );
+    let content = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert!(content.contains("* buy milk\na note\n"), "{}", content);
+
+    // A second daemon on the same socket is refused
+    let second = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .arg("daemon")
+        .arg("--socket")
+        .arg(&socket)
+        .env("PLAN_DIR", plan_dir)
+        .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+        .env("XDG_STATE_HOME", plan_dir.join("_state"))
+        .output()
+        .unwrap();
+    assert!(!second.status.success());
+    assert!(String::from_utf8_lossy(&second.stderr).contains("already listening"));
+}
+
+#[test]
+fn test_daemon_replaces_stale_socket() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    let socket = plan_dir.join("plan.sock");
+    // A socket file nobody listens on, as a killed daemon leaves behind
+    drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+
+    let _daemon = start(plan_dir, &socket);
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    stream.write_all(b"jot after restart\n").unwrap();
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).unwrap();
+    assert_eq!(reply, "ok 2026-02-19.plan\n");
+}
+
+#[test]
+fn test_daemon_socket_is_private_and_writes_undoable() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    let socket = plan_dir.join("_run/plan.sock");
+    let _daemon = start(plan_dir, &socket);
+    let mode = fs::metadata(&socket).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    // Nothing is left over from binding
+    assert_eq!(fs::read_dir(plan_dir.join("_run")).unwrap().count(), 1);
+
+    let mut stream = UnixStream::connect(&socket).unwrap();
+    stream.write_all(b"log first\nlog second\n").unwrap();
+    let replies = BufReader::new(stream.try_clone().unwrap())
+        .lines()
+        .take(2)
+        .count();
+    assert_eq!(replies, 2);
+
+    let undo = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .arg("undo")
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+        .env("XDG_STATE_HOME", plan_dir.join("_state"))
+        .output()
+        .unwrap();
+    assert!(undo.status.success(), "{:?}", undo);
+    let content = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert!(content.contains("* first\n"), "{}", content);
+    assert!(!content.contains("second"), "{}", content);
+}
+
+fn plan(plan_dir: &std::path::Path) -> Command {
+    let mut cmd = Command::new(assert_cmd::cargo::cargo_bin!("plan"));
+    cmd.env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+        .env("XDG_STATE_HOME", plan_dir.join("_state"))
+        .env("XDG_RUNTIME_DIR", plan_dir.join("_run"));
+    cmd
+}
+
+/// Where the CLI looks for the daemon with `XDG_RUNTIME_DIR` set as above.
+fn default_socket(plan_dir: &std::path::Path) -> std::path::PathBuf {
+    let key = plan::paths::dir_key(plan_dir);
+    plan_dir.join("_run/plan").join(format!("{}.sock", key))
+}
+
+/// Answer one client with `greeting` and "ok" to its next line, returning
+/// the lines it sent.
+fn fake_daemon(socket: &std::path::Path, greeting: String) -> std::thread::JoinHandle<Vec<String>> {
+    fs::create_dir_all(socket.parent().unwrap()).unwrap();
+    let listener = std::os::unix::net::UnixListener::bind(socket).unwrap();
+    std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut replies = stream.try_clone().unwrap();
+        let mut seen = Vec::new();
+        for line in BufReader::new(stream).lines() {
+            let line = line.unwrap();
+            let reply = if line == "hello" {
+                greeting.clone()
+            } else {
+                "ok 2026-02-19.plan".to_string()
+            };
+            seen.push(line);
+            if writeln!(replies, "{}", reply).is_err() {
+                break;
+            }
+        }
+        seen
+    })
+}
+
+#[test]
+fn test_cli_delegates_plain_capture_to_daemon() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    assert!(
+        plan(plan_dir)
+            .args(["jot", "seed"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    let before = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+
+    let key = plan::paths::dir_key(plan_dir);
+    let daemon = fake_daemon(&default_socket(plan_dir), format!("ok plan {}", key));
+    assert!(
+        plan(plan_dir)
+            .args(["log", "buy milk"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    assert_eq!(daemon.join().unwrap(), ["hello", "log buy milk"]);
+    // The daemon did the write, not the CLI
+    let after = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert_eq!(before, after);
+}
+
+#[test]
+fn test_cli_ignores_daemon_for_another_dir() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    assert!(
+        plan(plan_dir)
+            .args(["jot", "seed"])
+            .status()
+            .unwrap()
+            .success()
+    );
+
+    let daemon = fake_daemon(
+        &default_socket(plan_dir),
+        "ok plan 0000000000000000".to_string(),
+    );
+    assert!(
+        plan(plan_dir)
+            .args(["log", "buy milk"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    assert_eq!(daemon.join().unwrap(), ["hello"]);
+    let content = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert!(content.contains("* buy milk\n"), "{}", content);
+}
+
+#[test]
+fn test_cli_writes_through_running_daemon() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    assert!(
+        plan(plan_dir)
+            .args(["jot", "seed"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    let _daemon = start(plan_dir, &default_socket(plan_dir));
+
+    let out = plan(plan_dir).args(["log", "buy milk"]).output().unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    // Anything beyond a plain one-line capture is still written directly
+    let out = plan(plan_dir)
+        .args(["log", "-p", "A", "call bank"])
+        .output()
+        .unwrap();
+    assert!(out.status.success(), "{:?}", out);
+    let content = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert!(content.contains("* buy milk\n"), "{}", content);
+    assert!(content.contains("* (A) call bank\n"), "{}", content);
+}
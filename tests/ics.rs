@@ -0,0 +1,111 @@
+use chrono::NaiveDate;
+use plan::export::PlanFile;
+use plan::ics;
+
+fn day(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+fn plan_file(date: &str, content: &str) -> PlanFile {
+    PlanFile {
+        name: format!("{}.plan", date),
+        date: day(date),
+        content: content.to_string(),
+    }
+}
+
+#[test]
+fn test_ics_due_tasks_and_events() {
+    let files = [plan_file(
+        "2026-02-19",
+        "\
+2026, Feb 19 - Thursday
+~~~~~~~~~inbox~~~~~~~~~
+* (A) file taxes, finally due:2026-04-15
+* no due date
+* call the bank due:2026-02-19
++ done already due:2026-02-20
+@ 09:30 standup; daily
+@ all-day offsite
+~~~~~~~~~~~~~~~~~~~~~~~
+
+---
+--- :sh
+@ 10:00 not an event
+",
+    )];
+    let out = ics::to_ics(&files);
+    assert!(out.split_inclusive('\n').all(|l| l.ends_with("\r\n")));
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "BEGIN:VCALENDAR",
+            "VERSION:2.0",
+            "PRODID:-//plan//plan export//EN",
+            "BEGIN:VTODO",
+            "UID:20260219-todo-d8fcfb99d505f175@plan",
+            "DTSTAMP:20260219T000000Z",
+            "DTSTART;VALUE=DATE:20260219",
+            "DUE;VALUE=DATE:20260415",
+            "SUMMARY:file taxes\\, finally",
+            "PRIORITY:1",
+            "STATUS:NEEDS-ACTION",
+            "END:VTODO",
+            // Due the day it was written, so there is no start
+            "BEGIN:VTODO",
+            "UID:20260219-todo-dca5154e0308dd8c@plan",
+            "DTSTAMP:20260219T000000Z",
+            "DUE;VALUE=DATE:20260219",
+            "SUMMARY:call the bank",
+            "STATUS:NEEDS-ACTION",
+            "END:VTODO",
+            "BEGIN:VEVENT",
+            "UID:20260219-event-ca7b97bcf492fe84@plan",
+            "DTSTAMP:20260219T000000Z",
+            "DTSTART:20260219T093000",
+            "SUMMARY:standup\\; daily",
+            "END:VEVENT",
+            "BEGIN:VEVENT",
+            "UID:20260219-event-5d6d7a1129b66caf@plan",
+            "DTSTAMP:20260219T000000Z",
+            "DTSTART;VALUE=DATE:20260219",
+            "SUMMARY:all-day offsite",
+            "END:VEVENT",
+            "END:VCALENDAR",
+        ]
+    );
+    // Exporting again gives the same UIDs and bytes
+    assert_eq!(ics::to_ics(&files), out);
+}
+
+#[test]
+fn test_ics_uids_follow_text_not_lines() {
+    let uids = |content: &str| -> Vec<String> {
+        ics::to_ics(&[plan_file("2026-02-19", content)])
+            .lines()
+            .filter(|l| l.starts_with("UID:"))
+            .map(str::to_string)
+            .collect()
+    };
+    let before = uids("* pay rent due:2026-03-01\n@ 09:00 standup\n@ 09:00 standup\n");
+    let after = uids("* new first\n* pay rent due:2026-03-02\n@ 09:30 standup\n@ 09:00 standup\n");
+    // Lines moved and the due date and time changed; the entries are the same
+    assert_eq!(before, after);
+    // The repeated event still gets its own UID
+    assert_eq!(before.len(), 3);
+    assert_ne!(before[1], before[2]);
+}
+
+#[test]
+fn test_ics_folds_long_lines() {
+    let text = "word ".repeat(40);
+    let files = [plan_file(
+        "2026-02-19",
+        &format!("@ 08:00 {}\n", text.trim()),
+    )];
+    let out = ics::to_ics(&files);
+    assert!(out.split("\r\n").all(|l| l.len() <= 75));
+    let unfolded = out.replace("\r\n ", "");
+    assert!(unfolded.contains(&format!("SUMMARY:{}\r\n", text.trim())));
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "test-clock")]
+
+use assert_cmd::Command;
+use serde_json::{Value, json};
+use std::fs;
+use tempfile::TempDir;
+
+/// Run `plan mcp` over `messages` and return its replies, one per line.
+fn session(plan_dir: &std::path::Path, messages: &[Value]) -> Vec<Value> {
+    let input: String = messages.iter().map(|m| format!("{}\n", m)).collect();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .env("PLAN_DIR", plan_dir)
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .arg("mcp")
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+fn call(id: u64, name: &str, arguments: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": "tools/call",
+        "params": { "name": name, "arguments": arguments },
+    })
+}
+
+fn text(reply: &Value) -> &str {
+    reply["result"]["content"][0]["text"].as_str().unwrap()
+}
+
+#[test]
+fn test_mcp_tools() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+
+    let replies = session(
+        plan_dir,
+        &[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" }),
+            call(
+                3,
+                "append_inbox",
+                json!({ "text": "* buy milk\n\n@ 14:00 dentist" }),
+            ),
+            call(4, "get_plan", json!({})),
+            call(5, "search", json!({ "query": "MILK" })),
+            call(6, "get_plan", json!({ "date": "2000-01-01" })),
+            call(
+                7,
+                "append_inbox",
+                json!({ "text": "late", "date": "2000-01-01" }),
+            ),
+            call(8, "search", json!({})),
+            call(9, "delete_everything", json!({})),
+            json!({ "jsonrpc": "2.0", "id": 10, "method": "resources/list" }),
+        ],
+    );
+    // The notification gets no reply
+    assert_eq!(replies.len(), 10);
+
+    assert_eq!(replies[0]["id"], 1);
+    assert_eq!(replies[0]["result"]["serverInfo"]["name"], "plan");
+    let tools: Vec<&str> = replies[1]["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(tools, ["get_plan", "append_inbox", "search"]);
+
+    assert_eq!(text(&replies[2]), "Added 2 line(s) to 2026-02-19.plan");
+    assert_eq!(replies[2]["result"]["isError"], false);
+    let content = fs::read_to_string(plan_dir.join("2026-02-19.plan")).unwrap();
+    assert!(content.contains("* buy milk\n@ 14:00 dentist\n"));
+    assert_eq!(text(&replies[3]), content);
+    assert_eq!(text(&replies[4]), "2026-02-19.plan:3: * buy milk\n");
+
+    // Past days are neither invented nor created
+    assert_eq!(text(&replies[5]), "No plan file for 2000-01-01");
+    assert_eq!(replies[5]["result"]["isError"], true);
+    assert_eq!(replies[6]["result"]["isError"], true);
+    assert!(!plan_dir.join("2000-01-01.plan").exists());
+    assert_eq!(text(&replies[7]), "Missing string argument 'query'");
+
+    assert_eq!(replies[8]["error"]["code"], -32602);
+    assert_eq!(replies[9]["error"]["code"], -32601);
+    assert_eq!(replies[9]["id"], 10);
+}
+
+#[test]
+fn test_mcp_reports_malformed_messages() {
+    let temp = TempDir::new().unwrap();
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .env("PLAN_DIR", temp.path())
+        .env("PLAN_MOCK_TIME", "2026-02-19")
+        .arg("mcp")
+        .write_stdin("{not json\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let reply: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(reply["error"]["code"], -32700);
+    assert_eq!(reply["id"], Value::Null);
+}
@@ -0,0 +1,87 @@
+#![cfg(all(unix, feature = "test-clock"))]
+
+mod common;
+
+use assert_cmd::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn write_script(path: &Path, body: &str, mode: u32) {
+    fs::write(path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+fn plan(root: &Path) -> Command {
+    let path = std::env::join_paths(
+        std::iter::once(root.join("bin"))
+            .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    let mut command = common::plan_in(root, &root.join("plan"));
+    command.env("PATH", path);
+    command
+}
+
+#[test]
+fn test_unknown_subcommand_runs_plugin() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("bin")).unwrap();
+    fs::create_dir_all(root.join("plan")).unwrap();
+    fs::create_dir_all(root.join("other")).unwrap();
+    write_script(
+        &root.join("bin/plan-hello"),
+        r#"echo "dir=$PLAN_DIR"
+echo "config=$PLAN_CONFIG"
+echo "date=$PLAN_DATE"
+echo "args=$*"
+exit 4"#,
+        0o755,
+    );
+    write_script(&root.join("bin/plan-hidden"), "echo nope", 0o644);
+
+    let out = plan(root)
+        .args(["hello", "--loud", "world"])
+        .assert()
+        .code(4)
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "dir={}\nconfig={}\ndate=2026-02-19\nargs=--loud world\n",
+            root.join("plan").display(),
+            root.join("xdg/plan/config").display()
+        )
+    );
+
+    // plan's own options come before the plugin's name
+    let out = plan(root)
+        .args(["--dir"])
+        .arg(root.join("other"))
+        .args(["--from", "yesterday", "hello"])
+        .assert()
+        .code(4)
+        .get_output()
+        .stdout
+        .clone();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains(&format!("dir={}\n", root.join("other").display())));
+    assert!(out.contains("date=2026-02-18\n"));
+
+    // Built-in commands win, and files that aren't executable are skipped
+    write_script(&root.join("bin/plan-ls"), "echo shadowed", 0o755);
+    let out = plan(root)
+        .arg("ls")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert!(!String::from_utf8(out).unwrap().contains("shadowed"));
+    plan(root).arg("hidden").assert().failure();
+}
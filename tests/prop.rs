@@ -1,4 +1,6 @@
+use plan::config::LockConfig;
 use plan::file;
+use plan::layout::Layout;
 use proptest::prelude::*;
 use std::fs;
 use tempfile::TempDir;
@@ -33,8 +35,8 @@ proptest! {
         fs::write(&file_path, &mock_body).unwrap();
 
         // Attempt to insert
-        let lock = file::acquire_lock(&file_path).unwrap();
-        let res = file::insert_into_inbox(&file_path, &format!("* {}", mock_task), &lock);
+        let lock = file::acquire_lock(&file_path, &LockConfig::default()).unwrap();
+        let res = file::insert_into_inbox(&Layout::Plan, &file_path, &format!("* {}", mock_task), &lock);
 
         // The result should either succeed natively or return an OS Error if the fs hits weird limits.
         // But what it absolutely MUST NOT do is panic (unless missing box bounds and exiting cleanly,
@@ -98,4 +100,178 @@ proptest! {
             assert!(res.is_err());
         }
     }
+
+    #[test]
+    fn test_suggest_date_always_parses(ref s in ".*") {
+        // A suggestion is only useful if accepting it actually works
+        if let Some(suggestion) = plan::date::suggest_date(s) {
+            assert!(plan::date::parse_date_opt(Some(&suggestion)).is_ok());
+            assert_ne!(suggestion, s.trim().to_lowercase());
+        }
+    }
+
+    #[test]
+    fn test_suggest_date_keeps_negative_sign(prefix in "@?~?", n in 0u32..1000, suffix in "( dyas ago)?") {
+        // "-3" asks for the other side of today; "@~3" would be wrong
+        let input = format!("{}-{}{}", prefix, n, suffix);
+        if let Some(suggestion) = plan::date::suggest_date(&input) {
+            assert_ne!(suggestion, format!("@~{}", n));
+            assert_ne!(suggestion, format!("{} days ago", n));
+        }
+    }
+
+    #[test]
+    fn test_section_spans_in_bounds(ref body in "(~{0,5}[a-z]{0,6}~{0,5}\n){0,20}") {
+        let line_count = body.lines().count();
+        let mut last_open = None;
+        for span in plan::section::parse_sections(body) {
+            assert!(span.open < line_count);
+            if let Some(close) = span.close {
+                assert!(close > span.open && close < line_count);
+            }
+            assert!(last_open < Some(span.open), "sections out of order");
+            last_open = Some(span.open);
+        }
+    }
+
+    #[test]
+    fn test_task_priority_roundtrip(letter in "[A-Z]", ref text in "[^\n]{0,40}") {
+        let p = plan::task::Priority::parse(&letter).unwrap();
+        let line = format!("* {} {}", p, text);
+        let task = plan::task::parse_task(&line, 1).unwrap();
+        assert_eq!(task.priority, Some(p));
+        assert_eq!(task.text, text.trim_start());
+    }
+
+    #[test]
+    fn test_event_roundtrip(h in 0u32..24, m in 0u32..60, ref text in "[a-z][a-z ]{0,30}") {
+        let time = chrono::NaiveTime::from_hms_opt(h, m, 0);
+        let line = plan::task::format_event(time, text);
+        assert_eq!(plan::task::entry_kind(&line), plan::task::EntryKind::Event);
+        let event = plan::task::parse_event(&line, 1).unwrap();
+        assert_eq!(event.time, time);
+        assert_eq!(&event.text, text);
+    }
+
+    #[test]
+    fn test_fuzzy_subsequence_always_matches(ref candidate in "[a-zA-Z0-9 -]{0,30}", ref mask in proptest::collection::vec(any::<bool>(), 30)) {
+        let query: String = candidate
+            .chars()
+            .zip(mask.iter())
+            .filter(|(_, keep)| **keep)
+            .map(|(c, _)| c)
+            .collect();
+        assert!(plan::search::fuzzy_score(&query, candidate).is_some());
+        assert!(plan::search::fuzzy_score(&format!("{}~", query), candidate).is_none());
+    }
+
+    #[test]
+    fn test_index_never_filters_out_a_match(ref line in "[a-zA-Z0-9 #*]{0,40}", start in 0usize..40, len in 0usize..40) {
+        let chars: Vec<char> = line.chars().collect();
+        let start = start.min(chars.len());
+        let end = (start + len).min(chars.len());
+        let query: String = chars[start..end].iter().collect::<String>().to_uppercase();
+        let content = format!("first line\n{}\nlast line\n", line);
+        let have = plan::index::trigrams(&content);
+        assert!(plan::index::trigrams(&query).is_subset(&have));
+    }
+
+    #[test]
+    fn test_crlf_files_stay_crlf(ref existing in proptest::collection::vec("[a-z][a-z ]{0,15}", 0..5), ref new_line in "[a-z][a-z ]{0,15}", ref section in "inbox|work") {
+        let temp = TempDir::new().unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 2, 19).unwrap();
+        let mut lf = plan::date::generate_template(&Layout::Plan, None, date);
+        for line in existing {
+            lf.push_str(&format!("* {}\n", line));
+        }
+        let lf_path = temp.path().join("lf.plan");
+        let crlf_path = temp.path().join("crlf.plan");
+        fs::write(&lf_path, &lf).unwrap();
+        fs::write(&crlf_path, lf.replace('\n', "\r\n")).unwrap();
+
+        for path in [&lf_path, &crlf_path] {
+            let lock = file::acquire_lock(path, &LockConfig::default()).unwrap();
+            file::insert_into_section(&Layout::Plan, path, section, &format!("* {}", new_line), &lock).unwrap();
+        }
+
+        let lf_after = fs::read_to_string(&lf_path).unwrap();
+        let crlf_after = fs::read_to_string(&crlf_path).unwrap();
+        assert!(!lf_after.contains('\r'));
+        assert_eq!(crlf_after, lf_after.replace('\n', "\r\n"));
+    }
+
+    #[test]
+    fn test_format_size_fits_a_column(bytes in any::<u64>()) {
+        let size = plan::opener::format_size(bytes);
+        assert!(size.len() <= 9, "{} is too wide", size);
+        let (number, unit) = size.split_once(' ').unwrap();
+        assert!(number.parse::<f64>().is_ok());
+        assert!(["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"].contains(&unit));
+    }
+
+    #[test]
+    fn test_attach_finds_every_reference(ref names in proptest::collection::vec("[a-z0-9_-]{1,8}\\.pdf", 1..5)) {
+        let mut expected: Vec<String> = Vec::new();
+        let mut content = String::new();
+        for name in names {
+            let relative = format!("assets/2026-02-19-{}", name);
+            let line = plan::attach::reference(std::path::Path::new(name), &relative);
+            content.push_str(&format!("see {} later\n", line));
+            if !expected.contains(&relative) {
+                expected.push(relative);
+            }
+        }
+        assert_eq!(plan::attach::referenced(&content), expected);
+    }
+
+    #[test]
+    fn test_model_round_trips_bytes(ref lines in proptest::collection::vec(prop_oneof![
+        "[a-z* +\\\\-]{0,12}",
+        Just("~~~~inbox~~~~".to_string()),
+        Just("~~~work~~~".to_string()),
+        Just("~~~~~~~~".to_string()),
+        Just("--- :sh".to_string()),
+        Just("---".to_string()),
+    ], 0..15), ref endings in proptest::collection::vec(prop_oneof![Just("\n"), Just("\r\n")], 15), trailing in any::<bool>()) {
+        let mut content: String = lines
+            .iter()
+            .zip(endings)
+            .map(|(l, e)| format!("{}{}", l, e))
+            .collect();
+        if !trailing && content.ends_with('\n') {
+            content.pop();
+            if content.ends_with('\r') {
+                content.pop();
+            }
+        }
+        let plan = plan::model::Plan::parse(&content);
+        assert_eq!(plan.to_string(), content);
+
+        let spans = plan::section::parse_sections(&content);
+        let names: Vec<&str> = plan.sections().map(|s| s.name.as_str()).collect();
+        let span_names: Vec<&str> = spans.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, span_names);
+        assert_eq!(plan.tasks(), plan::task::parse_tasks(&content));
+    }
+
+    #[test]
+    fn test_model_push_matches_insert_into_section(ref existing in proptest::collection::vec("[a-z][a-z ]{0,15}", 0..5), ref new_line in "[a-z][a-z ]{0,15}", crlf in any::<bool>()) {
+        let temp = TempDir::new().unwrap();
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 2, 19).unwrap();
+        let mut content = plan::date::generate_template(&Layout::Plan, None, date);
+        for line in existing {
+            content.push_str(&format!("{}\n", line));
+        }
+        if crlf {
+            content = content.replace('\n', "\r\n");
+        }
+        let path = temp.path().join("2026-02-19.plan");
+        fs::write(&path, &content).unwrap();
+        let lock = file::acquire_lock(&path, &LockConfig::default()).unwrap();
+        file::insert_into_inbox(&Layout::Plan, &path, &format!("* {}", new_line), &lock).unwrap();
+
+        let mut plan = plan::model::Plan::parse(&content);
+        plan.section_mut("inbox").unwrap().push(format!("* {}", new_line));
+        assert_eq!(plan.to_string(), fs::read_to_string(&path).unwrap());
+    }
 }
@@ -0,0 +1,37 @@
+#![cfg(feature = "test-clock")]
+
+mod common;
+
+use assert_cmd::prelude::*;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[test]
+fn test_unwritable_directory_is_not_read_only() {
+    let temp = TempDir::new().unwrap();
+    // The XDG directories stay writable, outside the plan directory
+    let dir = &temp.path().join("plan");
+    fs::create_dir(dir).unwrap();
+    let plan = || common::plan_in(temp.path(), dir);
+    plan().args(["log", "read me"]).assert().success();
+    fs::remove_file(dir.join("2026-02-19.lock")).unwrap();
+
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o555)).unwrap();
+    // Permissions don't stop root; there is nothing to test then
+    if fs::write(dir.join("probe"), "").is_ok() {
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+        return;
+    }
+
+    // Only a read-only filesystem is reported as one; a directory the user
+    // can't write to is a permissions error like any other
+    let log = plan().args(["log", "write me"]).output().unwrap();
+    assert!(!log.status.success());
+    let stderr = String::from_utf8_lossy(&log.stderr);
+    assert!(!stderr.contains("is read-only"));
+    assert!(stderr.contains("Permission denied"));
+    assert!(!dir.join("2026-02-19.lock").exists());
+
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o755)).unwrap();
+}
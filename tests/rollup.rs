@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+use plan::rollup::{self, Highlight};
+
+fn day(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn test_highlight_candidates_marked_and_top_priority() {
+    let content = "\
+2026, Feb 19 - Thursday
+~~~~~~~~~~~inbox~~~~~~~~~~~
+* (A) still open
+~~~~~~~~~~~~~~~~~~~~~~~~~~~
+
+---
+! shipped the importer
++ (A) cut the release (2026-02-19)
++ (B) tidy docs
+just a note
+--- :sh
+! not prose
+---
+";
+    let mut candidates = rollup::highlight_candidates(day("2026-02-19"), content);
+    candidates.insert(
+        0,
+        Highlight {
+            date: day("2026-02-16"),
+            text: "+ (A) earlier win".to_string(),
+            score: 1,
+        },
+    );
+    rollup::rank(&mut candidates);
+    let texts: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(
+        texts,
+        [
+            "shipped the importer",
+            "+ (A) earlier win",
+            "+ (A) cut the release (2026-02-19)",
+        ]
+    );
+}
+
+#[test]
+fn test_curate_answers_and_defaults() {
+    let candidates = |texts: &[(&str, u8)]| -> Vec<Highlight> {
+        texts
+            .iter()
+            .map(|(t, score)| Highlight {
+                date: day("2026-02-19"),
+                text: t.to_string(),
+                score: *score,
+            })
+            .collect()
+    };
+    let list = candidates(&[("a", 2), ("b", 1), ("c", 1), ("d", 2), ("e", 1)]);
+
+    // Explicit answers, an empty answer taking the default, then quitting
+    let mut output = Vec::new();
+    let kept = rollup::curate(list.clone(), &b"n\ny\nwhat\n\nq\n"[..], &mut output).unwrap();
+    let kept: Vec<&str> = kept.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(kept, ["b", "d"]);
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("[1/5] 2026-02-19  a"));
+    assert!(output.contains("Please answer y, n, or q."));
+
+    // Running out of input keeps only marked lines
+    let kept = rollup::curate(list, &b""[..], Vec::new()).unwrap();
+    let kept: Vec<&str> = kept.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(kept, ["a", "d"]);
+}
+
+#[test]
+fn test_period_bounds_and_names() {
+    use rollup::Period;
+
+    let sunday = day("2026-03-01");
+    assert_eq!(
+        Period::Week.bounds(sunday),
+        (day("2026-02-23"), day("2026-03-01"))
+    );
+    assert_eq!(Period::Week.stem(sunday), "2026-W09");
+    assert_eq!(
+        Period::Month.bounds(day("2026-02-19")),
+        (day("2026-02-01"), day("2026-02-28"))
+    );
+    assert_eq!(Period::Month.stem(day("2026-02-19")), "2026-02");
+    // ISO weeks can belong to the neighbouring year
+    assert_eq!(Period::Week.stem(day("2027-01-01")), "2026-W53");
+}
@@ -0,0 +1,192 @@
+#![cfg(feature = "serve")]
+
+use plan::config::Config;
+use plan::serve;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use tempfile::TempDir;
+
+#[test]
+fn test_serve_routes() {
+    let temp = TempDir::new().unwrap();
+    let cfg = Config::with_defaults(temp.path().to_path_buf());
+
+    let res = serve::handle(
+        &cfg,
+        "POST",
+        "/plans/2099-01-01/inbox",
+        "* buy milk\n\ncall mom\n",
+    );
+    assert_eq!(res.status, 201, "{}", res.body);
+    assert_eq!(res.body, r#"{"added":2,"file":"2099-01-01.plan"}"#);
+
+    let res = serve::handle(&cfg, "GET", "/plans/2099-01-01", "");
+    assert_eq!(res.status, 200);
+    assert!(res.body.contains("~inbox~"));
+    assert!(res.body.contains("* buy milk\ncall mom\n~"));
+
+    let res = serve::handle(&cfg, "GET", "/plans", "");
+    assert_eq!(res.status, 200);
+    let listing: serde_json::Value = serde_json::from_str(&res.body).unwrap();
+    assert_eq!(listing["days"][0]["date"], "2099-01-01");
+    assert_eq!(listing["days"][0]["open"], 1);
+
+    // Past days aren't created on demand
+    let res = serve::handle(&cfg, "POST", "/plans/2000-01-01/inbox", "late\n");
+    assert_eq!(res.status, 404);
+    assert!(!cfg.dir.join("2000-01-01.plan").exists());
+
+    assert_eq!(
+        serve::handle(&cfg, "GET", "/plans/2000-01-01", "").status,
+        404
+    );
+    assert_eq!(serve::handle(&cfg, "GET", "/plans/someday", "").status, 400);
+    assert_eq!(
+        serve::handle(&cfg, "DELETE", "/plans/today", "").status,
+        405
+    );
+    assert_eq!(
+        serve::handle(&cfg, "POST", "/plans/today/inbox", " \n").status,
+        400
+    );
+    assert_eq!(serve::handle(&cfg, "GET", "/nope", "").status, 404);
+
+    let page = serve::handle(&cfg, "GET", "/", "");
+    assert_eq!(page.content_type, "text/html; charset=utf-8");
+    assert!(page.body.contains("/plans/today/inbox"));
+}
+
+#[test]
+fn test_serve_without_token_only_answers_localhost() {
+    for host in ["127.0.0.1:8080", "localhost", "LOCALHOST:80", "[::1]:8080"] {
+        assert_eq!(
+            serve::check_local("GET", Some(host), None),
+            None,
+            "{}",
+            host
+        );
+    }
+    for host in [Some("evil.example:8080"), Some("192.168.1.5:8080"), None] {
+        let refusal = serve::check_local("GET", host, None).unwrap();
+        assert_eq!(refusal.status, 403, "{:?}", host);
+    }
+
+    // A form POST from another site is a CORS simple request
+    let refusal =
+        serve::check_local("POST", Some("127.0.0.1:8080"), Some("https://evil.example")).unwrap();
+    assert_eq!(refusal.status, 403);
+    assert_eq!(
+        serve::check_local(
+            "POST",
+            Some("127.0.0.1:8080"),
+            Some("http://127.0.0.1:8080")
+        ),
+        None
+    );
+    assert_eq!(
+        serve::check_local("POST", Some("localhost:8080"), None),
+        None
+    );
+}
+
+/// Send one request and return the status code and body.
+fn request(addr: &str, head: &str, body: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{}\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        head,
+        addr,
+        body.len(),
+        body
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+    (status, body)
+}
+
+/// Start `plan serve` on a free port and return it with its address.
+fn spawn_server(plan_dir: &Path) -> (Child, String) {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("plan"))
+        .args(["serve", "--addr", "127.0.0.1:0"])
+        .env("PLAN_DIR", plan_dir)
+        .env("XDG_CONFIG_HOME", plan_dir.join("_xdg"))
+        .env("XDG_STATE_HOME", plan_dir.join("_state"))
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut banner = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut banner)
+        .unwrap();
+    let addr = banner.trim().rsplit("http://").next().unwrap().to_string();
+    (child, addr)
+}
+
+#[test]
+fn test_serve_requires_configured_token() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    fs::create_dir_all(plan_dir.join("_xdg/plan")).unwrap();
+    fs::write(plan_dir.join("_xdg/plan/config"), "serve.token = s3cret\n").unwrap();
+    let (mut child, addr) = spawn_server(plan_dir);
+
+    let (status, _) = request(&addr, "POST /plans/2099-01-01/inbox HTTP/1.1", "note");
+    assert_eq!(status, 401);
+    assert!(!plan_dir.join("2099-01-01.plan").exists());
+
+    let (status, body) = request(
+        &addr,
+        "POST /plans/2099-01-01/inbox HTTP/1.1\r\nAuthorization: Bearer s3cret",
+        "note",
+    );
+    assert_eq!(status, 201, "{}", body);
+    assert!(
+        fs::read_to_string(plan_dir.join("2099-01-01.plan"))
+            .unwrap()
+            .contains("note\n")
+    );
+
+    // The capture page needs no token
+    let (status, _) = request(&addr, "GET / HTTP/1.1", "");
+    assert_eq!(status, 200);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn test_serve_without_token_refuses_cross_origin_writes() {
+    let temp = TempDir::new().unwrap();
+    let plan_dir = temp.path();
+    fs::create_dir_all(plan_dir.join("_xdg/plan")).unwrap();
+    let (mut child, addr) = spawn_server(plan_dir);
+
+    let (status, _) = request(
+        &addr,
+        "POST /plans/2099-01-01/inbox HTTP/1.1\r\nOrigin: https://evil.example",
+        "note",
+    );
+    assert_eq!(status, 403);
+    assert!(!plan_dir.join("2099-01-01.plan").exists());
+
+    // The capture page posts from its own origin
+    let (status, body) = request(
+        &addr,
+        &format!(
+            "POST /plans/2099-01-01/inbox HTTP/1.1\r\nOrigin: http://{}",
+            addr
+        ),
+        "note",
+    );
+    assert_eq!(status, 201, "{}", body);
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
@@ -0,0 +1,229 @@
+#![cfg(feature = "test-clock")]
+
+mod common;
+
+use assert_cmd::prelude::*;
+use plan::sync::{self, Action};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process;
+use tempfile::TempDir;
+
+fn files(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(n, c)| (n.to_string(), c.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_reconcile() {
+    let local = files(&[
+        ("same.plan", "a\n"),
+        ("new-here.plan", "n\n"),
+        ("edited-here.plan", "local\n"),
+        ("edited-there.plan", "old\n"),
+        ("both.plan", "mine\n"),
+        ("never-synced.plan", "mine\n"),
+    ]);
+    let remote = files(&[
+        ("same.plan", "a\n"),
+        ("new-there.plan", "n\n"),
+        ("edited-here.plan", "old\n"),
+        ("edited-there.plan", "remote\n"),
+        ("both.plan", "theirs\n"),
+        ("never-synced.plan", "theirs\n"),
+    ]);
+    // The last sync left every shared file reading "old"
+    let base: BTreeMap<String, u64> = ["edited-here.plan", "edited-there.plan", "both.plan"]
+        .iter()
+        .map(|n| (n.to_string(), sync::content_hash("old\n")))
+        .collect();
+
+    assert_eq!(
+        sync::reconcile(&local, &remote, &base),
+        [
+            Action::Conflict("both.plan".to_string()),
+            Action::Push("edited-here.plan".to_string()),
+            Action::Pull("edited-there.plan".to_string()),
+            Action::Conflict("never-synced.plan".to_string()),
+            Action::Push("new-here.plan".to_string()),
+            Action::Pull("new-there.plan".to_string()),
+        ]
+    );
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?}", args);
+}
+
+/// `plan` in `dir`, configured to sync with git.
+fn plan(root: &Path, dir: &Path) -> process::Command {
+    let mut command = common::plan_in(root, dir);
+    command
+        .env("PLAN_MOCK_TIME", "2026-02-19T10:15")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .env("GIT_AUTHOR_NAME", "plan")
+        .env("GIT_AUTHOR_EMAIL", "plan@example.com")
+        .env("GIT_COMMITTER_NAME", "plan")
+        .env("GIT_COMMITTER_EMAIL", "plan@example.com");
+    command
+}
+
+fn sync_output(root: &Path, dir: &Path, args: &[&str]) -> String {
+    let output = plan(root, dir).arg("sync").args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_git_sync_pulls_pushes_and_keeps_conflicts() {
+    if process::Command::new("git")
+        .arg("--version")
+        .output()
+        .is_err()
+    {
+        return;
+    }
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    fs::create_dir_all(root.join("xdg/plan")).unwrap();
+    fs::write(root.join("xdg/plan/config"), "[sync]\nbackend = git\n").unwrap();
+    let remote = root.join("remote.git");
+    let (a, b) = (root.join("a"), root.join("b"));
+    git(
+        root,
+        &["init", "--quiet", "--bare", "-b", "main", "remote.git"],
+    );
+    for dir in [&a, &b] {
+        git(
+            root,
+            &[
+                "clone",
+                "--quiet",
+                remote.to_str().unwrap(),
+                dir.to_str().unwrap(),
+            ],
+        );
+        git(dir, &["symbolic-ref", "HEAD", "refs/heads/main"]);
+    }
+    let file = "2026-02-19.plan";
+
+    plan(root, &a).args(["log", "from a"]).assert().success();
+    assert_eq!(sync_output(root, &a, &[]), format!("push      {}\n", file));
+    assert_eq!(sync_output(root, &b, &[]), format!("pull      {}\n", file));
+    assert_eq!(
+        fs::read_to_string(a.join(file)).unwrap(),
+        fs::read_to_string(b.join(file)).unwrap()
+    );
+    assert_eq!(sync_output(root, &b, &[]), "Already in sync.\n");
+
+    // Both sides change the same line
+    for (dir, text) in [(&a, "edited in a"), (&b, "edited in b")] {
+        let content = fs::read_to_string(dir.join(file)).unwrap();
+        fs::write(dir.join(file), content.replace("* from a", text)).unwrap();
+    }
+    sync_output(root, &a, &[]);
+    let copy = "2026-02-19.sync-conflict-20260219-101500-remote.plan";
+    assert_eq!(
+        sync_output(root, &b, &["--dry-run"]),
+        format!("conflict  {} (remote copy: {})\n", file, copy)
+    );
+    assert!(!b.join(copy).exists());
+    assert_eq!(
+        sync_output(root, &b, &[]),
+        format!("conflict  {} (remote copy: {})\n", file, copy)
+    );
+    assert!(
+        fs::read_to_string(b.join(file))
+            .unwrap()
+            .contains("edited in b")
+    );
+    assert!(
+        fs::read_to_string(b.join(copy))
+            .unwrap()
+            .contains("edited in a")
+    );
+
+    // a gets b's side; the conflict copy stays local to b
+    assert_eq!(sync_output(root, &a, &[]), format!("pull      {}\n", file));
+    assert!(
+        fs::read_to_string(a.join(file))
+            .unwrap()
+            .contains("edited in b")
+    );
+    assert!(!a.join(copy).exists());
+}
+
+#[test]
+fn test_sync_needs_backend_and_respects_offline() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    let dir = root.join("plan");
+    fs::create_dir_all(&dir).unwrap();
+
+    let output = plan(root, &dir).arg("sync").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("set sync.backend"));
+
+    let output = plan(root, &dir)
+        .args(["--offline", "sync"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "plan: offline; not syncing\n"
+    );
+}
+
+#[test]
+fn test_sync_refuses_remote_that_looks_like_an_option() {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    let dir = root.join("plan");
+    fs::create_dir_all(&dir).unwrap();
+    fs::create_dir_all(root.join("xdg/plan")).unwrap();
+    fs::write(
+        root.join("xdg/plan/config"),
+        "[sync]\nbackend = rsync\nremote = --rsh=touch pwned\n",
+    )
+    .unwrap();
+
+    let output = plan(root, &dir).arg("sync").output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("sync.remote must not start with '-'"),
+        "{}",
+        stderr
+    );
+    assert!(!dir.join("pwned").exists());
+
+    // Checking the config after editing it points at the key
+    let output = plan(root, &dir)
+        .args(["config", "edit"])
+        .env("VISUAL", "true")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("'sync.remote' must not start with '-'"),
+        "{}",
+        stderr
+    );
+}
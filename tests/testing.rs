@@ -0,0 +1,73 @@
+#![cfg(feature = "testing")]
+
+use chrono::NaiveDate;
+use plan::config::LockConfig;
+use plan::layout::Layout;
+use plan::testing::{FixedClock, PlanDir, PlanDoc};
+
+fn day(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+#[test]
+fn test_fixed_clock_nests_and_restores() {
+    let outer = FixedClock::on(day("2026-02-19"));
+    assert_eq!(plan::date::today(), day("2026-02-19"));
+    {
+        let _inner = FixedClock::at(day("2030-01-01").and_hms_opt(9, 30, 0).unwrap());
+        assert_eq!(
+            plan::date::now().format("%F %R").to_string(),
+            "2030-01-01 09:30"
+        );
+    }
+    assert_eq!(plan::date::today(), day("2026-02-19"));
+    drop(outer);
+}
+
+#[test]
+fn test_plan_doc_matches_written_files() {
+    let today = day("2026-02-19");
+    assert_eq!(
+        PlanDoc::new(today).build(),
+        plan::date::generate_template(&Layout::Plan, None, today)
+    );
+
+    let dir = PlanDir::new().unwrap();
+    let path = dir.write(today, &PlanDoc::new(today).build()).unwrap();
+    let lock = plan::file::acquire_lock(&path, &LockConfig::default()).unwrap();
+    plan::file::insert_into_inbox(&Layout::Plan, &path, "* call plumber", &lock).unwrap();
+    plan::file::insert_into_section(&Layout::Plan, &path, "work", "\\ write report", &lock)
+        .unwrap();
+    drop(lock);
+
+    let expected = PlanDoc::new(today)
+        .log("call plumber")
+        .section("work", &["\\ write report"])
+        .build();
+    assert_eq!(dir.read(today).unwrap(), expected);
+}
+
+#[test]
+fn test_plan_dir_scans_like_the_cli() {
+    let dir = PlanDir::new().unwrap();
+    let doc = PlanDoc::new(day("2026-02-18"))
+        .jot("loose note")
+        .line("\\ open task")
+        .code("sh", &["\\ not a task"]);
+    dir.write(day("2026-02-18"), &doc.build()).unwrap();
+    dir.write_file("notes.txt", "stray").unwrap();
+
+    let scan = dir.scan().unwrap();
+    assert_eq!(scan.plan_entries.len(), 1);
+    assert_eq!(scan.unexpected, vec!["notes.txt".to_string()]);
+    assert!(
+        plan::file::check_plan_root(&Layout::Plan, dir.path())
+            .unwrap()
+            .is_none()
+    );
+
+    let content = dir.read(day("2026-02-18")).unwrap();
+    let tasks = plan::task::parse_tasks(&content);
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].text, "open task");
+}
@@ -0,0 +1,164 @@
+#![cfg(feature = "tui")]
+
+use plan::config::Config;
+use plan::tui::{Action, App, PickAction, Picker};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use tempfile::TempDir;
+
+fn press(app: &mut App, code: KeyCode) -> Action {
+    app.handle_key(KeyEvent::from(code)).unwrap()
+}
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        press(app, KeyCode::Char(c));
+    }
+}
+
+fn screen(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn setup() -> TempDir {
+    let temp = TempDir::new().expect("Failed to create temp dir");
+    fs::write(
+        temp.path().join("2026-02-17.plan"),
+        "2026, Feb 17 - Tuesday\n~~~~~~~~~inbox~~~~~~~~~\n* call plumber\n~~~~~~~~~~~~~~~~~~~~~~~\n",
+    )
+    .unwrap();
+    fs::write(
+        temp.path().join("2026-02-18.plan"),
+        "\\ write report\n* renew passport\n",
+    )
+    .unwrap();
+    temp
+}
+
+#[test]
+fn test_tui_lists_and_previews_newest_first() {
+    let temp = setup();
+    let cfg = Config::with_defaults(temp.path().to_path_buf());
+    let mut app = App::load(&cfg).unwrap();
+
+    let before = screen(&app);
+    assert!(before.contains("2026-02-18 Wed"), "{}", before);
+    assert!(before.contains("2026-02-17 Tue"), "{}", before);
+    assert!(before.contains("write report"), "{}", before);
+
+    press(&mut app, KeyCode::Char('j'));
+    let after = screen(&app);
+    assert!(after.contains("call plumber"), "{}", after);
+    assert!(!after.contains("write report"), "{}", after);
+    assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+}
+
+#[test]
+fn test_tui_incremental_search_filters_dates() {
+    let temp = setup();
+    let cfg = Config::with_defaults(temp.path().to_path_buf());
+    let mut app = App::load(&cfg).unwrap();
+
+    press(&mut app, KeyCode::Char('/'));
+    type_text(&mut app, "plumb");
+    let filtered = screen(&app);
+    assert!(filtered.contains("/plumb"), "{}", filtered);
+    assert!(filtered.contains("2026-02-17 Tue"), "{}", filtered);
+    assert!(!filtered.contains("2026-02-18 Wed"), "{}", filtered);
+
+    // Enter keeps the filter; Esc in browse mode clears it before quitting
+    press(&mut app, KeyCode::Enter);
+    assert_eq!(press(&mut app, KeyCode::Esc), Action::Continue);
+    assert!(screen(&app).contains("2026-02-18 Wed"));
+}
+
+#[test]
+fn test_tui_log_and_complete_write_to_file() {
+    let temp = setup();
+    let path = temp.path().join("2026-02-17.plan");
+    let cfg = Config::with_defaults(temp.path().to_path_buf());
+    let mut app = App::load(&cfg).unwrap();
+    press(&mut app, KeyCode::Char('j'));
+
+    press(&mut app, KeyCode::Char('a'));
+    type_text(&mut app, "buy stamps");
+    press(&mut app, KeyCode::Enter);
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(
+        content.contains("* call plumber\n* buy stamps\n~"),
+        "{}",
+        content
+    );
+
+    press(&mut app, KeyCode::Char('c'));
+    press(&mut app, KeyCode::Char('j'));
+    press(&mut app, KeyCode::Enter);
+    let today = plan::date::today().format("%Y-%m-%d");
+    let content = fs::read_to_string(&path).unwrap();
+    assert!(
+        content.contains(&format!("* call plumber\n+ buy stamps ({})\n", today)),
+        "{}",
+        content
+    );
+    assert!(screen(&app).contains("Completed: buy stamps"));
+
+    assert_eq!(
+        press(&mut app, KeyCode::Char('e')),
+        Action::Edit(path.clone())
+    );
+}
+
+#[test]
+fn test_picker_fuzzy_filters_and_selects() {
+    let items = vec![
+        "2026-02-19  Thu  * call plumber".to_string(),
+        "2026-02-18  Wed  launch-day".to_string(),
+        "2026-01-30  Fri".to_string(),
+    ];
+    let mut picker = Picker::new(items);
+
+    for c in "lnch".chars() {
+        picker.handle_key(KeyEvent::from(KeyCode::Char(c)));
+    }
+    assert_eq!(
+        picker.handle_key(KeyEvent::from(KeyCode::Enter)),
+        PickAction::Select(1)
+    );
+
+    // Clear the query; arrow keys move through the unfiltered list
+    for _ in 0..4 {
+        picker.handle_key(KeyEvent::from(KeyCode::Backspace));
+    }
+    picker.handle_key(KeyEvent::from(KeyCode::Down));
+    picker.handle_key(KeyEvent::from(KeyCode::Down));
+    assert_eq!(
+        picker.handle_key(KeyEvent::from(KeyCode::Enter)),
+        PickAction::Select(2)
+    );
+
+    for c in "zzz".chars() {
+        picker.handle_key(KeyEvent::from(KeyCode::Char(c)));
+    }
+    assert_eq!(
+        picker.handle_key(KeyEvent::from(KeyCode::Enter)),
+        PickAction::Continue
+    );
+    assert_eq!(
+        picker.handle_key(KeyEvent::from(KeyCode::Esc)),
+        PickAction::Cancel
+    );
+}
@@ -0,0 +1,109 @@
+#![cfg(all(feature = "test-clock", feature = "watch"))]
+
+mod common;
+
+use common::plan;
+use plan::watch;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// A child process killed on drop, so a failed assertion doesn't leave it
+/// running.
+struct Killed(Child);
+
+impl Drop for Killed {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Spawn `command` and stream its stdout lines through a channel.
+fn follow(command: &mut Command) -> (Killed, mpsc::Receiver<String>) {
+    let mut child = command.stdout(Stdio::piped()).spawn().unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (tx, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if tx.send(line.unwrap()).is_err() {
+                break;
+            }
+        }
+    });
+    (Killed(child), lines)
+}
+
+/// Wait up to five seconds for a line containing `needle`.
+fn wait_for(lines: &mpsc::Receiver<String>, needle: &str) {
+    loop {
+        match lines.recv_timeout(Duration::from_secs(5)) {
+            Ok(line) if line.contains(needle) => return,
+            Ok(_) => {}
+            Err(_) => panic!("timed out waiting for {:?}", needle),
+        }
+    }
+}
+
+#[test]
+fn test_watch_reprints_on_change() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    assert!(plan(dir).args(["log", "first"]).status().unwrap().success());
+
+    let (_child, lines) = follow(plan(dir).arg("watch"));
+    wait_for(&lines, "* first");
+    assert!(
+        plan(dir)
+            .args(["log", "second"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    wait_for(&lines, "* second");
+}
+
+#[test]
+fn test_tail_follows_inbox() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path();
+    for task in ["first", "older"] {
+        assert!(plan(dir).args(["log", task]).status().unwrap().success());
+    }
+
+    let (_child, lines) = follow(plan(dir).args(["tail", "-n", "1"]));
+    wait_for(&lines, "* older");
+    assert!(
+        plan(dir)
+            .args(["jot", "second"])
+            .status()
+            .unwrap()
+            .success()
+    );
+    assert_eq!(
+        lines.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "second"
+    );
+    // A changed line shows up as it is now
+    assert!(plan(dir).args(["check", "1"]).status().unwrap().success());
+    assert!(
+        lines
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap()
+            .starts_with("+ first")
+    );
+    assert!(plan(dir).args(["jot", "third"]).status().unwrap().success());
+    assert_eq!(lines.recv_timeout(Duration::from_secs(5)).unwrap(), "third");
+}
+
+#[test]
+fn test_new_lines() {
+    let lines = |s: &str| s.lines().map(String::from).collect::<Vec<_>>();
+    let before = lines("a\nb\na\n");
+    let after = lines("a\nb\nc\na\na\n");
+    assert_eq!(watch::new_lines(&before, &after), vec!["c", "a"]);
+    assert!(watch::new_lines(&after, &before).is_empty());
+}